@@ -2,6 +2,35 @@ use getset::{Getters, Setters};
 use sea_orm::prelude::*;
 use serde::{Deserialize, Serialize};
 
+/// Whether the object of a file has been uploaded to its presigned URL
+#[derive(Clone, Copy, Debug, Eq, PartialEq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "camelCase")]
+pub enum FileStatus {
+    /// Waiting for the client to upload the object and confirm it
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "uploaded")]
+    Uploaded,
+}
+
+/// Who can download the object of a file
+#[derive(
+    Clone, Copy, Debug, Default, Eq, PartialEq, EnumIter, DeriveActiveEnum, Deserialize, Serialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "camelCase")]
+pub enum FileVisibility {
+    /// Readable by anyone from its public URL
+    #[default]
+    #[sea_orm(string_value = "public")]
+    Public,
+    /// Only downloadable through presigned URLs issued to API keys of its
+    /// namespace
+    #[sea_orm(string_value = "private")]
+    Private,
+}
+
 #[derive(
     Clone, Debug, Eq, PartialEq, DeriveEntityModel, Deserialize, Serialize, Getters, Setters,
 )]
@@ -15,10 +44,30 @@ pub struct Model {
     pub namespace: String,
     #[sea_orm(column_type = "Text")]
     pub storage_key: String,
+    pub status: FileStatus,
+    pub visibility: FileVisibility,
+    /// Content type and size in bytes declared when requesting the upload,
+    /// unknown for files uploaded before they were recorded
+    #[sea_orm(column_type = "Text")]
+    pub content_type: Option<String>,
+    pub content_length: Option<i64>,
+    /// Hex encoded SHA-256 of the object, when declared by the client
+    #[sea_orm(column_type = "Text")]
+    pub sha256: Option<String>,
+    /// Expiration of the presigned upload URL, pending files are removed once
+    /// it is reached
+    pub upload_expires_at: Option<DateTimeUtc>,
+    /// S3 multipart upload the object is uploaded through, until it is
+    /// completed
+    #[sea_orm(column_type = "Text")]
+    pub upload_id: Option<String>,
+    /// Media library folder, at the root when unset
+    pub folder_id: Option<i32>,
     pub tags: Vec<String>,
     pub metadata: Json,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
+    pub deleted_at: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,43 @@
+use getset::Getters;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A previous object of a file, kept when its content is replaced so that it
+/// can be restored
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel, Deserialize, Serialize, Getters)]
+#[sea_orm(table_name = "file_versions")]
+#[getset(get = "pub")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    pub file_id: i32,
+    #[sea_orm(column_type = "Text", unique)]
+    pub storage_key: String,
+    #[sea_orm(column_type = "Text")]
+    pub content_type: Option<String>,
+    pub content_length: Option<i64>,
+    #[sea_orm(column_type = "Text")]
+    pub sha256: Option<String>,
+    /// Date the object was replaced
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::file::Entity",
+        from = "Column::FileId",
+        to = "crate::file::Column::Id"
+    )]
+    File,
+}
+
+impl Related<crate::file::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::File.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,7 +1,23 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+/// Whether the renditions of an image are generated
+#[derive(Clone, Copy, Debug, Eq, PartialEq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "camelCase")]
+pub enum ImageStatus {
+    /// Renditions are being generated, the image may still have the ones
+    /// of its previous original
+    #[sea_orm(string_value = "processing")]
+    Processing,
+    #[sea_orm(string_value = "ready")]
+    Ready,
+    /// Renditions could not be generated, the image can be reprocessed
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
 #[sea_orm(table_name = "images")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -11,12 +27,50 @@ pub struct Model {
     pub namespace: String,
     #[sea_orm(column_type = "Text", unique)]
     pub storage_key: String,
+    /// Upload the renditions are generated from, unknown for images uploaded
+    /// before originals were kept
+    #[sea_orm(column_type = "Text", unique)]
+    pub original_storage_key: Option<String>,
     #[sea_orm(unique)]
     pub lazy_image_id: Option<i32>,
+    pub status: ImageStatus,
     #[sea_orm(column_type = "Text")]
     pub alt: Option<String>,
+    /// Media library folder, at the root when unset
+    pub folder_id: Option<i32>,
+    pub tags: Vec<String>,
+    pub metadata: Json,
+    /// Formats the image is stored in, the one of `storage_key` first
+    pub formats: Vec<String>,
+    /// Size in bytes of the image in all of its formats, unknown for images
+    /// processed before it was recorded
+    pub size: Option<i64>,
+    /// Dimensions of the stored image, unknown for images uploaded before
+    /// they were recorded
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// Dimensions of the image as uploaded
+    pub original_width: Option<i32>,
+    pub original_height: Option<i32>,
+    /// MIME type of the format the upload was decoded from
+    #[sea_orm(column_type = "Text")]
+    pub original_mime_type: Option<String>,
+    /// Size of the upload in bytes
+    pub original_size: Option<i64>,
+    /// EXIF orientation of the upload, applied before generating renditions
+    pub orientation: Option<i16>,
+    /// Most common color of the image, as `#rrggbb`
+    #[sea_orm(column_type = "Text")]
+    pub dominant_color: Option<String>,
+    /// Point to keep in view when cropping, from 0 to 1 along each axis
+    pub focal_x: Option<f64>,
+    pub focal_y: Option<f64>,
+    /// Compact placeholder rendered by clients while the image loads
+    #[sea_orm(column_type = "Text")]
+    pub blurhash: Option<String>,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
+    pub deleted_at: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
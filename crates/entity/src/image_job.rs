@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Generation of the renditions of an image from its original, queued on
+/// upload and retried until it completes or runs out of attempts
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "image_jobs")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    pub image_id: i32,
+    /// How renditions are generated, see `ImageJobOptions`
+    pub options: Json,
+    #[sea_orm(default_value = "0")]
+    pub attempts: i32,
+    #[sea_orm(column_type = "Text")]
+    pub last_error: Option<String>,
+    /// The job is not picked before this date, pushed back on each failure
+    pub run_at: DateTimeUtc,
+    /// Set while a worker processes the job
+    pub locked_at: Option<DateTimeUtc>,
+    pub completed_at: Option<DateTimeUtc>,
+    /// Set once the job ran out of attempts
+    pub failed_at: Option<DateTimeUtc>,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::image::Entity",
+        from = "Column::ImageId",
+        to = "crate::image::Column::Id"
+    )]
+    Image,
+}
+
+impl Related<crate::image::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Image.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
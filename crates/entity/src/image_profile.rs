@@ -0,0 +1,78 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How an image is resized to fit the variant dimensions
+#[derive(Clone, Copy, Debug, Eq, PartialEq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "camelCase")]
+pub enum ImageFit {
+    /// Fills the whole box, cropping what overflows
+    #[sea_orm(string_value = "cover")]
+    Cover,
+    /// Fits the whole image inside the box, keeping its aspect ratio
+    #[sea_orm(string_value = "contain")]
+    Contain,
+    /// Stretches the image to the box, ignoring its aspect ratio
+    #[sea_orm(string_value = "fill")]
+    Fill,
+}
+
+/// Encoding of a generated image
+#[derive(Clone, Copy, Debug, Eq, PartialEq, EnumIter, DeriveActiveEnum, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "camelCase")]
+pub enum ImageFormat {
+    #[sea_orm(string_value = "jpeg")]
+    Jpeg,
+    /// Lossless, used to keep the transparency of uploaded images
+    #[sea_orm(string_value = "png")]
+    Png,
+    #[sea_orm(string_value = "webp")]
+    Webp,
+    #[sea_orm(string_value = "avif")]
+    Avif,
+    /// Vector images, only stored as uploaded once sanitized
+    #[sea_orm(string_value = "svg")]
+    Svg,
+    /// Animated images, only stored as uploaded
+    #[sea_orm(string_value = "gif")]
+    Gif,
+}
+
+/// A named image variant generated on upload for every image of a namespace
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "image_profiles")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    #[sea_orm(column_type = "Text")]
+    pub namespace: String,
+    #[sea_orm(column_type = "Text")]
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    pub fit: ImageFit,
+    pub format: ImageFormat,
+    pub quality: i32,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::namespace::Entity",
+        from = "Column::Namespace",
+        to = "crate::namespace::Column::Name"
+    )]
+    Namespace,
+}
+
+impl Related<crate::namespace::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Namespace.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,37 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A transformation of an image generated on demand and cached in storage
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "image_transforms")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    pub image_id: i32,
+    #[sea_orm(column_type = "Text", unique)]
+    pub storage_key: String,
+    /// Size in bytes, unknown for transformations cached before it was
+    /// recorded
+    pub size: Option<i64>,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::image::Entity",
+        from = "Column::ImageId",
+        to = "crate::image::Column::Id"
+    )]
+    Image,
+}
+
+impl Related<crate::image::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Image.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,42 @@
+use crate::image_profile::ImageFormat;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A rendition of an image generated from an image profile
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "image_variants")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    pub image_id: i32,
+    #[sea_orm(column_type = "Text")]
+    pub name: String,
+    #[sea_orm(column_type = "Text", unique)]
+    pub storage_key: String,
+    pub width: i32,
+    pub height: i32,
+    pub format: ImageFormat,
+    /// Size in bytes, unknown for variants generated before it was recorded
+    pub size: Option<i64>,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::image::Entity",
+        from = "Column::ImageId",
+        to = "crate::image::Column::Id"
+    )]
+    Image,
+}
+
+impl Related<crate::image::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Image.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
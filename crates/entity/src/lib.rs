@@ -1,11 +1,20 @@
 pub mod api_key;
 pub mod blok;
 pub mod file;
+pub mod file_version;
 pub mod git_auth;
 pub mod image;
+pub mod image_job;
+pub mod image_profile;
+pub mod image_transform;
+pub mod image_transform_size;
+pub mod image_variant;
 pub mod locale;
 pub mod locale_data;
+pub mod media_folder;
+pub mod media_reference;
 pub mod namespace;
+pub mod object_deletion;
 pub mod page;
 pub mod post;
 pub mod quote;
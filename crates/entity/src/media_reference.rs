@@ -0,0 +1,71 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An image or a file used in the props of a blok or the body of a post,
+/// extracted whenever they are written
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel, Deserialize, Serialize)]
+#[sea_orm(table_name = "media_references")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    pub image_id: Option<i32>,
+    pub file_id: Option<i32>,
+    pub blok_id: Option<i32>,
+    pub post_id: Option<i32>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::image::Entity",
+        from = "Column::ImageId",
+        to = "crate::image::Column::Id"
+    )]
+    Image,
+    #[sea_orm(
+        belongs_to = "crate::file::Entity",
+        from = "Column::FileId",
+        to = "crate::file::Column::Id"
+    )]
+    File,
+    #[sea_orm(
+        belongs_to = "crate::blok::Entity",
+        from = "Column::BlokId",
+        to = "crate::blok::Column::Id"
+    )]
+    Blok,
+    #[sea_orm(
+        belongs_to = "crate::post::Entity",
+        from = "Column::PostId",
+        to = "crate::post::Column::Id"
+    )]
+    Post,
+}
+
+impl Related<crate::image::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Image.def()
+    }
+}
+
+impl Related<crate::file::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::File.def()
+    }
+}
+
+impl Related<crate::blok::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Blok.def()
+    }
+}
+
+impl Related<crate::post::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Post.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,28 @@
+use getset::Getters;
+use sea_orm::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An S3 object whose deletion failed and must be retried
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel, Deserialize, Serialize, Getters)]
+#[sea_orm(table_name = "object_deletions")]
+#[getset(get = "pub")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_deserializing)]
+    pub id: i32,
+    #[sea_orm(column_type = "Text")]
+    pub bucket: String,
+    #[sea_orm(column_type = "Text")]
+    pub storage_key: String,
+    #[sea_orm(default_value = "0")]
+    pub attempts: i32,
+    #[sea_orm(column_type = "Text")]
+    pub last_error: Option<String>,
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
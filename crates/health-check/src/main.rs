@@ -1,3 +1,5 @@
+// The fields are only read through Debug, when main returns the error
+#[allow(dead_code)]
 #[derive(Debug)]
 enum Error {
     ReqError(minreq::Error),
@@ -16,6 +16,25 @@ mod m20220917_000012_fix_blok_priority_trigger;
 mod m20221013_000013_create_locale_table;
 mod m20221023_000014_add_timestamps_to_locales_data;
 mod m20221103_000015_create_files_table;
+mod m20230115_000016_add_deleted_at_columns;
+mod m20230201_000017_create_object_deletions_table;
+mod m20230215_000018_create_image_profiles_tables;
+mod m20230301_000019_add_formats_to_images;
+mod m20230315_000020_add_dimensions_to_images;
+mod m20230401_000021_create_image_transforms_tables;
+mod m20230415_000022_add_blurhash_to_images;
+mod m20230501_000023_add_metadata_to_images;
+mod m20230515_000024_add_tags_to_images;
+mod m20230601_000025_create_image_jobs_table;
+mod m20230615_000026_add_upload_status_to_files;
+mod m20230701_000027_add_upload_id_to_files;
+mod m20230715_000028_add_visibility_to_files;
+mod m20230801_000029_create_file_versions_table;
+mod m20230815_000030_add_sha256_to_files;
+mod m20230901_000031_add_sizes_to_images;
+mod m20230915_000032_create_media_folders_table;
+mod m20231001_000033_create_media_references_table;
+mod m20231015_000034_exclude_trashed_bloks_from_priority;
 pub(crate) mod utils;
 
 pub struct Migrator;
@@ -39,6 +58,25 @@ impl MigratorTrait for Migrator {
             Box::new(m20221013_000013_create_locale_table::Migration),
             Box::new(m20221023_000014_add_timestamps_to_locales_data::Migration),
             Box::new(m20221103_000015_create_files_table::Migration),
+            Box::new(m20230115_000016_add_deleted_at_columns::Migration),
+            Box::new(m20230201_000017_create_object_deletions_table::Migration),
+            Box::new(m20230215_000018_create_image_profiles_tables::Migration),
+            Box::new(m20230301_000019_add_formats_to_images::Migration),
+            Box::new(m20230315_000020_add_dimensions_to_images::Migration),
+            Box::new(m20230401_000021_create_image_transforms_tables::Migration),
+            Box::new(m20230415_000022_add_blurhash_to_images::Migration),
+            Box::new(m20230501_000023_add_metadata_to_images::Migration),
+            Box::new(m20230515_000024_add_tags_to_images::Migration),
+            Box::new(m20230601_000025_create_image_jobs_table::Migration),
+            Box::new(m20230615_000026_add_upload_status_to_files::Migration),
+            Box::new(m20230701_000027_add_upload_id_to_files::Migration),
+            Box::new(m20230715_000028_add_visibility_to_files::Migration),
+            Box::new(m20230801_000029_create_file_versions_table::Migration),
+            Box::new(m20230815_000030_add_sha256_to_files::Migration),
+            Box::new(m20230901_000031_add_sizes_to_images::Migration),
+            Box::new(m20230915_000032_create_media_folders_table::Migration),
+            Box::new(m20231001_000033_create_media_references_table::Migration),
+            Box::new(m20231015_000034_exclude_trashed_bloks_from_priority::Migration),
         ]
     }
 }
@@ -0,0 +1,93 @@
+use crate::utils::macros::exec_stmt;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230115_000016_add_deleted_at_columns"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in ["pages", "bloks", "posts", "images", "files"] {
+            exec_stmt!(
+                manager,
+                r#"alter table {table}
+                    drop column if exists deleted_at,
+                    add column deleted_at timestamptz
+                "#
+            )?;
+            exec_stmt!(
+                manager,
+                r#"create index {table}__deleted_at__idx on {table} (deleted_at) where deleted_at is not null"#
+            )?;
+        }
+
+        // Trashed pages and posts must not prevent reusing their path / slug
+        exec_stmt!(
+            manager,
+            r#"drop index if exists page__namespace_path__uniq_idx"#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"create unique index page__namespace_path__uniq_idx on pages (namespace, path) where deleted_at is null"#
+        )?;
+
+        exec_stmt!(
+            manager,
+            r#"alter table posts drop constraint if exists posts_slug_key"#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"create unique index post__slug__uniq_idx on posts (slug) where deleted_at is null"#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Trashed rows would violate the restored constraints, they are not
+        // deleted along with the column so that no data is lost silently
+        for table in ["pages", "bloks", "posts", "images", "files"] {
+            exec_stmt!(
+                manager,
+                r#"
+        do $$
+          begin
+            if exists(select 1 from {table} where deleted_at is not null) then
+              raise exception 'Trashed {table} must be restored or purged before reverting this migration';
+            end if;
+          end;
+        $$
+      "#
+            )?;
+        }
+
+        exec_stmt!(manager, r#"drop index if exists post__slug__uniq_idx"#)?;
+        exec_stmt!(
+            manager,
+            r#"alter table posts add constraint posts_slug_key unique (slug)"#
+        )?;
+
+        exec_stmt!(
+            manager,
+            r#"drop index if exists page__namespace_path__uniq_idx"#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"create unique index page__namespace_path__uniq_idx on pages (namespace, path)"#
+        )?;
+
+        for table in ["pages", "bloks", "posts", "images", "files"] {
+            exec_stmt!(
+                manager,
+                r#"alter table {table} drop column if exists deleted_at"#
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use crate::utils::macros::{create_table_from_entity, exec_stmt};
+use entity::object_deletion::Entity;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230201_000017_create_object_deletions_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(manager, r#"drop table if exists object_deletions"#)?;
+        create_table_from_entity!(manager, Entity)?;
+
+        // Set default value for created_at / updated_at / attempts columns and
+        // adds constraint
+        exec_stmt!(
+            manager,
+            r#"alter table object_deletions
+                alter column created_at set default now(),
+                alter column updated_at set default now(),
+                alter column attempts set default 0,
+                add constraint object_deletions_bucket_storage_key_unique unique (bucket, storage_key)
+            "#
+        )?;
+
+        // Trigger for timestamps
+        exec_stmt!(
+            manager,
+            r#"create trigger _100_timestamps
+                before insert or update on object_deletions
+                for each row execute procedure tg__timestamps();
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Entity).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
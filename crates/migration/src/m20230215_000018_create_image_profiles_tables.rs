@@ -0,0 +1,87 @@
+use crate::utils::macros::{create_table_from_entity, exec_stmt};
+use entity::{image_profile::Entity as ProfileEntity, image_variant::Entity as VariantEntity};
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230215_000018_create_image_profiles_tables"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(manager, r#"drop table if exists image_variants"#)?;
+        exec_stmt!(manager, r#"drop table if exists image_profiles"#)?;
+        create_table_from_entity!(manager, ProfileEntity)?;
+        create_table_from_entity!(manager, VariantEntity)?;
+
+        // Set default value for created_at / updated_at columns and adds
+        // constraint
+        exec_stmt!(
+            manager,
+            r#"alter table image_profiles
+                alter column created_at set default now(),
+                alter column updated_at set default now(),
+                drop constraint if exists "fk-image_profiles-namespace",
+                add constraint "fk-image_profiles-namespace"
+                    foreign key (namespace)
+                    references namespaces (name)
+                    on update cascade
+                    on delete cascade,
+                add constraint "image_profiles-namespace-name-uniq-idx"
+                    unique (namespace, name)
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"alter table image_variants
+                alter column created_at set default now(),
+                alter column updated_at set default now(),
+                drop constraint if exists "fk-image_variants-image_id",
+                add constraint "fk-image_variants-image_id"
+                    foreign key (image_id)
+                    references images
+                    on update cascade
+                    on delete cascade
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"create index image_variants__image_id__idx on image_variants (image_id)"#
+        )?;
+
+        // Trigger for timestamps
+        for table in ["image_profiles", "image_variants"] {
+            exec_stmt!(
+                manager,
+                r#"create trigger _100_timestamps
+                    before insert or update on {table}
+                    for each row execute procedure tg__timestamps();
+                "#
+            )?;
+        }
+        exec_stmt!(
+            manager,
+            r#"create trigger _500_create_missing_namespace
+                before insert or update on image_profiles
+                for each row execute procedure public.tg__create_missing_namespace();
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(VariantEntity).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ProfileEntity).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
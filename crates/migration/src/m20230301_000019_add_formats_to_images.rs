@@ -0,0 +1,37 @@
+use crate::utils::macros::exec_stmt;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230301_000019_add_formats_to_images"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table images
+                drop column if exists formats,
+                add column formats text[] not null default '{{}}'::text[]
+            "#
+        )?;
+
+        // Images were always encoded to JPEG until now
+        exec_stmt!(manager, r#"update images set formats = '{{jpeg}}'::text[]"#)?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table images drop column if exists formats"#
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use crate::utils::macros::exec_stmt;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230315_000020_add_dimensions_to_images"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table images
+                drop column if exists width,
+                drop column if exists height,
+                drop column if exists original_width,
+                drop column if exists original_height,
+                drop column if exists focal_x,
+                drop column if exists focal_y,
+                add column width integer,
+                add column height integer,
+                add column original_width integer,
+                add column original_height integer,
+                add column focal_x double precision,
+                add column focal_y double precision
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table images
+                drop column if exists width,
+                drop column if exists height,
+                drop column if exists original_width,
+                drop column if exists original_height,
+                drop column if exists focal_x,
+                drop column if exists focal_y
+            "#
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use crate::utils::macros::{create_table_from_entity, exec_stmt};
+use entity::{
+    image_transform::Entity as TransformEntity, image_transform_size::Entity as SizeEntity,
+};
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230401_000021_create_image_transforms_tables"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(manager, r#"drop table if exists image_transforms"#)?;
+        exec_stmt!(manager, r#"drop table if exists image_transform_sizes"#)?;
+        create_table_from_entity!(manager, SizeEntity)?;
+        create_table_from_entity!(manager, TransformEntity)?;
+
+        // Set default value for created_at / updated_at columns and adds
+        // constraint
+        exec_stmt!(
+            manager,
+            r#"alter table image_transform_sizes
+                alter column created_at set default now(),
+                alter column updated_at set default now(),
+                drop constraint if exists "fk-image_transform_sizes-namespace",
+                add constraint "fk-image_transform_sizes-namespace"
+                    foreign key (namespace)
+                    references namespaces (name)
+                    on update cascade
+                    on delete cascade,
+                add constraint "image_transform_sizes-namespace-width-height-uniq-idx"
+                    unique (namespace, width, height)
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"alter table image_transforms
+                alter column created_at set default now(),
+                alter column updated_at set default now(),
+                drop constraint if exists "fk-image_transforms-image_id",
+                add constraint "fk-image_transforms-image_id"
+                    foreign key (image_id)
+                    references images
+                    on update cascade
+                    on delete cascade
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"create index image_transforms__image_id__idx on image_transforms (image_id)"#
+        )?;
+
+        // Trigger for timestamps
+        for table in ["image_transform_sizes", "image_transforms"] {
+            exec_stmt!(
+                manager,
+                r#"create trigger _100_timestamps
+                    before insert or update on {table}
+                    for each row execute procedure tg__timestamps();
+                "#
+            )?;
+        }
+        exec_stmt!(
+            manager,
+            r#"create trigger _500_create_missing_namespace
+                before insert or update on image_transform_sizes
+                for each row execute procedure public.tg__create_missing_namespace();
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TransformEntity).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(SizeEntity).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
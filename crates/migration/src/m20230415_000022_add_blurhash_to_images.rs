@@ -0,0 +1,34 @@
+use crate::utils::macros::exec_stmt;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230415_000022_add_blurhash_to_images"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table images
+                drop column if exists blurhash,
+                add column blurhash text
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table images drop column if exists blurhash"#
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use crate::utils::macros::exec_stmt;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230501_000023_add_metadata_to_images"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table images
+                drop column if exists original_mime_type,
+                drop column if exists original_size,
+                drop column if exists orientation,
+                drop column if exists dominant_color,
+                add column original_mime_type text,
+                add column original_size bigint,
+                add column orientation smallint,
+                add column dominant_color text
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table images
+                drop column if exists original_mime_type,
+                drop column if exists original_size,
+                drop column if exists orientation,
+                drop column if exists dominant_color
+            "#
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use crate::utils::macros::exec_stmt;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230515_000024_add_tags_to_images"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table images
+                drop column if exists tags,
+                drop column if exists metadata,
+                add column tags text[] not null default '{{}}'::text[],
+                add column metadata jsonb not null default '{{}}'::jsonb
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table images
+                drop column if exists tags,
+                drop column if exists metadata
+            "#
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use crate::utils::macros::{create_table_from_entity, exec_stmt};
+use entity::image_job::Entity;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230601_000025_create_image_jobs_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing images already have their renditions
+        exec_stmt!(
+            manager,
+            r#"alter table images
+                drop column if exists original_storage_key,
+                drop column if exists status,
+                add column original_storage_key text unique,
+                add column status text not null default 'ready'
+            "#
+        )?;
+
+        exec_stmt!(manager, r#"drop table if exists image_jobs"#)?;
+        create_table_from_entity!(manager, Entity)?;
+
+        // Set default value for created_at / updated_at / attempts / run_at
+        // columns and adds constraint
+        exec_stmt!(
+            manager,
+            r#"alter table image_jobs
+                alter column created_at set default now(),
+                alter column updated_at set default now(),
+                alter column attempts set default 0,
+                alter column run_at set default now(),
+                drop constraint if exists "fk-image_jobs-image_id",
+                add constraint "fk-image_jobs-image_id"
+                    foreign key (image_id)
+                    references images
+                    on update cascade
+                    on delete cascade
+            "#
+        )?;
+        // Workers only look for pending jobs
+        exec_stmt!(
+            manager,
+            r#"create index image_jobs__pending__idx on image_jobs (run_at)
+                where completed_at is null and failed_at is null
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"create index image_jobs__image_id__idx on image_jobs (image_id)"#
+        )?;
+
+        // Trigger for timestamps
+        exec_stmt!(
+            manager,
+            r#"create trigger _100_timestamps
+                before insert or update on image_jobs
+                for each row execute procedure tg__timestamps();
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Entity).to_owned())
+            .await?;
+        exec_stmt!(
+            manager,
+            r#"alter table images
+                drop column if exists original_storage_key,
+                drop column if exists status
+            "#
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use crate::utils::macros::exec_stmt;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230615_000026_add_upload_status_to_files"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing files cannot be checked anymore, they are considered
+        // uploaded
+        exec_stmt!(
+            manager,
+            r#"alter table files
+                drop column if exists status,
+                drop column if exists content_type,
+                drop column if exists content_length,
+                drop column if exists upload_expires_at,
+                add column status text not null default 'uploaded',
+                add column content_type text,
+                add column content_length bigint,
+                add column upload_expires_at timestamp with time zone
+            "#
+        )?;
+        // The cleanup job only looks for expired pending uploads
+        exec_stmt!(
+            manager,
+            r#"create index files__pending__idx on files (upload_expires_at)
+                where status = 'pending'
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(manager, r#"drop index if exists files__pending__idx"#)?;
+        exec_stmt!(
+            manager,
+            r#"alter table files
+                drop column if exists status,
+                drop column if exists content_type,
+                drop column if exists content_length,
+                drop column if exists upload_expires_at
+            "#
+        )?;
+
+        Ok(())
+    }
+}
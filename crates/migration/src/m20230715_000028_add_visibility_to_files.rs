@@ -0,0 +1,37 @@
+use crate::utils::macros::exec_stmt;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230715_000028_add_visibility_to_files"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing files were all uploaded as public
+        exec_stmt!(
+            manager,
+            r#"alter table files
+                drop column if exists visibility,
+                add column visibility text not null default 'public'
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table files
+                drop column if exists visibility
+            "#
+        )?;
+
+        Ok(())
+    }
+}
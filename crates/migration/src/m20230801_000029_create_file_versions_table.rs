@@ -0,0 +1,58 @@
+use crate::utils::macros::{create_table_from_entity, exec_stmt};
+use entity::file_version::Entity;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230801_000029_create_file_versions_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(manager, r#"drop table if exists file_versions"#)?;
+        create_table_from_entity!(manager, Entity)?;
+
+        // Set default value for created_at / updated_at columns and adds
+        // constraint
+        exec_stmt!(
+            manager,
+            r#"alter table file_versions
+                alter column created_at set default now(),
+                alter column updated_at set default now(),
+                drop constraint if exists "fk-file_versions-file_id",
+                add constraint "fk-file_versions-file_id"
+                    foreign key (file_id)
+                    references files
+                    on update cascade
+                    on delete cascade
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"create index file_versions__file_id__idx on file_versions (file_id)"#
+        )?;
+
+        // Trigger for timestamps
+        exec_stmt!(
+            manager,
+            r#"create trigger _100_timestamps
+                before insert or update on file_versions
+                for each row execute procedure tg__timestamps();
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Entity).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
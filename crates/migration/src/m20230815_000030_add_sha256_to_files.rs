@@ -0,0 +1,56 @@
+use crate::utils::macros::exec_stmt;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230815_000030_add_sha256_to_files"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table files
+                drop column if exists sha256,
+                add column sha256 text
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"alter table file_versions
+                drop column if exists sha256,
+                add column sha256 text
+            "#
+        )?;
+        // Duplicates are looked up per namespace
+        exec_stmt!(
+            manager,
+            r#"create index files__namespace_sha256__idx on files (namespace, sha256)
+                where sha256 is not null
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table file_versions
+                drop column if exists sha256
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"alter table files
+                drop column if exists sha256
+            "#
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use crate::utils::macros::exec_stmt;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230901_000031_add_sizes_to_images"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table images
+                drop column if exists size,
+                add column size bigint
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"alter table image_variants
+                drop column if exists size,
+                add column size bigint
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"alter table image_transforms
+                drop column if exists size,
+                add column size bigint
+            "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(
+            manager,
+            r#"alter table image_transforms
+                drop column if exists size
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"alter table image_variants
+                drop column if exists size
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"alter table images
+                drop column if exists size
+            "#
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use crate::utils::macros::{create_table_from_entity, exec_stmt};
+use entity::media_folder::Entity;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20230915_000032_create_media_folders_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(manager, r#"drop table if exists media_folders"#)?;
+        create_table_from_entity!(manager, Entity)?;
+
+        // Set default value for created_at / updated_at columns and adds
+        // constraints
+        exec_stmt!(
+            manager,
+            r#"alter table media_folders
+                alter column created_at set default now(),
+                alter column updated_at set default now(),
+                drop constraint if exists "fk-media_folders-namespace",
+                add constraint "fk-media_folders-namespace"
+                    foreign key (namespace)
+                    references namespaces (name)
+                    on update cascade
+                    on delete cascade,
+                drop constraint if exists "fk-media_folders-parent_id",
+                add constraint "fk-media_folders-parent_id"
+                    foreign key (parent_id)
+                    references media_folders
+                    on update cascade
+                    on delete cascade
+            "#
+        )?;
+        // Root folders have no parent, they are told apart by their name too
+        exec_stmt!(
+            manager,
+            r#"create unique index media_folders__namespace_parent_id_name__idx
+                on media_folders (namespace, coalesce(parent_id, 0), name)
+            "#
+        )?;
+
+        // Trigger for timestamps
+        exec_stmt!(
+            manager,
+            r#"create trigger _100_timestamps
+                before insert or update on media_folders
+                for each row execute procedure tg__timestamps();
+            "#
+        )?;
+        exec_stmt!(
+            manager,
+            r#"create trigger _500_create_missing_namespace
+                before insert or update on media_folders
+                for each row execute procedure public.tg__create_missing_namespace();
+            "#
+        )?;
+
+        // Items of a removed folder are moved to the root
+        for table in ["images", "files"] {
+            exec_stmt!(
+                manager,
+                r#"alter table {table}
+                    drop column if exists folder_id,
+                    add column folder_id integer
+                        references media_folders
+                        on update cascade
+                        on delete set null
+                "#
+            )?;
+            exec_stmt!(
+                manager,
+                r#"create index {table}__folder_id__idx on {table} (folder_id)"#
+            )?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in ["images", "files"] {
+            exec_stmt!(
+                manager,
+                r#"alter table {table}
+                    drop column if exists folder_id
+                "#
+            )?;
+        }
+        manager
+            .drop_table(Table::drop().table(Entity).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
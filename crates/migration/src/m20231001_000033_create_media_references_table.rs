@@ -0,0 +1,111 @@
+use crate::utils::macros::{create_table_from_entity, exec_stmt};
+use entity::media_reference::Entity;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20231001_000033_create_media_references_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        exec_stmt!(manager, r#"drop table if exists media_references"#)?;
+        create_table_from_entity!(manager, Entity)?;
+
+        // Set default value for created_at column and adds constraints, a
+        // reference links a single media to a single content
+        exec_stmt!(
+            manager,
+            r#"alter table media_references
+                alter column created_at set default now(),
+                add constraint single_media check (num_nonnulls(image_id, file_id) = 1),
+                add constraint single_content check (num_nonnulls(blok_id, post_id) = 1)
+            "#
+        )?;
+        for (column, table) in [
+            ("image_id", "images"),
+            ("file_id", "files"),
+            ("blok_id", "bloks"),
+            ("post_id", "posts"),
+        ] {
+            exec_stmt!(
+                manager,
+                r#"alter table media_references
+                    drop constraint if exists "fk-media_references-{column}",
+                    add constraint "fk-media_references-{column}"
+                        foreign key ({column})
+                        references {table}
+                        on update cascade
+                        on delete cascade
+                "#
+            )?;
+            exec_stmt!(
+                manager,
+                r#"create index media_references__{column}__idx on media_references ({column})"#
+            )?;
+        }
+
+        // References of existing contents, images are used through the URL of
+        // any of their renditions which share the stem of the main image key
+        for (content_id, contents) in [
+            (
+                "blok_id",
+                "select bloks.id, bloks.props as content, pages.namespace
+                    from bloks join pages on pages.id = bloks.page_id",
+            ),
+            (
+                "post_id",
+                "select posts.id, posts.body as content, posts.namespace from posts",
+            ),
+        ] {
+            exec_stmt!(
+                manager,
+                r#"insert into media_references (image_id, {content_id})
+                    select media.id, contents.id
+                    from ({contents}) contents
+                    join images media on media.namespace = contents.namespace
+                    where (media.lazy_image_id is not null or media.status <> 'ready')
+                        and (
+                            strpos(
+                                contents.content::text,
+                                left(media.storage_key, length(media.storage_key) - strpos(reverse(media.storage_key), '.'))
+                            ) > 0
+                            or jsonb_path_exists(
+                                contents.content,
+                                'lax $.**.imageId ? (@ == $id)',
+                                jsonb_build_object('id', media.id)
+                            )
+                        )
+                "#
+            )?;
+            exec_stmt!(
+                manager,
+                r#"insert into media_references (file_id, {content_id})
+                    select media.id, contents.id
+                    from ({contents}) contents
+                    join files media on media.namespace = contents.namespace
+                    where strpos(contents.content::text, media.storage_key) > 0
+                        or jsonb_path_exists(
+                            contents.content,
+                            'lax $.**.fileId ? (@ == $id)',
+                            jsonb_build_object('id', media.id)
+                        )
+                "#
+            )?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Entity).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use crate::utils::macros::exec_stmt;
+use sea_orm_migration::{prelude::*, MigrationName};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20231015_000034_exclude_trashed_bloks_from_priority"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Trashed bloks must not keep their slot, restoring one offsets the
+        // bloks which took it meanwhile. Unique constraints cannot be partial,
+        // an exclusion constraint stays deferrable for the offset.
+        exec_stmt!(
+            manager,
+            r#"alter table bloks drop constraint if exists page_id_priority__uniq, add constraint page_id_priority__uniq exclude (page_id with =, priority with =) where (deleted_at is null) deferrable initially immediate"#
+        )?;
+
+        exec_stmt!(
+            manager,
+            r#"
+        create or replace function tg_bloks__set_priority() returns trigger as $$
+          declare
+            max_priority integer;
+            updated_ids integer[];
+          begin
+            set constraints all deferred;
+
+            -- Set default priority to maximum
+            if new.priority is null then
+              select max(b1.priority) into max_priority from bloks b1 where b1.page_id = new.page_id and b1.deleted_at is null;
+              new.priority := coalesce(max_priority + 1, 0);
+            end if;
+
+            if new.deleted_at is null
+              and (tg_op = 'INSERT' or (tg_op = 'UPDATE' and (old.priority != new.priority or old.page_id != new.page_id or old.deleted_at is not null)))
+              and exists(select 1 from bloks where page_id = new.page_id and priority = new.priority and deleted_at is null and id is distinct from new.id) then
+              with t as (update bloks b2 set priority = b2.priority + 1 where b2.page_id = new.page_id and b2.priority >= new.priority and b2.deleted_at is null and b2.id is distinct from new.id returning id)
+              select array_agg(id) into updated_ids from t;
+
+              raise notice 'Offset priority for rows IDS : %', updated_ids;
+            end if;
+
+            return new;
+          end;
+        $$ language plpgsql volatile;
+      "#
+        )?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Trashed bloks may share their slot with another blok
+        exec_stmt!(
+            manager,
+            r#"
+        do $$
+          begin
+            if exists(select 1 from bloks where deleted_at is not null) then
+              raise exception 'Trashed bloks must be restored or purged before reverting this migration';
+            end if;
+          end;
+        $$
+      "#
+        )?;
+
+        exec_stmt!(
+            manager,
+            r#"
+        create or replace function tg_bloks__set_priority() returns trigger as $$
+          declare
+            max_priority integer;
+            updated_ids integer[];
+          begin
+            set constraints all deferred;
+
+            -- Set default priority to maximum
+            if new.priority is null then
+              select max(b1.priority) into max_priority from bloks b1 where b1.page_id = new.page_id;
+              new.priority := coalesce(max_priority + 1, 0);
+            end if;
+
+            if (tg_op = 'INSERT' or (tg_op = 'UPDATE' and old.priority != new.priority)) and exists(select 1 from bloks where page_id = new.page_id and priority = new.priority) then
+              with t as (update bloks b2 set priority = b2.priority + 1 where b2.page_id = new.page_id and b2.priority >= new.priority and b2.id is distinct from new.id returning id)
+              select array_agg(id) into updated_ids from t;
+
+              raise notice 'Offset priority for rows IDS : %', updated_ids;
+            end if;
+
+            return new;
+          end;
+        $$ language plpgsql volatile;
+      "#
+        )?;
+
+        exec_stmt!(
+            manager,
+            r#"alter table bloks drop constraint if exists page_id_priority__uniq, add constraint page_id_priority__uniq unique (page_id, priority) deferrable initially immediate"#
+        )?;
+
+        Ok(())
+    }
+}
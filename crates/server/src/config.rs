@@ -1,12 +1,13 @@
 use aws_credential_types::Credentials;
 use aws_smithy_async::rt::sleep::default_async_sleep;
 use aws_types::{app_name::AppName, region::Region};
-use config::Environment;
+use config::{builder::DefaultState, ConfigBuilder, Environment, Value};
 pub use config::{Config, ConfigError};
 use derive_more::Constructor;
 use getset::Getters;
 use once_cell::sync::Lazy as SyncLazy;
 use serde::Deserialize;
+use std::collections::HashMap;
 use url::Url;
 
 #[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -24,9 +25,197 @@ pub struct Settings {
     port: String,
     database_url: String,
     telemetry: bool,
-    s3: S3Config,
+    storage: StorageSettings,
     cors: Vec<String>,
     log_format: LogFormat,
+    cache_control: CacheControlSettings,
+    jobs: JobsSettings,
+}
+
+/// `Cache-Control` header values sent on read endpoints, per resource type
+#[derive(Deserialize, Getters, Constructor, Clone, Debug)]
+#[getset(get = "pub")]
+pub struct CacheControlSettings {
+    page: String,
+    post: String,
+    locale: String,
+}
+
+/// Limits of uploaded images (`image`) and files (`file`)
+#[derive(Deserialize, Getters, Constructor, Clone, Debug)]
+#[getset(get = "pub")]
+pub struct UploadSettings {
+    image: ImageUploadSettings,
+    file: FileUploadSettings,
+}
+
+/// Limits of uploaded images: `max_size` in bytes, enforced while the upload
+/// is streamed, and maximum dimensions in pixels, checked before decoding
+#[derive(Deserialize, Getters, Constructor, Clone, Debug)]
+#[getset(get = "pub")]
+pub struct ImageUploadSettings {
+    max_size: u32,
+    max_width: u32,
+    max_height: u32,
+}
+
+/// Limits of uploaded files: `max_size` in bytes, `url_ttl` and
+/// `download_url_ttl`, the validity in seconds of presigned upload and
+/// download URLs. All of them can be overridden per namespace,
+/// e.g. with `STORAGE__UPLOAD__FILE__NAMESPACES__<NAMESPACE>__MAX_SIZE`
+/// (namespaces set from the environment are lowercased). Replacing the content
+/// of a file keeps its `max_versions` previous objects, older ones are deleted.
+#[derive(Deserialize, Getters, Constructor, Clone, Debug)]
+#[getset(get = "pub")]
+pub struct FileUploadSettings {
+    max_size: u64,
+    url_ttl: u64,
+    download_url_ttl: u64,
+    max_versions: u32,
+    #[serde(default)]
+    namespaces: HashMap<String, NamespaceFileUploadSettings>,
+}
+
+#[derive(Deserialize, Getters, Constructor, Clone, Debug, Default)]
+#[getset(get = "pub")]
+pub struct NamespaceFileUploadSettings {
+    max_size: Option<u64>,
+    url_ttl: Option<u64>,
+    download_url_ttl: Option<u64>,
+}
+
+impl FileUploadSettings {
+    pub fn max_size_for(&self, namespace: &str) -> u64 {
+        self.namespaces
+            .get(namespace)
+            .and_then(|settings| settings.max_size)
+            .unwrap_or(self.max_size)
+    }
+
+    pub fn url_ttl_for(&self, namespace: &str) -> u64 {
+        self.namespaces
+            .get(namespace)
+            .and_then(|settings| settings.url_ttl)
+            .unwrap_or(self.url_ttl)
+    }
+
+    pub fn download_url_ttl_for(&self, namespace: &str) -> u64 {
+        self.namespaces
+            .get(namespace)
+            .and_then(|settings| settings.download_url_ttl)
+            .unwrap_or(self.download_url_ttl)
+    }
+}
+
+/// Storage quotas: `max_size` is the most bytes the images and files of a
+/// namespace can take in storage, unlimited when unset. It can be overridden
+/// per namespace, e.g. with
+/// `STORAGE__QUOTA__NAMESPACES__<NAMESPACE>__MAX_SIZE`.
+#[derive(Deserialize, Getters, Constructor, Clone, Debug, Default)]
+#[getset(get = "pub")]
+pub struct QuotaSettings {
+    max_size: Option<u64>,
+    #[serde(default)]
+    namespaces: HashMap<String, NamespaceQuotaSettings>,
+}
+
+#[derive(Deserialize, Getters, Constructor, Clone, Debug, Default)]
+#[getset(get = "pub")]
+pub struct NamespaceQuotaSettings {
+    max_size: Option<u64>,
+}
+
+impl QuotaSettings {
+    pub fn max_size_for(&self, namespace: &str) -> Option<u64> {
+        self.namespaces
+            .get(namespace)
+            .and_then(|settings| settings.max_size)
+            .or(self.max_size)
+    }
+}
+
+/// Settings of the background jobs running alongside the server
+#[derive(Deserialize, Getters, Constructor, Clone, Debug)]
+#[getset(get = "pub")]
+pub struct JobsSettings {
+    trash: TrashSettings,
+    object_cleanup: ObjectCleanupSettings,
+    image_processing: ImageProcessingSettings,
+    pending_files: PendingFileSettings,
+}
+
+/// Soft-deleted resources stay in the trash for `retention_days` before being
+/// permanently removed, the purge runs every `purge_interval` seconds
+#[derive(Deserialize, Getters, Constructor, Clone, Debug)]
+#[getset(get = "pub")]
+pub struct TrashSettings {
+    retention_days: u32,
+    purge_interval: u64,
+}
+
+/// Intervals (in seconds) of the jobs cleaning up S3 objects. Failed deletions
+/// are retried every `retry_interval`, the image bucket is scanned for orphaned
+/// objects every `reconcile_interval`, ignoring objects written less than
+/// `orphan_grace_period` ago (their upload may still be in progress)
+#[derive(Deserialize, Getters, Constructor, Clone, Debug)]
+#[getset(get = "pub")]
+pub struct ObjectCleanupSettings {
+    retry_interval: u64,
+    reconcile_interval: u64,
+    orphan_grace_period: u64,
+}
+
+/// The image job queue is polled every `poll_interval` seconds for at most
+/// `batch_size` jobs. Failed jobs are retried after `retry_delay` seconds,
+/// doubled on each attempt, up to `max_attempts`. Jobs locked for more than
+/// `lock_timeout` seconds are considered abandoned by their worker.
+#[derive(Deserialize, Getters, Constructor, Clone, Debug)]
+#[getset(get = "pub")]
+pub struct ImageProcessingSettings {
+    poll_interval: u64,
+    batch_size: u64,
+    max_attempts: u32,
+    retry_delay: u64,
+    lock_timeout: u64,
+}
+
+/// Files whose upload URL expired without being confirmed are removed every
+/// `cleanup_interval` seconds
+#[derive(Deserialize, Getters, Constructor, Clone, Debug)]
+#[getset(get = "pub")]
+pub struct PendingFileSettings {
+    cleanup_interval: u64,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageDriver {
+    S3,
+    Local,
+}
+
+/// Where images and files are stored, S3 (configured by `s3`) or the local
+/// filesystem (configured by `local`). Both use the bucket names of
+/// `s3.buckets`. What can be stored is limited by `quota` and `upload`.
+#[derive(Deserialize, Getters, Constructor, Clone, Debug)]
+#[getset(get = "pub")]
+pub struct StorageSettings {
+    driver: StorageDriver,
+    s3: S3Config,
+    local: Option<LocalStorageSettings>,
+    #[serde(default)]
+    quota: QuotaSettings,
+    upload: UploadSettings,
+}
+
+/// Objects are written under `root` and served by the API, `base_url` being
+/// its public URL. Upload and private download URLs are signed with `secret`.
+#[derive(Deserialize, Getters, Constructor, Clone, Debug)]
+#[getset(get = "pub")]
+pub struct LocalStorageSettings {
+    root: String,
+    base_url: String,
+    secret: String,
 }
 
 #[derive(Deserialize, Getters, Constructor, Clone, Debug)]
@@ -41,7 +230,7 @@ pub struct S3Config {
 
 impl From<Settings> for aws_sdk_s3::Config {
     fn from(cfg: Settings) -> Self {
-        let s3_cfg = cfg.s3();
+        let s3_cfg = cfg.storage().s3();
         aws_sdk_s3::config::Builder::new()
             .app_name(
                 AppName::new(cfg.app_name().clone())
@@ -88,28 +277,112 @@ pub struct S3Buckets {
     file: String,
 }
 
+/// Keys settings were read from before being grouped under `storage`, along
+/// with their current key. They are still read, the current key taking
+/// precedence when both are set.
+const LEGACY_KEYS: [(&str, &str); 3] = [
+    ("s3", "storage.s3"),
+    ("image_upload", "storage.upload.image"),
+    ("file_upload", "storage.upload.file"),
+];
+
+fn environment() -> Environment {
+    Environment::default()
+        .try_parsing(true)
+        .with_list_parse_key("cors")
+        .separator("__")
+        .list_separator(",")
+}
+
+/// Sets every value under `value` as the default of the same path under `key`
+fn set_legacy_defaults(
+    builder: ConfigBuilder<DefaultState>,
+    key: &str,
+    value: Value,
+) -> Result<ConfigBuilder<DefaultState>, ConfigError> {
+    match value.clone().into_table() {
+        Ok(table) => table
+            .into_iter()
+            .try_fold(builder, |builder, (name, value)| {
+                set_legacy_defaults(builder, &format!("{key}.{name}"), value)
+            }),
+        Err(_) => builder.set_default(key, value),
+    }
+}
+
 impl Settings {
     pub fn from_env() -> Result<Self, ConfigError> {
-        let cfg = Config::builder()
+        let builder = Config::builder()
             .set_default("app_name", "lyonkit-api")?
             .set_default("port", 8080)?
             .set_default("host", "0.0.0.0")?
             .set_default("telemetry", false)?
-            .set_default("s3.buckets.image", "lyonkit-images")?
-            .set_default("s3.buckets.file", "lyonkit-files")?
+            .set_default("storage.driver", "s3")?
+            .set_default("storage.s3.endpoint", "")?
+            .set_default("storage.s3.base_url", "")?
+            .set_default("storage.s3.region", "")?
+            .set_default("storage.s3.credentials.access_key_id", "")?
+            .set_default("storage.s3.credentials.secret_access_key", "")?
+            .set_default("storage.s3.buckets.image", "lyonkit-images")?
+            .set_default("storage.s3.buckets.file", "lyonkit-files")?
             .set_default("cors", Vec::<String>::new())?
             .set_default("log_format", "json")?
-            .add_source(
-                Environment::default()
-                    .try_parsing(true)
-                    .with_list_parse_key("cors")
-                    .separator("__")
-                    .list_separator(","),
-            )
-            .build()
-            .unwrap();
+            .set_default("cache_control.page", "no-cache")?
+            .set_default("cache_control.post", "no-cache")?
+            .set_default("cache_control.locale", "no-cache")?
+            .set_default("jobs.trash.retention_days", 30)?
+            .set_default("jobs.trash.purge_interval", 3600)?
+            .set_default("jobs.object_cleanup.retry_interval", 300)?
+            .set_default("jobs.object_cleanup.reconcile_interval", 86400)?
+            .set_default("jobs.object_cleanup.orphan_grace_period", 3600)?
+            .set_default("jobs.image_processing.poll_interval", 1)?
+            .set_default("jobs.image_processing.batch_size", 4)?
+            .set_default("jobs.image_processing.max_attempts", 5)?
+            .set_default("jobs.image_processing.retry_delay", 30)?
+            .set_default("jobs.image_processing.lock_timeout", 600)?
+            .set_default("jobs.pending_files.cleanup_interval", 600)?
+            .set_default("storage.upload.image.max_size", 20_000_000)?
+            .set_default("storage.upload.image.max_width", 10_000)?
+            .set_default("storage.upload.image.max_height", 10_000)?
+            .set_default("storage.upload.file.max_size", 50_000_000)?
+            .set_default("storage.upload.file.url_ttl", 180)?
+            .set_default("storage.upload.file.download_url_ttl", 300)?
+            .set_default("storage.upload.file.max_versions", 3)?;
+
+        let legacy = Config::builder().add_source(environment()).build()?;
+        let builder = LEGACY_KEYS
+            .into_iter()
+            .filter_map(|(legacy_key, key)| legacy.get::<Value>(legacy_key).ok().map(|v| (key, v)))
+            .try_fold(builder, |builder, (key, value)| {
+                set_legacy_defaults(builder, key, value)
+            })?;
+
+        let cfg = builder.add_source(environment()).build().unwrap();
+
+        let settings: Self = cfg.try_deserialize()?;
+        match settings.storage().driver() {
+            StorageDriver::S3 if settings.storage().s3().endpoint().is_empty() => {
+                Err(ConfigError::Message(
+                    "The s3 storage driver requires storage.s3.endpoint to be set".to_string(),
+                ))
+            }
+            StorageDriver::Local if settings.storage().local().is_none() => {
+                Err(ConfigError::Message(
+                    "The local storage driver requires storage.local to be set".to_string(),
+                ))
+            }
+            _ => Ok(settings),
+        }
+    }
 
-        cfg.try_deserialize()
+    /// URL public objects are served from, followed by their bucket and key
+    pub fn storage_base_url(&self) -> String {
+        match (self.storage().driver(), self.storage().local()) {
+            (StorageDriver::Local, Some(local)) => {
+                format!("{}/storage", local.base_url().trim_end_matches('/'))
+            }
+            _ => self.storage().s3().base_url().clone(),
+        }
     }
 
     pub fn server_addr(&self) -> String {
@@ -1,6 +1,13 @@
 use std::fmt::{Debug, Display, Formatter};
 
-use actix_web::{body::BoxBody, http::StatusCode, HttpResponse, ResponseError};
+use actix_web::{
+    body::BoxBody,
+    http::{
+        header::{HeaderValue, ETAG},
+        StatusCode,
+    },
+    HttpResponse, ResponseError,
+};
 use humansize::{FormatSize, DECIMAL};
 use mime::Mime;
 use sea_orm::DbErr;
@@ -33,6 +40,8 @@ pub enum ApiError {
     MissingContentType(Vec<Mime>),
     InvalidContentType(Vec<Mime>, Mime),
     MissingField(String),
+    /// First is the field name, second is why its value is invalid
+    InvalidField(String, String),
     ImageNotDecodable,
     InternalServerError,
     PatchNotNullable(String),
@@ -41,7 +50,32 @@ pub enum ApiError {
     GitTokenMissing,
     GitBodyUnparseable,
     /// First is max size, second is actual size
-    FileTooBig(u32, u32),
+    FileTooBig(u64, u64),
+    /// Contains the current version (ETag) of the resource
+    PreconditionFailed(String),
+    /// Contains the unique field already used by another resource
+    RestoreConflict(String),
+    /// Requested width and height
+    ImageSizeNotAllowed(u32, u32),
+    /// First is max width and height, second is actual width and height
+    ImageDimensionsTooLarge((u32, u32), (u32, u32)),
+    /// The image already has a pending job
+    ImageProcessing,
+    /// The object of a pending file cannot be found
+    FileNotUploaded,
+    /// Why the uploaded object does not match the declared file
+    FileUploadMismatch(String),
+    /// The signature of a storage URL is invalid or expired
+    InvalidSignature,
+    /// First is the namespace quota, second the bytes it already uses and
+    /// third the size of the upload
+    QuotaExceeded(u64, u64, u64),
+    /// The folder still contains folders or media
+    FolderNotEmpty,
+    /// Another folder of the same parent has this name
+    FolderNameTaken(String),
+    /// Number of bloks and posts using the media
+    MediaInUse(usize),
 }
 
 impl Display for ApiError {
@@ -83,6 +117,9 @@ impl Display for ApiError {
                 )
             }
             ApiError::MissingField(field) => write!(f, "Missing field \"{}\"", field),
+            ApiError::InvalidField(field, reason) => {
+                write!(f, "Invalid field \"{}\": {}", field, reason)
+            }
             ApiError::ImageNotDecodable => write!(
                 f,
                 "Provide image is not decodable, make sure you provide a valid image or that you \
@@ -98,6 +135,62 @@ impl Display for ApiError {
                 actual_size.format_size(DECIMAL),
                 max_size.format_size(DECIMAL)
             ),
+            ApiError::PreconditionFailed(current_version) => write!(
+                f,
+                "The resource has been modified since you last fetched it (current version is \
+                 {current_version})"
+            ),
+            ApiError::RestoreConflict(field) => write!(
+                f,
+                "Cannot restore this resource, its \"{field}\" is already used by another one"
+            ),
+            ApiError::ImageSizeNotAllowed(width, height) => write!(
+                f,
+                "Image size {width}x{height} is not allowed, it must be added to the namespace \
+                 transform sizes first"
+            ),
+            ApiError::ImageDimensionsTooLarge((max_width, max_height), (width, height)) => write!(
+                f,
+                "The image you are trying to upload is too large (your image is {width}x{height} \
+                 but the maximum dimensions are {max_width}x{max_height})"
+            ),
+            ApiError::ImageProcessing => write!(
+                f,
+                "This image is already being processed, retry once its status is no longer \
+                 \"processing\""
+            ),
+            ApiError::FileNotUploaded => write!(
+                f,
+                "The file has not been uploaded yet, upload it to its upload URL before \
+                 confirming it"
+            ),
+            ApiError::FileUploadMismatch(reason) => {
+                write!(
+                    f,
+                    "The uploaded file does not match the declared one: {reason}"
+                )
+            }
+            ApiError::InvalidSignature => write!(f, "The URL signature is invalid or expired"),
+            ApiError::QuotaExceeded(quota, used, size) => write!(
+                f,
+                "The storage quota of this namespace would be exceeded (it uses {} of its {} and \
+                 your upload is {})",
+                used.format_size(DECIMAL),
+                quota.format_size(DECIMAL),
+                size.format_size(DECIMAL)
+            ),
+            ApiError::FolderNotEmpty => write!(
+                f,
+                "This folder is not empty, move or delete its folders and media first"
+            ),
+            ApiError::FolderNameTaken(name) => {
+                write!(f, "A folder named \"{name}\" already exists here")
+            }
+            ApiError::MediaInUse(count) => write!(
+                f,
+                "This media is used by {count} blok(s) or post(s), list them with its references \
+                 endpoint or delete it with \"force=true\""
+            ),
         }
     }
 }
@@ -115,6 +208,7 @@ impl ApiErrorTrait for ApiError {
             ApiError::MissingContentType(_) => String::from("MISCT"),
             ApiError::InvalidContentType(_, _) => String::from("BADCT"),
             ApiError::MissingField(_) => String::from("FLMIS"),
+            ApiError::InvalidField(_, _) => String::from("FLINV"),
             ApiError::ImageNotDecodable => String::from("IMGND"),
             ApiError::InternalServerError => String::from("INTSE"),
             ApiError::PatchNotNullable(_) => String::from("PTHNN"),
@@ -123,12 +217,26 @@ impl ApiErrorTrait for ApiError {
             ApiError::GitBodyUnparseable => String::from("GITBU"),
             ApiError::GitTokenMissing => String::from("GITTM"),
             ApiError::FileTooBig(_, _) => String::from("FTBIG"),
+            ApiError::PreconditionFailed(_) => String::from("PRCFL"),
+            ApiError::RestoreConflict(_) => String::from("RSTCF"),
+            ApiError::ImageSizeNotAllowed(_, _) => String::from("IMSNA"),
+            ApiError::ImageDimensionsTooLarge(_, _) => String::from("IMDTL"),
+            ApiError::ImageProcessing => String::from("IMPRG"),
+            ApiError::FileNotUploaded => String::from("FLNUP"),
+            ApiError::FileUploadMismatch(_) => String::from("FLUPM"),
+            ApiError::InvalidSignature => String::from("SGINV"),
+            ApiError::QuotaExceeded(_, _, _) => String::from("QTEXC"),
+            ApiError::FolderNotEmpty => String::from("FDNEM"),
+            ApiError::FolderNameTaken(_) => String::from("FDNTK"),
+            ApiError::MediaInUse(_) => String::from("MDUSE"),
         }
     }
 
     fn http_code(&self) -> StatusCode {
         match self {
-            ApiError::ApiKeyNotProvided | ApiError::ApiKeyInvalid => StatusCode::FORBIDDEN,
+            ApiError::ApiKeyNotProvided | ApiError::ApiKeyInvalid | ApiError::InvalidSignature => {
+                StatusCode::FORBIDDEN
+            }
             ApiError::ApiKeyReadOnly => StatusCode::UNAUTHORIZED,
             ApiError::NotFound => StatusCode::NOT_FOUND,
             ApiError::ReferenceNotFound(_)
@@ -136,9 +244,22 @@ impl ApiErrorTrait for ApiError {
             | ApiError::InvalidContentType(_, _)
             | ApiError::PatchNotNullable(_)
             | ApiError::PatchAtLeastOneField
-            | ApiError::MissingField(_) => StatusCode::BAD_REQUEST,
-            ApiError::ImageNotDecodable => StatusCode::UNPROCESSABLE_ENTITY,
-            ApiError::FileTooBig(_, _) => StatusCode::PAYLOAD_TOO_LARGE,
+            | ApiError::MissingField(_)
+            | ApiError::InvalidField(_, _)
+            | ApiError::ImageSizeNotAllowed(_, _) => StatusCode::BAD_REQUEST,
+            ApiError::ImageNotDecodable | ApiError::FileUploadMismatch(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            ApiError::FileTooBig(_, _)
+            | ApiError::ImageDimensionsTooLarge(_, _)
+            | ApiError::QuotaExceeded(_, _, _) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            ApiError::RestoreConflict(_)
+            | ApiError::ImageProcessing
+            | ApiError::FileNotUploaded
+            | ApiError::FolderNotEmpty
+            | ApiError::FolderNameTaken(_)
+            | ApiError::MediaInUse(_) => StatusCode::CONFLICT,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -146,7 +267,15 @@ impl ApiErrorTrait for ApiError {
 
 impl ResponseError for ApiError {
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        self.http_response()
+        let mut response = self.http_response();
+
+        if let ApiError::PreconditionFailed(current_version) = self {
+            if let Ok(etag) = HeaderValue::from_str(current_version) {
+                response.headers_mut().insert(ETAG, etag);
+            }
+        }
+
+        response
     }
 }
 
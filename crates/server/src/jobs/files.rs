@@ -0,0 +1,107 @@
+use crate::{
+    config::{S3Buckets, Settings},
+    errors::{utils::MapApiError, ApiError},
+    jobs::{delayed_interval, objects::delete_objects},
+    services::files::{repository::FilesRepository, services::abort_multipart_upload},
+    storage::{Storage, StorageExt},
+};
+use chrono::{DateTime, Utc};
+use entity::file::{Column, Entity, FileStatus};
+use sea_orm::{prelude::*, ConnectionTrait, DatabaseConnection};
+use std::time::Duration;
+use tracing::{error, info, info_span, warn, Instrument};
+
+/// Removes the pending files whose upload URL expired before `expired_before`,
+/// along with the object the client may have uploaded without confirming it.
+/// Files whose content was being replaced are restored to their latest version
+/// instead. Returns the number of expired uploads.
+pub async fn remove_expired_uploads<C: ConnectionTrait>(
+    conn: &C,
+    storage: &dyn Storage,
+    buckets: &S3Buckets,
+    expired_before: DateTime<Utc>,
+) -> Result<u64, ApiError> {
+    let files = Entity::find()
+        .filter(Column::Status.eq(FileStatus::Pending))
+        .filter(Column::UploadExpiresAt.lt(expired_before))
+        .all(conn)
+        .await
+        .map_api_err()?;
+
+    if files.is_empty() {
+        return Ok(0);
+    }
+
+    let expired = files.len() as u64;
+    let mut removed_ids = Vec::new();
+    let mut keys = Vec::new();
+
+    for file in files {
+        // Parts are not objects of the bucket, they are only discarded along
+        // with their multipart upload
+        if let Some(upload_id) = file.upload_id() {
+            if let Err(e) =
+                abort_multipart_upload(storage, buckets.file(), file.storage_key(), upload_id).await
+            {
+                warn!(
+                    error_message = format!("{:?}", e).as_str(),
+                    s3_key = file.storage_key().as_str(),
+                    "Cannot abort multipart upload of expired file"
+                );
+            }
+        }
+        keys.push(file.storage_key.clone());
+
+        match conn.find_file_versions(file.id).await?.into_iter().next() {
+            Some(version) => {
+                conn.restore_file_version(file, version).await?;
+            }
+            None => removed_ids.push(file.id),
+        }
+    }
+
+    if !removed_ids.is_empty() {
+        Entity::delete_many()
+            .filter(Column::Id.is_in(removed_ids))
+            .exec(conn)
+            .await
+            .map_api_err()?;
+    }
+
+    delete_objects(conn, storage, buckets.file(), keys).await?;
+
+    Ok(expired)
+}
+
+/// Runs [`remove_expired_uploads`] every `pending_files.cleanup_interval`
+/// seconds
+pub async fn remove_expired_uploads_periodically(
+    conn: DatabaseConnection,
+    settings: Settings,
+    storage: StorageExt,
+) {
+    let mut interval = delayed_interval(Duration::from_secs(
+        *settings.jobs().pending_files().cleanup_interval(),
+    ));
+
+    loop {
+        interval.tick().await;
+
+        match remove_expired_uploads(
+            &conn,
+            storage.as_ref(),
+            settings.storage().s3().buckets(),
+            Utc::now(),
+        )
+        .instrument(info_span!("REMOVE_EXPIRED_UPLOADS").or_current())
+        .await
+        {
+            Ok(0) => {}
+            Ok(expired) => info!(expired, "Removed uploads never confirmed"),
+            Err(e) => error!(
+                error_message = format!("{:?}", e).as_str(),
+                "An error occured while removing expired uploads"
+            ),
+        }
+    }
+}
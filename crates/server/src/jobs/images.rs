@@ -0,0 +1,485 @@
+use crate::{
+    config::{ImageProcessingSettings, Settings},
+    errors::{
+        utils::{MapApiError, TryUnwrapActiveValue},
+        ApiError,
+    },
+    jobs::{delayed_interval, objects::delete_objects},
+    services::{
+        image::services::{
+            download_object, format_values, generate_renditions, image_storage_keys,
+            insert_image_variants, ImageProcessingOptions, ProcessedImage,
+        },
+        usage::services::quota_usage,
+    },
+    storage::StorageExt,
+};
+use chrono::{Duration as ChronoDuration, Utc};
+use entity::{
+    image::{self, ImageStatus},
+    image_job::{ActiveModel, Column, Entity, Model},
+    image_profile::{self, ImageFit, ImageFormat},
+    image_transform, image_variant,
+};
+use futures::future::join_all;
+use sea_orm::{
+    prelude::*, sea_query::Expr, ActiveEnum, ActiveValue::Set, ConnectionTrait, DatabaseBackend,
+    DatabaseConnection, IntoActiveModel, QueryOrder, Statement, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use tracing::{error, info, info_span, warn, Instrument};
+use uuid::Uuid;
+
+/// How the renditions of an image are generated, stored along with its job
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageJobOptions {
+    /// Prefix of the rendition storage keys, renewed on each job so that
+    /// cached renditions are never served for new ones
+    pub id: String,
+    pub filename: String,
+    pub fit: ImageFit,
+    pub alternate_formats: Vec<ImageFormat>,
+}
+
+impl ImageJobOptions {
+    /// Options to regenerate the renditions of an image, keeping the ones of
+    /// its last job. Images uploaded before jobs were recorded keep their
+    /// alternate formats.
+    pub async fn for_reprocess<C: ConnectionTrait>(
+        conn: &C,
+        image: &image::Model,
+    ) -> Result<Self, ApiError> {
+        let last_job = Entity::find()
+            .filter(Column::ImageId.eq(image.id))
+            .order_by_desc(Column::Id)
+            .one(conn)
+            .await
+            .map_api_err()?;
+
+        let options = match last_job {
+            Some(job) => serde_json::from_value(job.options).map_err(|e| {
+                error!(
+                    error_message = format!("{:?}", e).as_str(),
+                    image_id = image.id,
+                    "Cannot read image job options"
+                );
+                ApiError::InternalServerError
+            })?,
+            None => Self {
+                id: String::new(),
+                filename: image
+                    .storage_key
+                    .split_once("__")
+                    .map(|(_, filename)| filename.to_string())
+                    .unwrap_or_else(|| image.storage_key.clone()),
+                fit: ImageFit::Contain,
+                alternate_formats: image
+                    .formats
+                    .iter()
+                    .skip(1)
+                    .filter_map(|format| ImageFormat::try_from_value(format).ok())
+                    .collect(),
+            },
+        };
+
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            ..options
+        })
+    }
+}
+
+/// Queues the generation of the renditions of an image, failing when it
+/// already has a pending job
+pub async fn enqueue_image_job<C: ConnectionTrait>(
+    conn: &C,
+    image_id: i32,
+    options: &ImageJobOptions,
+) -> Result<Model, ApiError> {
+    if has_pending_image_job(conn, image_id).await? {
+        return Err(ApiError::ImageProcessing);
+    }
+
+    ActiveModel {
+        image_id: Set(image_id),
+        options: Set(serde_json::to_value(options).map_err(|e| {
+            error!(
+                error_message = format!("{:?}", e).as_str(),
+                "Cannot write image job options"
+            );
+            ApiError::InternalServerError
+        })?),
+        run_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+    .map_api_err()
+}
+
+/// Whether the image has a job which is neither completed nor failed
+pub async fn has_pending_image_job<C: ConnectionTrait>(
+    conn: &C,
+    image_id: i32,
+) -> Result<bool, ApiError> {
+    Ok(Entity::find()
+        .filter(Column::ImageId.eq(image_id))
+        .filter(Column::CompletedAt.is_null())
+        .filter(Column::FailedAt.is_null())
+        .count(conn)
+        .await
+        .map_api_err()?
+        > 0)
+}
+
+/// Locks the next pending jobs, counting an attempt for each. Jobs locked by
+/// another worker are skipped, unless their lock timed out.
+pub async fn claim_image_jobs<C: ConnectionTrait>(
+    conn: &C,
+    settings: &ImageProcessingSettings,
+) -> Result<Vec<Model>, ApiError> {
+    Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            r#"update image_jobs
+                set locked_at = now(), attempts = attempts + 1
+                where id in (
+                    select id from image_jobs
+                    where completed_at is null
+                        and failed_at is null
+                        and run_at <= now()
+                        and (locked_at is null or locked_at < now() - make_interval(secs => $1))
+                    order by run_at
+                    limit $2
+                    for update skip locked
+                )
+                returning *"#,
+            vec![
+                (*settings.lock_timeout() as f64).into(),
+                (*settings.batch_size() as i64).into(),
+            ],
+        ))
+        .all(conn)
+        .await
+        .map_api_err()
+}
+
+/// Generates the renditions of the image of a claimed job from its original,
+/// then points the image to them. Renditions of its previous original are
+/// deleted.
+pub async fn run_image_job(
+    conn: &DatabaseConnection,
+    storage: &StorageExt,
+    settings: &Settings,
+    job: &Model,
+) -> Result<(), ApiError> {
+    // Jobs are removed along with their image by the foreign key
+    let Some(image) = image::Entity::find_by_id(job.image_id)
+        .one(conn)
+        .await
+        .map_api_err()?
+    else {
+        return Ok(());
+    };
+
+    let options: ImageJobOptions = serde_json::from_value(job.options.clone()).map_err(|e| {
+        error!(
+            error_message = format!("{:?}", e).as_str(),
+            "Cannot read image job options"
+        );
+        ApiError::InternalServerError
+    })?;
+
+    let bucket = Arc::new(settings.storage().s3().buckets().image().clone());
+    let source_key = image
+        .original_storage_key
+        .clone()
+        .unwrap_or_else(|| image.storage_key.clone());
+    let content_type = image
+        .original_mime_type
+        .as_deref()
+        .and_then(|mime_type| mime_type.parse().ok())
+        .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+
+    let profiles = image_profile::Entity::find()
+        .filter(image_profile::Column::Namespace.eq(image.namespace.clone()))
+        .order_by_asc(image_profile::Column::Name)
+        .all(conn)
+        .await
+        .map_api_err()?;
+
+    let processed = generate_renditions(
+        storage.clone(),
+        bucket.clone(),
+        download_object(storage.as_ref(), &bucket, &source_key).await?,
+        content_type,
+        (
+            *settings.storage().upload().image().max_width(),
+            *settings.storage().upload().image().max_height(),
+        ),
+        &ImageProcessingOptions {
+            fit: options.fit,
+            alternate_formats: options.alternate_formats,
+            focal_point: image.focal_x.zip(image.focal_y),
+            id: options.id.into(),
+            filename: options.filename.into(),
+        },
+        &profiles,
+    )
+    .await?;
+
+    let new_keys: HashSet<String> = [&processed.main, &processed.lazy]
+        .into_iter()
+        .chain(&processed.variants)
+        .flat_map(|uploaded| uploaded.keys.clone())
+        .chain([source_key.clone()])
+        .collect();
+
+    // Only the original was counted against the quota on upload, the
+    // renditions are counted once saved and not kept if they exceed it
+    let namespace = image.namespace.clone();
+    let quota = settings.storage().quota().max_size_for(&namespace);
+    let txn = conn.begin().await.map_api_err()?;
+    let used = match quota {
+        Some(_) => quota_usage(&txn, &namespace).await?,
+        None => 0,
+    };
+    let previous_keys = save_renditions(&txn, image, source_key.clone(), processed).await?;
+
+    if let Some(quota) = quota {
+        let stored = quota_usage(&txn, &namespace).await?;
+        if stored > quota && stored > used {
+            txn.rollback().await.map_api_err()?;
+
+            // Keys are renewed on each job, none of these is used by a row
+            let rendition_keys = new_keys
+                .into_iter()
+                .filter(|key| *key != source_key)
+                .collect();
+            delete_objects(conn, storage.as_ref(), &bucket, rendition_keys).await?;
+
+            return Err(ApiError::QuotaExceeded(quota, used, stored - used));
+        }
+    }
+
+    let mut job = job.clone().into_active_model();
+    job.completed_at = Set(Some(Utc::now()));
+    job.locked_at = Set(None);
+    job.update(&txn).await.map_api_err()?;
+    txn.commit().await.map_api_err()?;
+
+    // Rows now point to the new renditions, objects which cannot be deleted
+    // are queued for retry
+    let previous_keys = previous_keys
+        .into_iter()
+        .filter(|key| !new_keys.contains(key))
+        .collect();
+    delete_objects(conn, storage.as_ref(), &bucket, previous_keys).await
+}
+
+/// Points the image rows to the processed renditions, returns the storage
+/// keys of the renditions they pointed to
+async fn save_renditions<C: ConnectionTrait>(
+    conn: &C,
+    image: image::Model,
+    source_key: String,
+    processed: ProcessedImage,
+) -> Result<Vec<String>, ApiError> {
+    let lz_image = match image.lazy_image_id {
+        Some(lazy_image_id) => image::Entity::find_by_id(lazy_image_id)
+            .one(conn)
+            .await
+            .map_api_err()?,
+        None => None,
+    };
+
+    let mut previous_keys = Vec::new();
+    if lz_image.is_some() {
+        previous_keys.extend(image_storage_keys(&image));
+    }
+    if let Some(lz_image) = &lz_image {
+        previous_keys.extend(image_storage_keys(lz_image));
+    }
+    previous_keys.extend(
+        image_variant::Entity::find()
+            .filter(image_variant::Column::ImageId.eq(image.id))
+            .all(conn)
+            .await
+            .map_api_err()?
+            .into_iter()
+            .map(|variant| variant.storage_key),
+    );
+    previous_keys.extend(
+        image_transform::Entity::find()
+            .filter(image_transform::Column::ImageId.eq(image.id))
+            .all(conn)
+            .await
+            .map_api_err()?
+            .into_iter()
+            .map(|transform| transform.storage_key),
+    );
+
+    image_variant::Entity::delete_many()
+        .filter(image_variant::Column::ImageId.eq(image.id))
+        .exec(conn)
+        .await
+        .map_api_err()?;
+    image_transform::Entity::delete_many()
+        .filter(image_transform::Column::ImageId.eq(image.id))
+        .exec(conn)
+        .await
+        .map_api_err()?;
+
+    let mut image_lazy = match lz_image {
+        Some(lz_image) => lz_image.into_active_model(),
+        None => image::ActiveModel {
+            namespace: Set(image.namespace.clone()),
+            deleted_at: Set(image.deleted_at),
+            ..Default::default()
+        },
+    };
+    image_lazy.storage_key = Set(processed.lazy.keys[0].clone());
+    image_lazy.alt = Set(image.alt.clone());
+    image_lazy.formats = Set(format_values(&processed.lazy.rendition.formats));
+    image_lazy.size = Set(Some(processed.lazy.size()));
+    image_lazy.width = Set(Some(processed.lazy.width as i32));
+    image_lazy.height = Set(Some(processed.lazy.height as i32));
+    let image_lazy = image_lazy.save(conn).await.map_api_err()?;
+
+    let metadata = &processed.metadata;
+    let image_id = image.id;
+    let mut image = image.into_active_model();
+    image.storage_key = Set(processed.main.keys[0].clone());
+    image.original_storage_key = Set(Some(source_key));
+    image.lazy_image_id = Set(Some(image_lazy.id.try_unwrap_av()?));
+    image.status = Set(ImageStatus::Ready);
+    image.formats = Set(format_values(&processed.main.rendition.formats));
+    image.size = Set(Some(processed.main.size()));
+    image.width = Set(Some(processed.main.width as i32));
+    image.height = Set(Some(processed.main.height as i32));
+    image.original_width = Set(Some(metadata.width as i32));
+    image.original_height = Set(Some(metadata.height as i32));
+    image.original_mime_type = Set(metadata.mime_type.map(ToString::to_string));
+    image.original_size = Set(Some(metadata.size as i64));
+    image.orientation = Set(metadata.orientation.map(|orientation| orientation as i16));
+    image.dominant_color = Set(metadata.dominant_color.clone());
+    image.blurhash = Set(metadata.blurhash.clone());
+    image.update(conn).await.map_api_err()?;
+
+    insert_image_variants(conn, image_id, processed.variants).await?;
+
+    Ok(previous_keys)
+}
+
+/// Whether another attempt of a job which failed with this error may succeed.
+/// Invalid images and exceeded quotas fail the same way on each attempt.
+fn is_transient(error: &ApiError) -> bool {
+    matches!(
+        error,
+        ApiError::DbError | ApiError::DbDeserializeError | ApiError::InternalServerError
+    )
+}
+
+/// Schedules the next attempt of a failed job, with an exponential backoff,
+/// or marks it and its image as failed once it ran out of attempts or its
+/// error is not transient
+async fn retry_image_job<C: ConnectionTrait>(
+    conn: &C,
+    settings: &ImageProcessingSettings,
+    job: Model,
+    error: &ApiError,
+) -> Result<(), ApiError> {
+    let image_id = job.image_id;
+    let attempts = job.attempts;
+    let mut job = job.into_active_model();
+    job.locked_at = Set(None);
+    job.last_error = Set(Some(format!("{error:?}: {error}")));
+
+    if attempts >= *settings.max_attempts() as i32 || !is_transient(error) {
+        job.failed_at = Set(Some(Utc::now()));
+        job.update(conn).await.map_api_err()?;
+
+        image::Entity::update_many()
+            .col_expr(
+                image::Column::Status,
+                Expr::value(ImageStatus::Failed.to_value()),
+            )
+            .filter(image::Column::Id.eq(image_id))
+            .exec(conn)
+            .await
+            .map_api_err()?;
+    } else {
+        let delay = *settings.retry_delay() as i64 * 2_i64.pow(attempts.max(1) as u32 - 1);
+        job.run_at = Set(Utc::now() + ChronoDuration::seconds(delay));
+        job.update(conn).await.map_api_err()?;
+    }
+
+    Ok(())
+}
+
+/// Runs the next pending jobs concurrently, returns the number of jobs run
+pub async fn process_image_jobs(
+    conn: &DatabaseConnection,
+    storage: &StorageExt,
+    settings: &Settings,
+) -> Result<u64, ApiError> {
+    let jobs = claim_image_jobs(conn, settings.jobs().image_processing()).await?;
+    let claimed = jobs.len() as u64;
+
+    let results = join_all(jobs.iter().map(|job| {
+        run_image_job(conn, storage, settings, job).instrument(
+            info_span!("IMAGE_JOB", job_id = job.id, image_id = job.image_id).or_current(),
+        )
+    }))
+    .await;
+
+    for (job, result) in jobs.into_iter().zip(results) {
+        if let Err(e) = result {
+            warn!(
+                error_message = format!("{:?}", e).as_str(),
+                job_id = job.id,
+                image_id = job.image_id,
+                attempts = job.attempts,
+                "Image job failed"
+            );
+            retry_image_job(conn, settings.jobs().image_processing(), job, &e).await?;
+        }
+    }
+
+    Ok(claimed)
+}
+
+/// Runs [`process_image_jobs`] every `image_processing.poll_interval` seconds,
+/// until the queue is drained
+pub async fn process_image_jobs_periodically(
+    conn: DatabaseConnection,
+    settings: Settings,
+    storage: StorageExt,
+) {
+    let mut interval = delayed_interval(Duration::from_secs(
+        *settings.jobs().image_processing().poll_interval(),
+    ));
+
+    loop {
+        interval.tick().await;
+
+        loop {
+            match process_image_jobs(&conn, &storage, &settings)
+                .instrument(info_span!("PROCESS_IMAGE_JOBS").or_current())
+                .await
+            {
+                Ok(0) => break,
+                Ok(processed) => info!(processed, "Processed image jobs"),
+                Err(e) => {
+                    error!(
+                        error_message = format!("{:?}", e).as_str(),
+                        "An error occured while processing image jobs"
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,42 @@
+pub mod files;
+pub mod images;
+pub mod objects;
+pub mod trash;
+
+use crate::{config::Settings, storage::StorageExt};
+use sea_orm::DatabaseConnection;
+use std::time::Duration;
+use tokio::time::{interval_at, Instant, Interval};
+
+/// Spawns the background jobs running alongside the HTTP server
+pub fn spawn_jobs(conn: DatabaseConnection, settings: Settings, storage: StorageExt) {
+    tokio::spawn(trash::purge_trash_periodically(
+        conn.clone(),
+        settings.clone(),
+        storage.clone(),
+    ));
+    tokio::spawn(objects::retry_object_deletions_periodically(
+        conn.clone(),
+        settings.clone(),
+        storage.clone(),
+    ));
+    tokio::spawn(objects::reconcile_image_objects_periodically(
+        conn.clone(),
+        settings.clone(),
+        storage.clone(),
+    ));
+    tokio::spawn(images::process_image_jobs_periodically(
+        conn.clone(),
+        settings.clone(),
+        storage.clone(),
+    ));
+    tokio::spawn(files::remove_expired_uploads_periodically(
+        conn, settings, storage,
+    ));
+}
+
+/// Ticks every `period`, starting one period from now so that jobs do not all
+/// run on each startup
+fn delayed_interval(period: Duration) -> Interval {
+    interval_at(Instant::now() + period, period)
+}
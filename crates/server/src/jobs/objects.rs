@@ -0,0 +1,295 @@
+use crate::{
+    config::{S3Buckets, Settings},
+    errors::{utils::MapApiError, ApiError},
+    jobs::delayed_interval,
+    services::image::services::{image_storage_keys, storage_key_with_format},
+    storage::{Storage, StorageExt},
+};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use entity::{
+    file, file_version, image,
+    image_profile::ImageFormat,
+    image_transform, image_variant,
+    object_deletion::{ActiveModel, Column, Entity},
+};
+use sea_orm::{
+    prelude::*, sea_query::OnConflict, ActiveValue::Set, Condition, ConnectionTrait,
+    DatabaseConnection, Iterable, QueryOrder, QuerySelect,
+};
+use std::{collections::HashSet, time::Duration};
+use tracing::{error, info, info_span, warn, Instrument};
+
+/// Number of queued deletions retried per run
+const RETRY_BATCH_SIZE: u64 = 100;
+
+/// Deletes the given objects from the storage. Objects that cannot be deleted
+/// are queued and retried by [`retry_object_deletions`].
+pub async fn delete_objects<C: ConnectionTrait>(
+    conn: &C,
+    storage: &dyn Storage,
+    bucket: &str,
+    keys: Vec<String>,
+) -> Result<(), ApiError> {
+    for key in keys {
+        if let Err(e) = delete_object(storage, bucket, &key).await {
+            warn!(
+                error_message = e.as_str(),
+                s3_bucket = bucket,
+                s3_key = key.as_str(),
+                "Cannot delete stored object, queuing it for retry"
+            );
+
+            Entity::insert(ActiveModel {
+                bucket: Set(bucket.to_owned()),
+                storage_key: Set(key),
+                last_error: Set(Some(e)),
+                ..Default::default()
+            })
+            .on_conflict(
+                OnConflict::columns([Column::Bucket, Column::StorageKey])
+                    .update_column(Column::LastError)
+                    .to_owned(),
+            )
+            .exec(conn)
+            .await
+            .map_api_err()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Retries the queued object deletions, returns the number of deleted objects
+pub async fn retry_object_deletions<C: ConnectionTrait>(
+    conn: &C,
+    storage: &dyn Storage,
+) -> Result<u64, ApiError> {
+    let queued = Entity::find()
+        .order_by_asc(Column::UpdatedAt)
+        .limit(RETRY_BATCH_SIZE)
+        .all(conn)
+        .await
+        .map_api_err()?;
+
+    let mut deleted = 0;
+    for model in queued {
+        match delete_object(storage, model.bucket(), model.storage_key()).await {
+            Ok(()) => {
+                model.delete(conn).await.map_api_err()?;
+                deleted += 1;
+            }
+            Err(e) => {
+                let attempts = model.attempts + 1;
+                let mut active_model: ActiveModel = model.into();
+                active_model.attempts = Set(attempts);
+                active_model.last_error = Set(Some(e));
+                active_model.update(conn).await.map_api_err()?;
+            }
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// Deletes the objects of the image bucket which are not referenced by any
+/// image, ignoring objects modified after `modified_before`. Returns the number
+/// of orphaned objects found.
+pub async fn reconcile_image_objects<C: ConnectionTrait>(
+    conn: &C,
+    storage: &dyn Storage,
+    buckets: &S3Buckets,
+    modified_before: DateTime<Utc>,
+) -> Result<u64, ApiError> {
+    let bucket = buckets.image();
+    // Files may be stored in the same bucket
+    let shared_with_files = buckets.file() == bucket;
+
+    let mut orphans = 0;
+    let mut continuation_token: Option<String> = None;
+
+    loop {
+        let page = storage
+            .list_objects(bucket, continuation_token.take())
+            .await
+            .map_err(|e| e.into_api_error("An error occured while listing stored objects"))?;
+
+        let candidates: Vec<String> = page
+            .objects
+            .into_iter()
+            .filter(|object| {
+                object
+                    .last_modified
+                    .map(|last_modified| last_modified < modified_before)
+                    .unwrap_or(false)
+            })
+            .map(|object| object.key)
+            .collect();
+
+        if !candidates.is_empty() {
+            // Alternate formats of an image are only recorded on its row, so
+            // every key it could have been stored under is looked up. Originals
+            // do not share the prefix of the renditions after a replace, the
+            // row may not be on this page.
+            let image_keys: HashSet<String> = candidates
+                .iter()
+                .flat_map(|key| {
+                    ImageFormat::iter().map(move |format| storage_key_with_format(key, format))
+                })
+                .chain(candidates.iter().cloned())
+                .collect();
+
+            let mut referenced: HashSet<String> = image::Entity::find()
+                .filter(
+                    Condition::any()
+                        .add(image::Column::StorageKey.is_in(image_keys))
+                        .add(image::Column::OriginalStorageKey.is_in(candidates.clone())),
+                )
+                .all(conn)
+                .await
+                .map_api_err()?
+                .iter()
+                .flat_map(image_storage_keys)
+                .collect();
+
+            referenced.extend(
+                image_variant::Entity::find()
+                    .select_only()
+                    .column(image_variant::Column::StorageKey)
+                    .filter(image_variant::Column::StorageKey.is_in(candidates.clone()))
+                    .into_tuple::<String>()
+                    .all(conn)
+                    .await
+                    .map_api_err()?,
+            );
+            referenced.extend(
+                image_transform::Entity::find()
+                    .select_only()
+                    .column(image_transform::Column::StorageKey)
+                    .filter(image_transform::Column::StorageKey.is_in(candidates.clone()))
+                    .into_tuple::<String>()
+                    .all(conn)
+                    .await
+                    .map_api_err()?,
+            );
+
+            if shared_with_files {
+                referenced.extend(
+                    file::Entity::find()
+                        .select_only()
+                        .column(file::Column::StorageKey)
+                        .filter(file::Column::StorageKey.is_in(candidates.clone()))
+                        .into_tuple::<String>()
+                        .all(conn)
+                        .await
+                        .map_api_err()?,
+                );
+                referenced.extend(
+                    file_version::Entity::find()
+                        .select_only()
+                        .column(file_version::Column::StorageKey)
+                        .filter(file_version::Column::StorageKey.is_in(candidates.clone()))
+                        .into_tuple::<String>()
+                        .all(conn)
+                        .await
+                        .map_api_err()?,
+                );
+            }
+
+            let orphaned_keys: Vec<String> = candidates
+                .into_iter()
+                .filter(|key| !referenced.contains(key))
+                .collect();
+
+            for key in &orphaned_keys {
+                info!(
+                    s3_bucket = bucket.as_str(),
+                    s3_key = key.as_str(),
+                    "Found orphaned object"
+                );
+            }
+
+            orphans += orphaned_keys.len() as u64;
+            delete_objects(conn, storage, bucket, orphaned_keys).await?;
+        }
+
+        continuation_token = page.continuation;
+        if continuation_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(orphans)
+}
+
+/// Runs [`retry_object_deletions`] every `object_cleanup.retry_interval`
+/// seconds
+pub async fn retry_object_deletions_periodically(
+    conn: DatabaseConnection,
+    settings: Settings,
+    storage: StorageExt,
+) {
+    let mut interval = delayed_interval(Duration::from_secs(
+        *settings.jobs().object_cleanup().retry_interval(),
+    ));
+
+    loop {
+        interval.tick().await;
+
+        match retry_object_deletions(&conn, storage.as_ref())
+            .instrument(info_span!("RETRY_OBJECT_DELETIONS").or_current())
+            .await
+        {
+            Ok(0) => {}
+            Ok(deleted) => info!(deleted, "Deleted queued objects"),
+            Err(e) => error!(
+                error_message = format!("{:?}", e).as_str(),
+                "An error occured while retrying object deletions"
+            ),
+        }
+    }
+}
+
+/// Runs [`reconcile_image_objects`] every `object_cleanup.reconcile_interval`
+/// seconds
+pub async fn reconcile_image_objects_periodically(
+    conn: DatabaseConnection,
+    settings: Settings,
+    storage: StorageExt,
+) {
+    let mut interval = delayed_interval(Duration::from_secs(
+        *settings.jobs().object_cleanup().reconcile_interval(),
+    ));
+
+    loop {
+        interval.tick().await;
+
+        let modified_before = Utc::now()
+            - ChronoDuration::seconds(
+                *settings.jobs().object_cleanup().orphan_grace_period() as i64
+            );
+
+        match reconcile_image_objects(
+            &conn,
+            storage.as_ref(),
+            settings.storage().s3().buckets(),
+            modified_before,
+        )
+        .instrument(info_span!("RECONCILE_IMAGE_OBJECTS").or_current())
+        .await
+        {
+            Ok(0) => {}
+            Ok(orphans) => info!(orphans, "Deleted orphaned image objects"),
+            Err(e) => error!(
+                error_message = format!("{:?}", e).as_str(),
+                "An error occured while reconciling image objects"
+            ),
+        }
+    }
+}
+
+async fn delete_object(storage: &dyn Storage, bucket: &str, key: &str) -> Result<(), String> {
+    storage
+        .delete_object(bucket, key)
+        .await
+        .map_err(|e| format!("{:?}", e))
+}
@@ -0,0 +1,159 @@
+use crate::{
+    config::{S3Buckets, Settings},
+    errors::{utils::MapApiError, ApiError},
+    jobs::{delayed_interval, objects::delete_objects},
+    services::image::services::image_storage_keys,
+    storage::{Storage, StorageExt},
+};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use entity::{blok, file, file_version, image, image_transform, image_variant, page, post};
+use sea_orm::{prelude::*, ConnectionTrait, DatabaseConnection};
+use std::time::Duration;
+use tracing::{error, info, info_span, Instrument};
+
+/// Runs [`purge_trash`] every `trash.purge_interval` seconds
+pub async fn purge_trash_periodically(
+    conn: DatabaseConnection,
+    settings: Settings,
+    storage: StorageExt,
+) {
+    let mut interval = delayed_interval(Duration::from_secs(
+        *settings.jobs().trash().purge_interval(),
+    ));
+
+    loop {
+        interval.tick().await;
+
+        let deleted_before =
+            Utc::now() - ChronoDuration::days((*settings.jobs().trash().retention_days()).into());
+
+        match purge_trash(
+            &conn,
+            storage.as_ref(),
+            settings.storage().s3().buckets(),
+            deleted_before,
+        )
+        .instrument(info_span!("PURGE_TRASH").or_current())
+        .await
+        {
+            Ok(0) => {}
+            Ok(purged) => info!(purged, "Purged trashed resources"),
+            Err(e) => error!(
+                error_message = format!("{:?}", e).as_str(),
+                "An error occured while purging trash"
+            ),
+        }
+    }
+}
+
+/// Permanently removes resources trashed before `deleted_before`, along with
+/// their stored objects. Returns the number of deleted rows.
+pub async fn purge_trash<C: ConnectionTrait>(
+    conn: &C,
+    storage: &dyn Storage,
+    buckets: &S3Buckets,
+    deleted_before: DateTime<Utc>,
+) -> Result<u64, ApiError> {
+    let mut purged = 0;
+
+    // Bloks of trashed pages are removed by the page foreign key cascade
+    purged += blok::Entity::delete_many()
+        .filter(blok::Column::DeletedAt.lt(deleted_before))
+        .exec(conn)
+        .await
+        .map_api_err()?
+        .rows_affected;
+
+    purged += page::Entity::delete_many()
+        .filter(page::Column::DeletedAt.lt(deleted_before))
+        .exec(conn)
+        .await
+        .map_api_err()?
+        .rows_affected;
+
+    purged += post::Entity::delete_many()
+        .filter(post::Column::DeletedAt.lt(deleted_before))
+        .exec(conn)
+        .await
+        .map_api_err()?
+        .rows_affected;
+
+    // Rows are deleted first so that purged resources are never served again,
+    // objects which cannot be deleted are queued for retry
+    let images = image::Entity::find()
+        .filter(image::Column::DeletedAt.lt(deleted_before))
+        .all(conn)
+        .await
+        .map_api_err()?;
+
+    if !images.is_empty() {
+        let image_ids: Vec<i32> = images.iter().map(|image| image.id).collect();
+
+        // Variant and transform rows are removed along with their image by
+        // the foreign key
+        let variants = image_variant::Entity::find()
+            .filter(image_variant::Column::ImageId.is_in(image_ids.clone()))
+            .all(conn)
+            .await
+            .map_api_err()?;
+        let transforms = image_transform::Entity::find()
+            .filter(image_transform::Column::ImageId.is_in(image_ids.clone()))
+            .all(conn)
+            .await
+            .map_api_err()?;
+
+        // Lazy images are trashed along with their image so both are purged
+        // here
+        purged += image::Entity::delete_many()
+            .filter(image::Column::Id.is_in(image_ids))
+            .exec(conn)
+            .await
+            .map_api_err()?
+            .rows_affected;
+
+        let keys = images
+            .into_iter()
+            .flat_map(|image| image_storage_keys(&image))
+            .chain(variants.into_iter().map(|variant| variant.storage_key))
+            .chain(
+                transforms
+                    .into_iter()
+                    .map(|transform| transform.storage_key),
+            )
+            .collect();
+        delete_objects(conn, storage, buckets.image(), keys).await?;
+    }
+
+    let files = file::Entity::find()
+        .filter(file::Column::DeletedAt.lt(deleted_before))
+        .all(conn)
+        .await
+        .map_api_err()?;
+
+    if !files.is_empty() {
+        let file_ids: Vec<i32> = files.iter().map(|file| file.id).collect();
+
+        // Version rows are removed along with their file by the foreign key
+        let versions = file_version::Entity::find()
+            .filter(file_version::Column::FileId.is_in(file_ids.clone()))
+            .all(conn)
+            .await
+            .map_api_err()?;
+
+        purged += file::Entity::delete_many()
+            .filter(file::Column::Id.is_in(file_ids))
+            .exec(conn)
+            .await
+            .map_api_err()?
+            .rows_affected;
+
+        let keys = files
+            .into_iter()
+            .map(|file| file.storage_key)
+            .chain(versions.into_iter().map(|version| version.storage_key))
+            .collect();
+        delete_objects(conn, storage, buckets.file(), keys).await?;
+    }
+
+    Ok(purged)
+}
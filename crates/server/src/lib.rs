@@ -3,9 +3,11 @@ use std::io;
 
 pub mod config;
 pub mod errors;
+pub mod jobs;
 pub mod middlewares;
 pub mod server;
 pub mod services;
+pub mod storage;
 pub mod telemetry;
 mod utils;
 
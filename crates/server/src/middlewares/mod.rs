@@ -1,2 +1,2 @@
 pub mod api_key;
-pub mod s3;
+pub mod storage;
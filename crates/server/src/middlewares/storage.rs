@@ -1,4 +1,4 @@
-use crate::config::SETTINGS;
+use crate::storage::StorageExt;
 use actix_web::{
     dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
     Error as ActixError, FromRequest, HttpMessage, HttpRequest,
@@ -9,27 +9,28 @@ use getset::Getters;
 use std::{
     future::{ready, Ready},
     rc::Rc,
-    sync::Arc,
 };
 
 #[derive(Constructor)]
-pub struct S3ProviderMiddlewareFactory;
+pub struct StorageProviderMiddlewareFactory {
+    storage: StorageExt,
+}
 
-impl<S, B> Transform<S, ServiceRequest> for S3ProviderMiddlewareFactory
+impl<S, B> Transform<S, ServiceRequest> for StorageProviderMiddlewareFactory
 where
     S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = ActixError> + 'static,
 {
     type Response = ServiceResponse<B>;
     type Error = ActixError;
-    type Transform = S3ProviderMiddleware<S>;
+    type Transform = StorageProviderMiddleware<S>;
     type InitError = ();
     type Future = LocalBoxFuture<'static, Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
+        let storage = self.storage.clone();
         async move {
-            let client = aws_sdk_s3::Client::from_conf((*SETTINGS).clone().into());
-            Ok(S3ProviderMiddleware {
-                client: Arc::new(client),
+            Ok(StorageProviderMiddleware {
+                storage,
                 service: Rc::new(service),
             })
         }
@@ -37,16 +38,14 @@ where
     }
 }
 
-pub type S3ClientExt = Arc<aws_sdk_s3::Client>;
-
 #[derive(Getters)]
 #[getset(get = "pub")]
-pub struct S3ProviderMiddleware<S> {
-    client: S3ClientExt,
+pub struct StorageProviderMiddleware<S> {
+    storage: StorageExt,
     service: Rc<S>,
 }
 
-impl<S, B> Service<ServiceRequest> for S3ProviderMiddleware<S>
+impl<S, B> Service<ServiceRequest> for StorageProviderMiddleware<S>
 where
     S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = ActixError> + 'static,
 {
@@ -58,9 +57,9 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let service = self.service.clone();
-        let client = self.client.clone();
+        let storage = self.storage.clone();
         async move {
-            req.extensions_mut().insert::<S3ClientExt>(client);
+            req.extensions_mut().insert::<StorageExt>(storage);
             let res = service.call(req).await?;
             Ok(res)
         }
@@ -68,22 +67,22 @@ where
     }
 }
 
-pub struct S3ClientProvider(S3ClientExt);
+pub struct StorageProvider(StorageExt);
 
-impl FromRequest for S3ClientProvider {
+impl FromRequest for StorageProvider {
     type Error = ActixError;
-    type Future = Ready<Result<S3ClientProvider, Self::Error>>;
+    type Future = Ready<Result<StorageProvider, Self::Error>>;
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
         let ext = req.extensions();
-        let s3_ext = ext.get::<S3ClientExt>().unwrap();
+        let storage_ext = ext.get::<StorageExt>().unwrap();
 
-        ready(Ok(S3ClientProvider(s3_ext.clone())))
+        ready(Ok(StorageProvider(storage_ext.clone())))
     }
 }
 
-impl S3ClientProvider {
-    pub fn provide(self) -> S3ClientExt {
+impl StorageProvider {
+    pub fn provide(self) -> StorageExt {
         self.0
     }
 }
@@ -1,8 +1,16 @@
-use crate::{config::Settings, services::api_services};
+use crate::{
+    config::{Settings, StorageDriver},
+    jobs::spawn_jobs,
+    services::api_services,
+    storage::{self, local::LocalStorage, routes::local_storage_service},
+};
 use actix_cors::Cors;
 use actix_web::{
     http::{
-        header::{HeaderName, CONTENT_DISPOSITION, CONTENT_TYPE, ORIGIN},
+        header::{
+            HeaderName, CONTENT_DISPOSITION, CONTENT_TYPE, ETAG, IF_MATCH, IF_MODIFIED_SINCE,
+            IF_NONE_MATCH, ORIGIN,
+        },
         Method,
     },
     web, App, HttpServer,
@@ -64,6 +72,19 @@ impl Server {
             settings: settings.clone(),
         };
 
+        let storage = storage::from_settings(settings);
+        // Objects of the local storage are served by the API itself
+        let local_storage = match settings.storage().driver() {
+            StorageDriver::Local => Some(LocalStorage::from_settings(settings)),
+            StorageDriver::S3 => None,
+        };
+
+        spawn_jobs(
+            self.database_connection.clone(),
+            settings.clone(),
+            storage.clone(),
+        );
+
         let server_addr = settings.server_addr();
 
         let cors_endpoints = settings.cors().clone();
@@ -82,17 +103,26 @@ impl Server {
                     ORIGIN,
                     CONTENT_TYPE,
                     CONTENT_DISPOSITION,
-                ]);
+                    IF_MATCH,
+                    IF_NONE_MATCH,
+                    IF_MODIFIED_SINCE,
+                ])
+                .expose_headers(&[ETAG]);
 
             for endpoint in &cors_endpoints {
                 cors = cors.allowed_origin(endpoint.as_ref());
             }
 
-            App::new()
+            let app = App::new()
                 .wrap(TracingLogger::default())
                 .wrap(cors)
                 .app_data(web::Data::new(app_state.clone()))
-                .service(api_services())
+                .service(api_services(storage.clone()));
+
+            match &local_storage {
+                Some(local_storage) => app.service(local_storage_service(local_storage.clone())),
+                None => app,
+            }
         })
         .bind(server_addr.clone())?
         .run();
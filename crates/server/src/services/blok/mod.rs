@@ -1,4 +1,6 @@
-use crate::services::blok::routes::{create_blok, delete_blok, get_blok, patch_blok, update_blok};
+use crate::services::blok::routes::{
+    create_blok, delete_blok, get_blok, patch_blok, restore_blok, update_blok,
+};
 use actix_web::{web::scope, Scope};
 
 pub(crate) mod models;
@@ -11,4 +13,5 @@ pub fn blok_service() -> Scope {
         .service(update_blok)
         .service(patch_blok)
         .service(delete_blok)
+        .service(restore_blok)
 }
@@ -1,3 +1,4 @@
+use crate::utils::http_cache::json_response_with_etag;
 use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
 use chrono::{DateTime, Utc};
 use entity::blok;
@@ -34,6 +35,6 @@ impl Responder for BlokOutput {
     type Body = BoxBody;
 
     fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
-        HttpResponse::Ok().json(self)
+        json_response_with_etag(&self)
     }
 }
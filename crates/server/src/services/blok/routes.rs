@@ -3,27 +3,83 @@ use crate::{
     errors::{utils::MapApiError, ApiError},
     middlewares::api_key::{ApiKey, WriteApiKey},
     server::AppState,
-    services::blok::models::BlokPatchInput,
-    utils::serde_json_patch::Patch::Value,
+    services::{
+        blok::models::BlokPatchInput,
+        media::{models::MediaReferenceKind, services::sync_media_references},
+    },
+    utils::{http_cache::check_if_match, serde_json_patch::Patch::Value},
 };
-use actix_web::{delete, get, patch, post, put, web, Error as ActixError, HttpResponse};
+use actix_web::{
+    delete, get, http::header::IfMatch, patch, post, put, web, Error as ActixError, HttpResponse,
+};
+use chrono::Utc;
 use entity::{
-    blok::{Column, Entity, Model},
+    blok::{ActiveModel, Column, Entity, Model},
     page::{Column as PageColumn, Entity as PageEntity},
 };
-use sea_orm::{prelude::*, ActiveValue::Set, IntoActiveModel, TryIntoModel};
+use sea_orm::{
+    prelude::*, sea_query::LockType, ActiveValue::Set, ConnectionTrait, DatabaseTransaction,
+    IntoActiveModel, QuerySelect, TransactionTrait, TryIntoModel,
+};
+
+/// Saves a blok along with the references of its props to media
+async fn save_blok<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+    blok: ActiveModel,
+) -> Result<Model, ApiError> {
+    let blok: Model = blok.save(conn).await.map_api_err()?.try_into_model()?;
+    sync_media_references(
+        conn,
+        namespace,
+        MediaReferenceKind::Blok,
+        blok.id,
+        &blok.props,
+    )
+    .await?;
+
+    Ok(blok)
+}
+
+/// Finds a live blok of the namespace and locks it until the end of the
+/// transaction, so that no other write can slip in after its precondition was
+/// checked
+async fn find_blok_for_update(
+    txn: &DatabaseTransaction,
+    namespace: &str,
+    id: i32,
+) -> Result<Model, ApiError> {
+    let mut query = Entity::find()
+        .find_also_related(PageEntity)
+        .filter(Column::Id.eq(id))
+        .filter(Column::DeletedAt.is_null())
+        .filter(PageColumn::DeletedAt.is_null());
+    // The page is on the nullable side of the join, which cannot be locked
+    QuerySelect::query(&mut query).lock_with_tables(LockType::Update, [Entity]);
+
+    query
+        .one(txn)
+        .await
+        .map_api_err()?
+        .and_then(|(blok, page)| page.map(|p| (blok, p)))
+        .filter(|(_, page)| page.namespace == namespace)
+        .map(|(blok, _)| blok)
+        .ok_or(ApiError::NotFound)
+}
 
 #[get("/{id}")]
 pub async fn get_blok(
     data: web::Data<AppState>,
     path_id: web::Path<i32>,
     api_key: ApiKey,
-) -> Result<HttpResponse, ActixError> {
+) -> Result<BlokOutput, ApiError> {
     let id = path_id.into_inner();
 
     let blok: Model = Entity::find()
         .find_also_related(PageEntity)
         .filter(Column::Id.eq(id))
+        .filter(Column::DeletedAt.is_null())
+        .filter(PageColumn::DeletedAt.is_null())
         .one(data.conn())
         .await
         .map_api_err()?
@@ -36,7 +92,7 @@ pub async fn get_blok(
         })
         .ok_or(ApiError::NotFound)?;
 
-    Ok(HttpResponse::Ok().json(BlokOutput::from(blok)))
+    Ok(blok.into())
 }
 
 #[post("")]
@@ -49,18 +105,18 @@ pub async fn create_blok(
 
     PageEntity::find()
         .filter(PageColumn::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(PageColumn::DeletedAt.is_null())
         .filter(PageColumn::Id.eq(*body.page_id()))
         .one(data.conn())
         .await
         .map_api_err()?
         .ok_or_else(|| ApiError::ReferenceNotFound("pageId".to_string()))?;
 
-    Ok(model
-        .save(data.conn())
-        .await
-        .map_api_err()?
-        .try_into_model()?
-        .into())
+    let txn = data.conn().begin().await.map_api_err()?;
+    let blok = save_blok(&txn, api_key.namespace(), model).await?;
+    txn.commit().await.map_api_err()?;
+
+    Ok(blok.into())
 }
 
 #[put("/{id}")]
@@ -69,28 +125,20 @@ pub async fn update_blok(
     path_id: web::Path<i32>,
     body: web::Json<BlokInput>,
     api_key: WriteApiKey,
+    if_match: Option<web::Header<IfMatch>>,
 ) -> Result<BlokOutput, ApiError> {
     let id = path_id.into_inner();
 
-    Entity::find()
-        .find_also_related(PageEntity)
-        .filter(Column::Id.eq(id))
-        .one(data.conn())
-        .await
-        .map_api_err()?
-        .and_then(|(blok, page)| page.map(|p| (blok, p)))
-        .and_then(|(blok, page)| {
-            if &page.namespace == api_key.namespace() {
-                return Some(blok);
-            }
-            None
-        })
-        .ok_or(ApiError::NotFound)?;
+    let txn = data.conn().begin().await.map_api_err()?;
+    let current = find_blok_for_update(&txn, api_key.namespace(), id).await?;
+
+    check_if_match(&if_match, &BlokOutput::from(current))?;
 
     PageEntity::find()
         .filter(PageColumn::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(PageColumn::DeletedAt.is_null())
         .filter(PageColumn::Id.eq(*body.page_id()))
-        .one(data.conn())
+        .one(&txn)
         .await
         .map_api_err()?
         .ok_or_else(|| ApiError::ReferenceNotFound("pageId".to_string()))?;
@@ -98,12 +146,10 @@ pub async fn update_blok(
     let mut model = body.active_model();
     model.id = Set(id);
 
-    Ok(model
-        .save(data.conn())
-        .await
-        .map_api_err()?
-        .try_into_model()?
-        .into())
+    let blok = save_blok(&txn, api_key.namespace(), model).await?;
+    txn.commit().await.map_api_err()?;
+
+    Ok(blok.into())
 }
 
 #[patch("/{id}")]
@@ -112,6 +158,7 @@ pub async fn patch_blok(
     path_id: web::Path<i32>,
     body: web::Json<BlokPatchInput>,
     api_key: WriteApiKey,
+    if_match: Option<web::Header<IfMatch>>,
 ) -> Result<BlokOutput, ApiError> {
     let id = path_id.into_inner();
 
@@ -139,27 +186,19 @@ pub async fn patch_blok(
         return Err(ApiError::PatchAtLeastOneField);
     }
 
-    let mut blok = Entity::find()
-        .find_also_related(PageEntity)
-        .filter(Column::Id.eq(id))
-        .one(data.conn())
-        .await
-        .map_api_err()?
-        .and_then(|(blok, page)| page.map(|p| (blok, p)))
-        .and_then(|(blok, page)| {
-            if &page.namespace == api_key.namespace() {
-                return Some(blok);
-            }
-            None
-        })
-        .ok_or(ApiError::NotFound)?
-        .into_active_model();
+    let txn = data.conn().begin().await.map_api_err()?;
+    let current = find_blok_for_update(&txn, api_key.namespace(), id).await?;
+
+    check_if_match(&if_match, &BlokOutput::from(current.clone()))?;
+
+    let mut blok = current.into_active_model();
 
     if let Value(page_id) = body.page_id() {
         PageEntity::find()
             .filter(PageColumn::Namespace.eq(api_key.namespace().to_owned()))
+            .filter(PageColumn::DeletedAt.is_null())
             .filter(PageColumn::Id.eq(*page_id))
-            .one(data.conn())
+            .one(&txn)
             .await
             .map_api_err()?
             .ok_or_else(|| ApiError::ReferenceNotFound("pageId".to_string()))?;
@@ -181,38 +220,61 @@ pub async fn patch_blok(
 
     blok.id = Set(id);
 
-    Ok(blok
-        .save(data.conn())
-        .await
-        .map_api_err()?
-        .try_into_model()?
-        .into())
+    let blok = save_blok(&txn, api_key.namespace(), blok).await?;
+    txn.commit().await.map_api_err()?;
+
+    Ok(blok.into())
 }
 
 #[delete("/{id}")]
 pub async fn delete_blok(
     data: web::Data<AppState>,
     path_id: web::Path<i32>,
-    api_key: ApiKey,
+    api_key: WriteApiKey,
+    if_match: Option<web::Header<IfMatch>>,
 ) -> Result<HttpResponse, ActixError> {
     let id = path_id.into_inner();
 
-    let blok = Entity::find()
+    let txn = data.conn().begin().await.map_api_err()?;
+    let blok = find_blok_for_update(&txn, api_key.namespace(), id).await?;
+
+    check_if_match(&if_match, &BlokOutput::from(blok.clone()))?;
+
+    let mut blok = blok.into_active_model();
+    blok.deleted_at = Set(Some(Utc::now()));
+
+    let blok = blok.update(&txn).await.map_api_err()?;
+    txn.commit().await.map_api_err()?;
+
+    Ok(HttpResponse::Ok().json(BlokOutput::from(blok)))
+}
+
+#[post("/{id}/restore")]
+pub async fn restore_blok(
+    data: web::Data<AppState>,
+    path_id: web::Path<i32>,
+    api_key: WriteApiKey,
+) -> Result<BlokOutput, ApiError> {
+    let id = path_id.into_inner();
+
+    let (blok, page) = Entity::find()
         .find_also_related(PageEntity)
         .filter(Column::Id.eq(id))
+        .filter(Column::DeletedAt.is_not_null())
         .one(data.conn())
         .await
         .map_api_err()?
         .and_then(|(blok, page)| page.map(|p| (blok, p)))
-        .and_then(|(blok, page)| {
-            if &page.namespace == api_key.namespace() {
-                return Some(blok);
-            }
-            None
-        })
+        .filter(|(_, page)| &page.namespace == api_key.namespace())
         .ok_or(ApiError::NotFound)?;
 
-    blok.clone().delete(data.conn()).await.map_api_err()?;
+    // The page must be restored first
+    if page.deleted_at.is_some() {
+        return Err(ApiError::ReferenceNotFound("pageId".to_string()));
+    }
+
+    let mut blok = blok.into_active_model();
+    blok.deleted_at = Set(None);
 
-    Ok(HttpResponse::Ok().json(BlokOutput::from(blok)))
+    Ok(blok.update(data.conn()).await.map_api_err()?.into())
 }
@@ -1,15 +1,30 @@
 use actix_web::{web::scope, Scope};
 
-use crate::services::files::routes::{create_file, delete_file, list_files, update_file};
+use crate::services::files::routes::{
+    abort_multipart_file, complete_multipart_file, confirm_file, create_file,
+    create_multipart_file, delete_file, download_file, list_file_tags, list_file_versions,
+    list_files, presign_file_parts, restore_file, restore_file_version, update_file,
+};
 
 pub mod models;
 pub mod repository;
 pub mod routes;
+pub mod services;
 
 pub fn file_service() -> Scope {
     scope("/file")
         .service(create_file)
         .service(list_files)
+        .service(list_file_tags)
         .service(update_file)
+        .service(confirm_file)
+        .service(download_file)
+        .service(list_file_versions)
+        .service(restore_file_version)
+        .service(create_multipart_file)
+        .service(presign_file_parts)
+        .service(complete_multipart_file)
+        .service(abort_multipart_file)
         .service(delete_file)
+        .service(restore_file)
 }
@@ -2,18 +2,43 @@ use std::collections::HashMap;
 
 use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
 use chrono::{DateTime, Utc};
-use entity::file::Model;
+use entity::{
+    file::{FileStatus, FileVisibility, Model},
+    file_version,
+};
 use getset::Getters;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::{errors::ApiError, storage::unhex};
+
 #[derive(Clone, Debug, Deserialize, Getters)]
 #[serde(rename_all = "camelCase")]
 #[getset(get = "pub")]
 pub struct FilePayload {
     pub content_type: Option<String>,
-    pub content_length: u32,
+    pub content_length: u64,
     pub file_name: String,
+    /// Hex encoded SHA-256 the uploaded object must match
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+impl FilePayload {
+    /// Declared SHA-256, lowercased once checked to be one
+    pub fn validated_sha256(&self) -> Result<Option<String>, ApiError> {
+        let Some(sha256) = &self.sha256 else {
+            return Ok(None);
+        };
+
+        match sha256.len() == 64 && unhex(sha256).is_some() {
+            true => Ok(Some(sha256.to_lowercase())),
+            false => Err(ApiError::InvalidField(
+                "file.sha256".to_string(),
+                "expected 64 hexadecimal characters".to_string(),
+            )),
+        }
+    }
 }
 
 #[derive(Deserialize, Clone, Getters)]
@@ -23,6 +48,8 @@ pub struct FileInput {
     pub file: FilePayload,
     pub tags: Vec<String>,
     pub metadata: HashMap<String, String>,
+    #[serde(default)]
+    pub visibility: FileVisibility,
 }
 
 #[derive(Serialize, Clone, Getters)]
@@ -31,8 +58,18 @@ pub struct FileInput {
 pub struct UploadFileOutput {
     pub id: i32,
     pub upload_url: Option<String>,
+    /// An uploaded file of the namespace has the declared SHA-256, it is
+    /// returned instead of creating another one
+    pub duplicate: bool,
+    pub sha256: Option<String>,
+    /// Pending files must be confirmed once uploaded, before this date
+    pub status: FileStatus,
+    pub upload_expires_at: Option<DateTime<Utc>>,
     pub key: String,
-    pub public_url: String,
+    /// Private files have no public URL, they are downloaded from presigned
+    /// URLs
+    pub public_url: Option<String>,
+    pub visibility: FileVisibility,
     pub tags: Vec<String>,
     pub metadata: Value,
     pub created_at: DateTime<Utc>,
@@ -47,19 +84,137 @@ impl Responder for UploadFileOutput {
     }
 }
 
+/// File uploaded in `part_count` parts of `part_size` bytes, the last one
+/// being smaller
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct MultipartUploadOutput {
+    pub id: i32,
+    pub status: FileStatus,
+    pub upload_expires_at: Option<DateTime<Utc>>,
+    pub part_size: u64,
+    pub part_count: u32,
+    pub key: String,
+    pub public_url: Option<String>,
+    pub visibility: FileVisibility,
+    pub tags: Vec<String>,
+    pub metadata: Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Responder for MultipartUploadOutput {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct MultipartPartsInput {
+    pub part_numbers: Vec<u32>,
+}
+
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct PartUploadOutput {
+    pub part_number: u32,
+    pub upload_url: String,
+}
+
+/// Requesting part URLs postpones the expiration of the upload, so that
+/// uploads can be resumed
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct MultipartPartsOutput {
+    pub parts: Vec<PartUploadOutput>,
+    pub upload_expires_at: Option<DateTime<Utc>>,
+}
+
+impl Responder for MultipartPartsOutput {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
+
+/// Part uploaded by the client along with the ETag returned by S3
+#[derive(Clone, Debug, Deserialize, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct CompletedPartInput {
+    pub part_number: u32,
+    pub etag: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct MultipartCompleteInput {
+    pub parts: Vec<CompletedPartInput>,
+}
+
+/// URL of the object of public files, `base_url` being the one of the bucket
+pub fn public_url(model: &Model, base_url: &str) -> Option<String> {
+    (*model.visibility() == FileVisibility::Public)
+        .then(|| format!("{}/{}", base_url, model.storage_key()))
+}
+
 #[derive(Serialize, Clone, Getters)]
 #[serde(rename_all = "camelCase")]
 #[getset(get = "pub")]
 pub struct FileOutput {
     pub id: i32,
     pub key: String,
-    pub public_url: String,
+    pub public_url: Option<String>,
+    pub visibility: FileVisibility,
+    pub status: FileStatus,
+    pub content_type: Option<String>,
+    pub content_length: Option<i64>,
+    pub sha256: Option<String>,
+    /// Media library folder, at the root when unset
+    pub folder_id: Option<i32>,
     pub tags: Vec<String>,
     pub metadata: Value,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl FileOutput {
+    pub fn from_model(model: &Model, base_url: &str) -> Self {
+        FileOutput {
+            id: *model.id(),
+            public_url: public_url(model, base_url),
+            key: model.storage_key().clone(),
+            visibility: *model.visibility(),
+            status: *model.status(),
+            content_type: model.content_type().clone(),
+            content_length: *model.content_length(),
+            sha256: model.sha256().clone(),
+            folder_id: *model.folder_id(),
+            tags: model.tags().to_owned(),
+            metadata: model.metadata().clone(),
+            created_at: *model.created_at(),
+            updated_at: *model.updated_at(),
+        }
+    }
+}
+
+impl Responder for FileOutput {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
+
 pub struct FileOutputList(Vec<FileOutput>);
 
 impl Responder for FileOutputList {
@@ -78,28 +233,153 @@ impl IntoFileOutputList for Vec<Model> {
     fn into_file_output_list(self, base_url: &str) -> FileOutputList {
         let files = self
             .iter()
-            .map(|model| {
-                let public_url = format!("{}/{}", base_url, model.storage_key());
-                FileOutput {
-                    id: *model.id(),
-                    public_url,
-                    key: model.storage_key().clone(),
-                    tags: model.tags().to_owned(),
-                    metadata: model.metadata().clone(),
-                    created_at: *model.created_at(),
-                    updated_at: *model.updated_at(),
-                }
-            })
+            .map(|model| FileOutput::from_model(model, base_url))
             .collect();
 
         FileOutputList(files)
     }
 }
 
+/// Previous content of a file, restorable until it is replaced by newer
+/// versions
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct FileVersionOutput {
+    pub id: i32,
+    pub key: String,
+    pub public_url: Option<String>,
+    pub content_type: Option<String>,
+    pub content_length: Option<i64>,
+    pub sha256: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FileVersionOutput {
+    pub fn from_model(version: &file_version::Model, file: &Model, base_url: &str) -> Self {
+        FileVersionOutput {
+            id: *version.id(),
+            key: version.storage_key().clone(),
+            public_url: (*file.visibility() == FileVisibility::Public)
+                .then(|| format!("{}/{}", base_url, version.storage_key())),
+            content_type: version.content_type().clone(),
+            content_length: *version.content_length(),
+            sha256: version.sha256().clone(),
+            created_at: *version.created_at(),
+        }
+    }
+}
+
+pub struct FileVersionOutputList(Vec<FileVersionOutput>);
+
+impl Responder for FileVersionOutputList {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self.0)
+    }
+}
+
+impl FileVersionOutputList {
+    pub fn from_models(versions: &[file_version::Model], file: &Model, base_url: &str) -> Self {
+        FileVersionOutputList(
+            versions
+                .iter()
+                .map(|version| FileVersionOutput::from_model(version, file, base_url))
+                .collect(),
+        )
+    }
+}
+
+/// Whether listed files must have any or all of the requested tags
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TagMatch {
+    #[default]
+    Any,
+    All,
+}
+
 #[derive(Debug, Deserialize, Getters)]
+#[serde(rename_all = "camelCase")]
 #[getset(get = "pub")]
 pub struct FileFilter {
     tag: Option<String>,
+    /// Comma separated tags, e.g. `events,2023`
+    tags: Option<String>,
+    #[serde(default)]
+    tag_match: TagMatch,
+    /// Comma separated `key:value` pairs the metadata must contain, or keys
+    /// it must have, e.g. `author:jane,draft`
+    metadata: Option<String>,
+    /// A content type, or a type followed by `/*`, e.g. `image/*`
+    content_type: Option<String>,
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
+    updated_after: Option<DateTime<Utc>>,
+    updated_before: Option<DateTime<Utc>>,
+    /// Searched in file names, case insensitively
+    search: Option<String>,
+}
+
+impl FileFilter {
+    /// Tags given with `tag` and `tags`
+    pub fn tag_list(&self) -> Vec<String> {
+        self.tag
+            .iter()
+            .chain(self.tags.iter())
+            .flat_map(|tags| tags.split(','))
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Metadata keys along with the value they must have, if any
+    pub fn metadata_filters(&self) -> Result<Vec<(String, Option<String>)>, ApiError> {
+        let Some(metadata) = &self.metadata else {
+            return Ok(Vec::new());
+        };
+
+        metadata
+            .split(',')
+            .filter(|filter| !filter.trim().is_empty())
+            .map(|filter| {
+                let (key, value) = match filter.split_once(':') {
+                    Some((key, value)) => (key.trim(), Some(value.to_string())),
+                    None => (filter.trim(), None),
+                };
+
+                if key.is_empty() {
+                    return Err(ApiError::InvalidField(
+                        "metadata".to_string(),
+                        format!("missing key in filter \"{filter}\""),
+                    ));
+                }
+
+                Ok((key.to_string(), value))
+            })
+            .collect()
+    }
+}
+
+/// Number of listed files having a tag
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct FileTagOutput {
+    pub tag: String,
+    pub count: i64,
+}
+
+pub struct FileTagOutputList(pub Vec<FileTagOutput>);
+
+impl Responder for FileTagOutputList {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self.0)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Getters)]
@@ -108,6 +388,25 @@ pub struct FileUpdateInput {
     pub file: Option<FilePayload>,
     pub tags: Option<Vec<String>>,
     pub metadata: Option<HashMap<String, String>>,
+    pub visibility: Option<FileVisibility>,
+}
+
+/// Presigned URL a file can be downloaded from until `expires_at`
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct FileDownloadOutput {
+    pub id: i32,
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Responder for FileDownloadOutput {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
 }
 
 #[derive(Debug, Serialize, Getters)]
@@ -1,35 +1,75 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
+use chrono::{Duration, Utc};
 use sea_orm::{
     prelude::*, sea_query::Expr, ActiveModelTrait, ActiveValue::Set, ConnectionTrait, QueryOrder,
+    QuerySelect,
 };
 use serde_json::{Map, Value};
 use uuid::Uuid;
 
-use entity::file::{ActiveModel, Column, Entity, Model};
+use entity::{
+    file::{ActiveModel, Column, Entity, FileStatus, FileVisibility, Model},
+    file_version,
+};
 use migration::Order;
 
 use crate::{
     errors::{utils::MapApiError, ApiError},
-    services::files::models::{FileInput, FileUpdateInput},
+    services::files::models::{FileFilter, FileInput, FilePayload, FileUpdateInput, TagMatch},
 };
 
 #[async_trait]
 pub trait FilesRepository {
-    async fn create_file(&self, namespace: &str, file: FileInput) -> Result<Model, ApiError>;
-    async fn find_files_by_tag(
+    async fn create_file(
+        &self,
+        namespace: &str,
+        file: FileInput,
+        url_ttl: u64,
+    ) -> Result<Model, ApiError>;
+    async fn find_files(
         &self,
         namespace: &str,
-        tag: &Option<String>,
+        filter: &FileFilter,
     ) -> Result<Vec<Model>, ApiError>;
+    async fn count_file_tags(&self, namespace: &str) -> Result<Vec<(String, i64)>, ApiError>;
+    async fn find_file(&self, namespace: &str, id: &i32) -> Result<Model, ApiError>;
+    async fn find_file_by_sha256(
+        &self,
+        namespace: &str,
+        sha256: &str,
+        visibility: FileVisibility,
+    ) -> Result<Option<Model>, ApiError>;
+    async fn confirm_file_upload(&self, model: Model) -> Result<Model, ApiError>;
+    async fn set_file_upload_id(&self, model: Model, upload_id: String) -> Result<Model, ApiError>;
+    async fn extend_file_upload(&self, model: Model, url_ttl: u64) -> Result<Model, ApiError>;
+    async fn remove_pending_file(&self, model: Model) -> Result<(), ApiError>;
     async fn update_file(
         &self,
         namespace: &str,
         id: &i32,
         payload: FileUpdateInput,
+        url_ttl: u64,
+    ) -> Result<Model, ApiError>;
+    async fn find_file_versions(&self, file_id: i32) -> Result<Vec<file_version::Model>, ApiError>;
+    async fn find_file_version(
+        &self,
+        file_id: i32,
+        id: i32,
+    ) -> Result<file_version::Model, ApiError>;
+    async fn archive_file_object(
+        &self,
+        previous: &Model,
+        max_versions: u32,
+    ) -> Result<Vec<String>, ApiError>;
+    async fn restore_file_version(
+        &self,
+        model: Model,
+        version: file_version::Model,
     ) -> Result<Model, ApiError>;
     async fn delete_file(&self, namespace: &str, id: &i32) -> Result<Model, ApiError>;
+    async fn restore_file(&self, namespace: &str, id: &i32) -> Result<Model, ApiError>;
 }
 
 /// Converts an HashMap to a serde_json::Value
@@ -43,39 +83,123 @@ fn string_map_to_json(map: &HashMap<String, String>) -> Value {
     Value::Object(value)
 }
 
+/// Files wait for their object to be uploaded and confirmed until their
+/// upload URL expires
+fn set_pending_upload(active_model: &mut ActiveModel, file: &FilePayload, url_ttl: u64) {
+    active_model.status = Set(FileStatus::Pending);
+    active_model.content_type = Set(file.content_type().clone());
+    active_model.content_length = Set(Some(*file.content_length() as i64));
+    active_model.sha256 = Set(file.sha256().as_ref().map(|sha256| sha256.to_lowercase()));
+    active_model.upload_expires_at = Set(Some(Utc::now() + Duration::seconds(url_ttl as i64)));
+    active_model.upload_id = Set(None);
+}
+
 #[async_trait]
 impl<T: ConnectionTrait> FilesRepository for T {
-    async fn create_file(&self, namespace: &str, input: FileInput) -> Result<Model, ApiError> {
+    async fn create_file(
+        &self,
+        namespace: &str,
+        input: FileInput,
+        url_ttl: u64,
+    ) -> Result<Model, ApiError> {
         let storage_key = format!(
             "{}_{}",
             Uuid::new_v4().to_string().replace('-', ""),
             input.file().file_name()
         );
-        let file_model = ActiveModel {
+        let mut file_model = ActiveModel {
             namespace: Set(namespace.to_owned()),
             storage_key: Set(storage_key),
             tags: Set(input.tags().to_owned()),
             metadata: Set(string_map_to_json(input.metadata())),
+            visibility: Set(*input.visibility()),
             ..Default::default()
         };
+        set_pending_upload(&mut file_model, input.file(), url_ttl);
 
         let inserted_file = file_model.insert(self).await.map_api_err()?;
         Ok(inserted_file)
     }
 
-    async fn find_files_by_tag(
+    async fn find_files(
         &self,
         namespace: &str,
-        tag: &Option<String>,
+        filter: &FileFilter,
     ) -> Result<Vec<Model>, ApiError> {
         let mut query = Entity::find()
             .filter(Column::Namespace.eq(namespace.to_owned()))
+            .filter(Column::DeletedAt.is_null())
+            .filter(Column::Status.eq(FileStatus::Uploaded))
             .order_by(Column::CreatedAt, Order::Desc);
 
-        if let Some(t) = tag {
+        let tags = filter.tag_list();
+        if !tags.is_empty() {
+            let operator = match filter.tag_match() {
+                TagMatch::Any => "&&",
+                TagMatch::All => "@>",
+            };
             query = query.filter(Expr::cust_with_values(
-                r#"$1 = any(tags)"#,
-                vec![t.to_string()],
+                format!("tags {operator} $1").as_str(),
+                vec![tags],
+            ));
+        }
+
+        let mut contained = Map::new();
+        for (key, value) in filter.metadata_filters()? {
+            match value {
+                Some(value) => {
+                    contained.insert(key, Value::String(value));
+                }
+                None => {
+                    query = query.filter(Expr::cust_with_values(
+                        r#"jsonb_exists(metadata, $1)"#,
+                        vec![key],
+                    ));
+                }
+            }
+        }
+        if !contained.is_empty() {
+            query = query.filter(Expr::cust_with_values(
+                r#"metadata @> $1"#,
+                vec![Value::Object(contained)],
+            ));
+        }
+
+        if let Some(content_type) = filter.content_type() {
+            query = match content_type.strip_suffix("/*") {
+                Some(main_type) => {
+                    query.filter(Column::ContentType.starts_with(&format!("{main_type}/")))
+                }
+                None => query.filter(Column::ContentType.eq(content_type.to_owned())),
+            };
+        }
+
+        if let Some(created_after) = filter.created_after() {
+            query = query.filter(Column::CreatedAt.gt(*created_after));
+        }
+        if let Some(created_before) = filter.created_before() {
+            query = query.filter(Column::CreatedAt.lt(*created_before));
+        }
+        if let Some(updated_after) = filter.updated_after() {
+            query = query.filter(Column::UpdatedAt.gt(*updated_after));
+        }
+        if let Some(updated_before) = filter.updated_before() {
+            query = query.filter(Column::UpdatedAt.lt(*updated_before));
+        }
+
+        // Storage keys are the file name prefixed with an UUID and an
+        // underscore
+        if let Some(search) = filter.search() {
+            let pattern = format!(
+                "%{}%",
+                search
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_")
+            );
+            query = query.filter(Expr::cust_with_values(
+                r#"substring(storage_key from position('_' in storage_key) + 1) ilike $1"#,
+                vec![pattern],
             ));
         }
 
@@ -84,14 +208,102 @@ impl<T: ConnectionTrait> FilesRepository for T {
         Ok(files)
     }
 
+    async fn count_file_tags(&self, namespace: &str) -> Result<Vec<(String, i64)>, ApiError> {
+        let tags = Entity::find()
+            .select_only()
+            .column_as(Expr::cust("unnest(tags)"), "tag")
+            .column_as(Expr::cust("count(*)"), "count")
+            .filter(Column::Namespace.eq(namespace.to_owned()))
+            .filter(Column::DeletedAt.is_null())
+            .filter(Column::Status.eq(FileStatus::Uploaded))
+            .group_by(Expr::cust("tag"))
+            .order_by(Expr::cust("count"), Order::Desc)
+            .order_by(Expr::cust("tag"), Order::Asc)
+            .into_tuple::<(String, i64)>()
+            .all(self)
+            .await
+            .map_api_err()?;
+
+        Ok(tags)
+    }
+
+    async fn find_file(&self, namespace: &str, id: &i32) -> Result<Model, ApiError> {
+        Entity::find()
+            .filter(Column::Namespace.eq(namespace.to_owned()))
+            .filter(Column::DeletedAt.is_null())
+            .filter(Column::Id.eq(id.to_owned()))
+            .one(self)
+            .await?
+            .ok_or(ApiError::NotFound)
+    }
+
+    async fn find_file_by_sha256(
+        &self,
+        namespace: &str,
+        sha256: &str,
+        visibility: FileVisibility,
+    ) -> Result<Option<Model>, ApiError> {
+        let file = Entity::find()
+            .filter(Column::Namespace.eq(namespace.to_owned()))
+            .filter(Column::DeletedAt.is_null())
+            .filter(Column::Status.eq(FileStatus::Uploaded))
+            .filter(Column::Sha256.eq(sha256.to_lowercase()))
+            .filter(Column::Visibility.eq(visibility))
+            .order_by(Column::CreatedAt, Order::Asc)
+            .one(self)
+            .await
+            .map_api_err()?;
+
+        Ok(file)
+    }
+
+    async fn confirm_file_upload(&self, model: Model) -> Result<Model, ApiError> {
+        let mut active_model: ActiveModel = model.into();
+        active_model.status = Set(FileStatus::Uploaded);
+        active_model.upload_expires_at = Set(None);
+        active_model.upload_id = Set(None);
+
+        let model = active_model.update(self).await?;
+        Ok(model)
+    }
+
+    async fn set_file_upload_id(&self, model: Model, upload_id: String) -> Result<Model, ApiError> {
+        let mut active_model: ActiveModel = model.into();
+        active_model.upload_id = Set(Some(upload_id));
+
+        let model = active_model.update(self).await?;
+        Ok(model)
+    }
+
+    async fn extend_file_upload(&self, model: Model, url_ttl: u64) -> Result<Model, ApiError> {
+        let mut active_model: ActiveModel = model.into();
+        active_model.upload_expires_at = Set(Some(Utc::now() + Duration::seconds(url_ttl as i64)));
+
+        let model = active_model.update(self).await?;
+        Ok(model)
+    }
+
+    async fn remove_pending_file(&self, model: Model) -> Result<(), ApiError> {
+        Entity::delete_many()
+            .filter(Column::Id.eq(model.id))
+            .filter(Column::Status.eq(FileStatus::Pending))
+            .exec(self)
+            .await
+            .map_api_err()?;
+
+        Ok(())
+    }
+
     async fn update_file(
         &self,
         namespace: &str,
         id: &i32,
         payload: FileUpdateInput,
+        url_ttl: u64,
     ) -> Result<Model, ApiError> {
         let mut active_model: ActiveModel = Entity::find()
             .filter(Column::Namespace.eq(namespace.to_owned()))
+            .filter(Column::DeletedAt.is_null())
             .filter(Column::Id.eq(id.to_owned()))
             .one(self)
             .await?
@@ -106,6 +318,10 @@ impl<T: ConnectionTrait> FilesRepository for T {
             active_model.metadata = Set(string_map_to_json(metadata));
         }
 
+        if let Some(visibility) = payload.visibility() {
+            active_model.visibility = Set(*visibility);
+        }
+
         if let Some(file) = payload.file() {
             let storage_key = format!(
                 "{}_{}",
@@ -113,6 +329,7 @@ impl<T: ConnectionTrait> FilesRepository for T {
                 file.file_name()
             );
             active_model.storage_key = Set(storage_key);
+            set_pending_upload(&mut active_model, file, url_ttl);
         }
 
         let model = active_model.update(self).await?;
@@ -120,15 +337,132 @@ impl<T: ConnectionTrait> FilesRepository for T {
         Ok(model)
     }
 
+    async fn find_file_versions(&self, file_id: i32) -> Result<Vec<file_version::Model>, ApiError> {
+        let versions = file_version::Entity::find()
+            .filter(file_version::Column::FileId.eq(file_id))
+            .order_by(file_version::Column::Id, Order::Desc)
+            .all(self)
+            .await
+            .map_api_err()?;
+
+        Ok(versions)
+    }
+
+    async fn find_file_version(
+        &self,
+        file_id: i32,
+        id: i32,
+    ) -> Result<file_version::Model, ApiError> {
+        file_version::Entity::find()
+            .filter(file_version::Column::FileId.eq(file_id))
+            .filter(file_version::Column::Id.eq(id))
+            .one(self)
+            .await?
+            .ok_or(ApiError::NotFound)
+    }
+
+    /// Keeps the object `previous` pointed to as a version of the file, unless
+    /// it was never confirmed, and drops the versions exceeding
+    /// `max_versions`. Returns the keys of the objects no longer referenced.
+    async fn archive_file_object(
+        &self,
+        previous: &Model,
+        max_versions: u32,
+    ) -> Result<Vec<String>, ApiError> {
+        if *previous.status() != FileStatus::Uploaded {
+            return Ok(vec![previous.storage_key().clone()]);
+        }
+
+        file_version::ActiveModel {
+            file_id: Set(previous.id),
+            storage_key: Set(previous.storage_key().clone()),
+            content_type: Set(previous.content_type().clone()),
+            content_length: Set(*previous.content_length()),
+            sha256: Set(previous.sha256().clone()),
+            ..Default::default()
+        }
+        .insert(self)
+        .await
+        .map_api_err()?;
+
+        let outdated = file_version::Entity::find()
+            .filter(file_version::Column::FileId.eq(previous.id))
+            .order_by(file_version::Column::Id, Order::Desc)
+            .offset(max_versions as u64)
+            .all(self)
+            .await
+            .map_api_err()?;
+
+        if !outdated.is_empty() {
+            file_version::Entity::delete_many()
+                .filter(file_version::Column::Id.is_in(outdated.iter().map(|version| version.id)))
+                .exec(self)
+                .await
+                .map_api_err()?;
+        }
+
+        Ok(outdated
+            .into_iter()
+            .map(|version| version.storage_key)
+            .collect())
+    }
+
+    /// Points the file back to the object of `version`, which is no longer a
+    /// version. The replaced object must be archived with
+    /// [`FilesRepository::archive_file_object`].
+    async fn restore_file_version(
+        &self,
+        model: Model,
+        version: file_version::Model,
+    ) -> Result<Model, ApiError> {
+        let mut active_model: ActiveModel = model.into();
+        active_model.storage_key = Set(version.storage_key.clone());
+        active_model.content_type = Set(version.content_type.clone());
+        active_model.content_length = Set(version.content_length);
+        active_model.sha256 = Set(version.sha256.clone());
+        active_model.status = Set(FileStatus::Uploaded);
+        active_model.upload_expires_at = Set(None);
+        active_model.upload_id = Set(None);
+
+        file_version::Entity::delete_by_id(version.id)
+            .exec(self)
+            .await
+            .map_api_err()?;
+
+        let model = active_model.update(self).await?;
+        Ok(model)
+    }
+
     async fn delete_file(&self, namespace: &str, id: &i32) -> Result<Model, ApiError> {
-        let model = Entity::find()
+        let mut active_model: ActiveModel = Entity::find()
+            .filter(Column::Namespace.eq(namespace.to_owned()))
+            .filter(Column::DeletedAt.is_null())
+            .filter(Column::Id.eq(id.to_owned()))
+            .one(self)
+            .await?
+            .ok_or(ApiError::NotFound)?
+            .into();
+
+        // The stored object is removed when the file is purged from the trash
+        active_model.deleted_at = Set(Some(Utc::now()));
+
+        let model = active_model.update(self).await?;
+        Ok(model)
+    }
+
+    async fn restore_file(&self, namespace: &str, id: &i32) -> Result<Model, ApiError> {
+        let mut active_model: ActiveModel = Entity::find()
             .filter(Column::Namespace.eq(namespace.to_owned()))
+            .filter(Column::DeletedAt.is_not_null())
             .filter(Column::Id.eq(id.to_owned()))
             .one(self)
             .await?
-            .ok_or(ApiError::NotFound)?;
+            .ok_or(ApiError::NotFound)?
+            .into();
+
+        active_model.deleted_at = Set(None);
 
-        model.clone().delete(self).await?;
+        let model = active_model.update(self).await?;
         Ok(model)
     }
 }
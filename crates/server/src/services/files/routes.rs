@@ -1,86 +1,181 @@
-use std::time::Duration;
-
 use actix_web::{delete, get, post, put, web};
-use aws_sdk_s3::{model::ObjectCannedAcl::PublicRead, presigning::config::PresigningConfig};
+use chrono::{Duration, Utc};
+use entity::file::{FileStatus, Model};
+use sea_orm::{DatabaseConnection, TransactionTrait};
+use tracing::warn;
 
 use crate::{
-    errors::ApiError,
+    config::Settings,
+    errors::{utils::MapApiError, ApiError},
+    jobs::objects::delete_objects,
     middlewares::{
         api_key::{ApiKey, WriteApiKey},
-        s3::S3ClientProvider,
+        storage::StorageProvider,
     },
     server::AppState,
-    services::files::{
-        models::{
-            FileDeleteResponse, FileFilter, FileInput, FileOutputList, FileUpdateInput,
-            IntoFileOutputList, UploadFileOutput,
+    services::{
+        files::{
+            models::{
+                public_url, FileDeleteResponse, FileDownloadOutput, FileFilter, FileInput,
+                FileOutput, FileOutputList, FilePayload, FileTagOutput, FileTagOutputList,
+                FileUpdateInput, FileVersionOutputList, IntoFileOutputList, MultipartCompleteInput,
+                MultipartPartsInput, MultipartPartsOutput, MultipartUploadOutput, PartUploadOutput,
+                UploadFileOutput,
+            },
+            repository::FilesRepository,
+            services::{
+                abort_multipart_upload, check_uploaded_object, complete_multipart_upload,
+                create_multipart_upload, multipart_part_count, multipart_part_size,
+                presign_download, presign_upload, presign_upload_part, set_object_visibility,
+                MAX_SINGLE_UPLOAD_SIZE,
+            },
+        },
+        media::{
+            models::{MediaDeleteQuery, MediaType},
+            services::check_media_unused,
         },
-        repository::FilesRepository,
+        usage::services::check_quota,
     },
+    storage::Storage,
 };
 
+/// Most part URLs presigned per request
+const MAX_PART_URLS: usize = 1000;
+
+fn check_file_size(
+    settings: &Settings,
+    namespace: &str,
+    file: &FilePayload,
+) -> Result<(), ApiError> {
+    let max_file_size = settings.storage().upload().file().max_size_for(namespace);
+    if *file.content_length() > max_file_size {
+        return Err(ApiError::FileTooBig(max_file_size, *file.content_length()));
+    }
+
+    Ok(())
+}
+
+fn upload_file_output(
+    settings: &Settings,
+    model: Model,
+    upload_url: Option<String>,
+    duplicate: bool,
+) -> UploadFileOutput {
+    let s3_base_url = format!(
+        "{}/{}",
+        settings.storage_base_url(),
+        settings.storage().s3().buckets().file()
+    );
+
+    UploadFileOutput {
+        id: model.id,
+        upload_url,
+        duplicate,
+        public_url: public_url(&model, s3_base_url.as_str()),
+        sha256: model.sha256,
+        status: model.status,
+        upload_expires_at: model.upload_expires_at,
+        key: model.storage_key,
+        tags: model.tags,
+        metadata: model.metadata,
+        visibility: model.visibility,
+        created_at: model.created_at,
+        updated_at: model.updated_at,
+    }
+}
+
+/// Removes the objects of a replaced content which are no longer referenced,
+/// `previous` being the file before it was replaced
+async fn discard_replaced_objects(
+    conn: &DatabaseConnection,
+    storage: &dyn Storage,
+    bucket: &str,
+    previous: &Model,
+    keys: Vec<String>,
+) -> Result<(), ApiError> {
+    if let Some(upload_id) = previous.upload_id() {
+        if let Err(e) =
+            abort_multipart_upload(storage, bucket, previous.storage_key(), upload_id).await
+        {
+            warn!(
+                error_message = format!("{:?}", e).as_str(),
+                s3_key = previous.storage_key().as_str(),
+                "Cannot abort multipart upload of replaced file"
+            );
+        }
+    }
+
+    // Objects which cannot be deleted are queued for retry
+    delete_objects(conn, storage, bucket, keys).await
+}
+
+/// Files are uploaded with a single PUT up to the S3 limit
+fn check_single_upload_size(file: &FilePayload) -> Result<(), ApiError> {
+    if *file.content_length() > MAX_SINGLE_UPLOAD_SIZE {
+        return Err(ApiError::InvalidField(
+            "file.contentLength".to_string(),
+            "files larger than 5 GB must be uploaded with a multipart upload".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
 #[post("")]
 pub async fn create_file(
     data: web::Data<AppState>,
     api_key: WriteApiKey,
-    s3_provider: S3ClientProvider,
+    storage_provider: StorageProvider,
     input: web::Json<FileInput>,
 ) -> Result<UploadFileOutput, ApiError> {
-    let maybe_content_type = input.file().content_type.clone();
-    let content_length = *input.file().content_length();
-    let max_file_size = 50_000_000_u32;
-    if content_length > max_file_size {
-        return Err(ApiError::FileTooBig(max_file_size, content_length));
+    let settings = data.settings();
+    let namespace = api_key.namespace().as_str();
+    check_file_size(settings, namespace, input.file())?;
+    check_single_upload_size(input.file())?;
+    input.file().validated_sha256()?;
+
+    // Contents are stored once per namespace and visibility, a private file
+    // is never answered with a public one
+    if let Some(sha256) = input.file().sha256() {
+        if let Some(model) = data
+            .conn()
+            .find_file_by_sha256(namespace, sha256, *input.visibility())
+            .await?
+        {
+            return Ok(upload_file_output(settings, model, None, true));
+        }
     }
+    check_quota(
+        data.conn(),
+        settings.storage().quota(),
+        namespace,
+        *input.file().content_length(),
+    )
+    .await?;
 
+    let input = input.into_inner();
+    let url_ttl = settings.storage().upload().file().url_ttl_for(namespace);
     let model = data
         .conn()
-        .create_file(api_key.namespace().as_str(), input.into_inner())
+        .create_file(namespace, input.clone(), url_ttl)
         .await?;
 
-    let settings = data.settings();
-    let s3_client = s3_provider.provide();
-
-    let mut s3_request = s3_client
-        .put_object()
-        .content_length(content_length.into())
-        .bucket(settings.s3().buckets().file())
-        .key(model.storage_key())
-        .acl(PublicRead);
-
-    if let Some(content_type) = maybe_content_type {
-        s3_request = s3_request.content_type(content_type)
-    }
-
-    let presigned_url = s3_request
-        .presigned(
-            PresigningConfig::builder()
-                .expires_in(Duration::from_secs(180))
-                .build()
-                .unwrap(),
-        )
-        .await
-        .map_err(|_e| ApiError::InternalServerError)?
-        .uri()
-        .to_string();
-
-    let public_url = format!(
-        "{}/{}/{}",
-        settings.s3().base_url(),
-        settings.s3().buckets().file(),
-        model.storage_key()
-    );
+    let presigned_url = presign_upload(
+        storage_provider.provide().as_ref(),
+        settings.storage().s3().buckets().file(),
+        model.storage_key(),
+        input.file(),
+        *model.visibility(),
+        url_ttl,
+    )
+    .await?;
 
-    Ok(UploadFileOutput {
-        id: model.id,
-        upload_url: Some(presigned_url),
-        key: model.storage_key,
-        tags: model.tags,
-        metadata: model.metadata,
-        public_url,
-        created_at: model.created_at,
-        updated_at: model.updated_at,
-    })
+    Ok(upload_file_output(
+        settings,
+        model,
+        Some(presigned_url),
+        false,
+    ))
 }
 
 #[get("")]
@@ -91,107 +186,513 @@ pub async fn list_files(
 ) -> Result<FileOutputList, ApiError> {
     let files = data
         .conn()
-        .find_files_by_tag(api_key.namespace().as_str(), filter.tag())
+        .find_files(api_key.namespace().as_str(), &filter)
         .await?;
 
-    let s3_settings = data.settings().s3();
+    let s3_settings = data.settings().storage().s3();
     let s3_base_url = format!(
         "{}/{}",
-        s3_settings.base_url(),
+        data.settings().storage_base_url(),
         s3_settings.buckets().file()
     );
 
     Ok(files.into_file_output_list(s3_base_url.as_str()))
 }
 
+/// Tags of the listed files of the namespace, most used first
+#[get("/tags")]
+pub async fn list_file_tags(
+    data: web::Data<AppState>,
+    api_key: ApiKey,
+) -> Result<FileTagOutputList, ApiError> {
+    let tags = data
+        .conn()
+        .count_file_tags(api_key.namespace().as_str())
+        .await?;
+
+    Ok(FileTagOutputList(
+        tags.into_iter()
+            .map(|(tag, count)| FileTagOutput { tag, count })
+            .collect(),
+    ))
+}
+
+#[post("/{id}/confirm")]
+pub async fn confirm_file(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path: web::Path<i32>,
+    storage_provider: StorageProvider,
+) -> Result<FileOutput, ApiError> {
+    let settings = data.settings();
+    let s3_base_url = format!(
+        "{}/{}",
+        settings.storage_base_url(),
+        settings.storage().s3().buckets().file()
+    );
+
+    let model = data
+        .conn()
+        .find_file(api_key.namespace(), &path.into_inner())
+        .await?;
+
+    if *model.status() == FileStatus::Uploaded {
+        return Ok(FileOutput::from_model(&model, s3_base_url.as_str()));
+    }
+
+    let storage = storage_provider.provide();
+    check_uploaded_object(
+        storage.as_ref(),
+        settings.storage().s3().buckets().file(),
+        &model,
+    )
+    .await?;
+    // The visibility may have changed since the upload URL was issued
+    set_object_visibility(
+        storage.as_ref(),
+        settings.storage().s3().buckets().file(),
+        model.storage_key(),
+        *model.visibility(),
+    )
+    .await?;
+
+    let model = data.conn().confirm_file_upload(model).await?;
+
+    Ok(FileOutput::from_model(&model, s3_base_url.as_str()))
+}
+
 #[put("/{id}")]
 pub async fn update_file(
     data: web::Data<AppState>,
     api_key: WriteApiKey,
     path: web::Path<i32>,
-    s3_provider: S3ClientProvider,
+    storage_provider: StorageProvider,
     payload: web::Json<FileUpdateInput>,
 ) -> Result<UploadFileOutput, ApiError> {
+    let settings = data.settings();
+    let namespace = api_key.namespace().as_str();
+    if let Some(file) = payload.file() {
+        check_file_size(settings, namespace, file)?;
+        check_single_upload_size(file)?;
+        file.validated_sha256()?;
+        check_quota(
+            data.conn(),
+            settings.storage().quota(),
+            namespace,
+            *file.content_length(),
+        )
+        .await?;
+    }
+
     let payload = payload.into_inner();
+    let url_ttl = settings.storage().upload().file().url_ttl_for(namespace);
+    let id = path.into_inner();
+    let previous = data.conn().find_file(namespace, &id).await?;
+
+    let txn = data.conn().begin().await.map_api_err()?;
+    let model = txn
+        .update_file(namespace, &id, payload.clone(), url_ttl)
+        .await?;
+    // The replaced content is kept as a version of the file
+    let mut outdated_keys = Vec::new();
+    if payload.file().is_some() {
+        outdated_keys = txn
+            .archive_file_object(
+                &previous,
+                *settings.storage().upload().file().max_versions(),
+            )
+            .await?;
+    }
+    txn.commit().await.map_api_err()?;
+
+    let storage = storage_provider.provide();
+    let bucket = settings.storage().s3().buckets().file();
+    let mut presigned_url = None;
+    if let Some(file) = payload.file() {
+        discard_replaced_objects(
+            data.conn(),
+            storage.as_ref(),
+            bucket,
+            &previous,
+            outdated_keys,
+        )
+        .await?;
+        presigned_url = Some(
+            presign_upload(
+                storage.as_ref(),
+                bucket,
+                model.storage_key(),
+                file,
+                *model.visibility(),
+                url_ttl,
+            )
+            .await?,
+        );
+    }
+
+    if payload.visibility().is_some() {
+        let mut keys: Vec<String> = data
+            .conn()
+            .find_file_versions(model.id)
+            .await?
+            .into_iter()
+            .map(|version| version.storage_key)
+            .collect();
+        // Pending objects get their visibility once confirmed
+        if payload.file().is_none() && *model.status() == FileStatus::Uploaded {
+            keys.push(model.storage_key().clone());
+        }
+
+        for key in keys {
+            set_object_visibility(storage.as_ref(), bucket, &key, *model.visibility()).await?;
+        }
+    }
+
+    Ok(upload_file_output(settings, model, presigned_url, false))
+}
+
+#[get("/{id}/versions")]
+pub async fn list_file_versions(
+    data: web::Data<AppState>,
+    api_key: ApiKey,
+    path: web::Path<i32>,
+) -> Result<FileVersionOutputList, ApiError> {
     let model = data
         .conn()
-        .update_file(api_key.namespace(), &path.into_inner(), payload.clone())
+        .find_file(api_key.namespace(), &path.into_inner())
+        .await?;
+    let versions = data.conn().find_file_versions(model.id).await?;
+
+    let s3_settings = data.settings().storage().s3();
+    let s3_base_url = format!(
+        "{}/{}",
+        data.settings().storage_base_url(),
+        s3_settings.buckets().file()
+    );
+
+    Ok(FileVersionOutputList::from_models(
+        &versions,
+        &model,
+        s3_base_url.as_str(),
+    ))
+}
+
+/// Makes a version the current content of its file, the replaced content
+/// becoming a version in turn
+#[post("/{id}/versions/{version_id}/restore")]
+pub async fn restore_file_version(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path: web::Path<(i32, i32)>,
+    storage_provider: StorageProvider,
+) -> Result<FileOutput, ApiError> {
+    let settings = data.settings();
+    let (id, version_id) = path.into_inner();
+    let previous = data.conn().find_file(api_key.namespace(), &id).await?;
+    let version = data.conn().find_file_version(id, version_id).await?;
+
+    let txn = data.conn().begin().await.map_api_err()?;
+    let model = txn.restore_file_version(previous.clone(), version).await?;
+    let outdated_keys = txn
+        .archive_file_object(
+            &previous,
+            *settings.storage().upload().file().max_versions(),
+        )
         .await?;
+    txn.commit().await.map_api_err()?;
+
+    discard_replaced_objects(
+        data.conn(),
+        storage_provider.provide().as_ref(),
+        settings.storage().s3().buckets().file(),
+        &previous,
+        outdated_keys,
+    )
+    .await?;
 
+    let s3_base_url = format!(
+        "{}/{}",
+        settings.storage_base_url(),
+        settings.storage().s3().buckets().file()
+    );
+
+    Ok(FileOutput::from_model(&model, s3_base_url.as_str()))
+}
+
+/// Issues a short-lived download URL, the only way to get private files
+#[get("/{id}/download")]
+pub async fn download_file(
+    data: web::Data<AppState>,
+    api_key: ApiKey,
+    path: web::Path<i32>,
+    storage_provider: StorageProvider,
+) -> Result<FileDownloadOutput, ApiError> {
     let settings = data.settings();
-    let s3_client = s3_provider.provide();
+    let namespace = api_key.namespace().as_str();
+    let model = data.conn().find_file(namespace, &path.into_inner()).await?;
 
-    let mut presigned_url = None;
-    if let Some(file) = payload.file() {
-        let maybe_content_type = file.content_type.clone();
-        let content_length = *file.content_length();
-
-        let mut s3_request = s3_client
-            .put_object()
-            .content_length(content_length.into())
-            .bucket(settings.s3().buckets().file())
-            .key(model.storage_key())
-            .acl(PublicRead);
-
-        if let Some(content_type) = maybe_content_type {
-            s3_request = s3_request.content_type(content_type)
-        }
+    if *model.status() != FileStatus::Uploaded {
+        return Err(ApiError::FileNotUploaded);
+    }
 
-        presigned_url = Some(
-            s3_request
-                .presigned(
-                    PresigningConfig::builder()
-                        .expires_in(Duration::from_secs(180))
-                        .build()
-                        .unwrap(),
-                )
-                .await
-                .map_err(|_e| ApiError::InternalServerError)?
-                .uri()
-                .to_string(),
-        );
+    let url_ttl = settings
+        .storage()
+        .upload()
+        .file()
+        .download_url_ttl_for(namespace);
+    let url = presign_download(
+        storage_provider.provide().as_ref(),
+        settings.storage().s3().buckets().file(),
+        model.storage_key(),
+        url_ttl,
+    )
+    .await?;
+
+    Ok(FileDownloadOutput {
+        id: model.id,
+        url,
+        expires_at: Utc::now() + Duration::seconds(url_ttl as i64),
+    })
+}
+
+/// Files used by bloks or posts are only deleted when forced
+#[delete("/{id}")]
+pub async fn delete_file(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path: web::Path<i32>,
+    query: web::Query<MediaDeleteQuery>,
+) -> Result<FileDeleteResponse, ApiError> {
+    let id = path.into_inner();
+    let namespace = api_key.namespace().as_str();
+    let txn = data.conn().begin().await.map_api_err()?;
+    let model = txn.delete_file(namespace, &id).await?;
+    check_media_unused(&txn, namespace, MediaType::File, id, *query.force()).await?;
+    txn.commit().await.map_api_err()?;
+
+    Ok(FileDeleteResponse { id: *model.id() })
+}
+
+#[post("/{id}/restore")]
+pub async fn restore_file(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path: web::Path<i32>,
+) -> Result<FileOutput, ApiError> {
+    let model = data
+        .conn()
+        .restore_file(api_key.namespace(), &path.into_inner())
+        .await?;
+
+    let s3_settings = data.settings().storage().s3();
+    let s3_base_url = format!(
+        "{}/{}",
+        data.settings().storage_base_url(),
+        s3_settings.buckets().file()
+    );
+
+    Ok(FileOutput::from_model(&model, s3_base_url.as_str()))
+}
+
+#[post("/multipart")]
+pub async fn create_multipart_file(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    storage_provider: StorageProvider,
+    input: web::Json<FileInput>,
+) -> Result<MultipartUploadOutput, ApiError> {
+    let settings = data.settings();
+    let namespace = api_key.namespace().as_str();
+    check_file_size(settings, namespace, input.file())?;
+    // S3 only computes checksums of the parts of multipart uploads
+    if input.file().sha256().is_some() {
+        return Err(ApiError::InvalidField(
+            "file.sha256".to_string(),
+            "checksums are only supported for single part uploads".to_string(),
+        ));
     }
+    check_quota(
+        data.conn(),
+        settings.storage().quota(),
+        namespace,
+        *input.file().content_length(),
+    )
+    .await?;
+
+    let input = input.into_inner();
+    let model = data
+        .conn()
+        .create_file(
+            namespace,
+            input.clone(),
+            settings.storage().upload().file().url_ttl_for(namespace),
+        )
+        .await?;
 
-    let public_url = format!(
-        "{}/{}/{}",
-        settings.s3().base_url(),
-        settings.s3().buckets().file(),
-        model.storage_key()
+    // Files whose multipart upload cannot be created are removed once expired
+    let upload_id = create_multipart_upload(
+        storage_provider.provide().as_ref(),
+        settings.storage().s3().buckets().file(),
+        model.storage_key(),
+        input.file(),
+        *model.visibility(),
+    )
+    .await?;
+    let model = data.conn().set_file_upload_id(model, upload_id).await?;
+
+    let content_length = *input.file().content_length();
+    let s3_base_url = format!(
+        "{}/{}",
+        settings.storage_base_url(),
+        settings.storage().s3().buckets().file()
     );
+    let public_url = public_url(&model, s3_base_url.as_str());
 
-    Ok(UploadFileOutput {
+    Ok(MultipartUploadOutput {
         id: model.id,
-        upload_url: presigned_url,
+        status: model.status,
+        upload_expires_at: model.upload_expires_at,
+        part_size: multipart_part_size(content_length),
+        part_count: multipart_part_count(content_length),
         key: model.storage_key,
         tags: model.tags,
         metadata: model.metadata,
         public_url,
+        visibility: model.visibility,
         created_at: model.created_at,
         updated_at: model.updated_at,
     })
 }
 
-#[delete("/{id}")]
-pub async fn delete_file(
+#[post("/{id}/multipart/parts")]
+pub async fn presign_file_parts(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path: web::Path<i32>,
+    storage_provider: StorageProvider,
+    input: web::Json<MultipartPartsInput>,
+) -> Result<MultipartPartsOutput, ApiError> {
+    let settings = data.settings();
+    let namespace = api_key.namespace().as_str();
+    let model = data.conn().find_file(namespace, &path.into_inner()).await?;
+    let upload_id = model.upload_id().clone().ok_or(ApiError::NotFound)?;
+
+    let part_count = multipart_part_count(model.content_length().unwrap_or_default() as u64);
+    if input.part_numbers().len() > MAX_PART_URLS {
+        return Err(ApiError::InvalidField(
+            "partNumbers".to_string(),
+            format!("at most {MAX_PART_URLS} parts can be requested at once"),
+        ));
+    }
+    if let Some(part_number) = input
+        .part_numbers()
+        .iter()
+        .find(|part_number| !(1..=part_count).contains(*part_number))
+    {
+        return Err(ApiError::InvalidField(
+            "partNumbers".to_string(),
+            format!(
+                "part {part_number} does not exist, expected a part between 1 and {part_count}"
+            ),
+        ));
+    }
+
+    let url_ttl = settings.storage().upload().file().url_ttl_for(namespace);
+    let storage = storage_provider.provide();
+    let mut parts = Vec::with_capacity(input.part_numbers().len());
+    for part_number in input.part_numbers() {
+        parts.push(PartUploadOutput {
+            part_number: *part_number,
+            upload_url: presign_upload_part(
+                storage.as_ref(),
+                settings.storage().s3().buckets().file(),
+                model.storage_key(),
+                &upload_id,
+                *part_number,
+                url_ttl,
+            )
+            .await?,
+        });
+    }
+
+    let model = data.conn().extend_file_upload(model, url_ttl).await?;
+
+    Ok(MultipartPartsOutput {
+        parts,
+        upload_expires_at: model.upload_expires_at,
+    })
+}
+
+#[post("/{id}/multipart/complete")]
+pub async fn complete_multipart_file(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path: web::Path<i32>,
+    storage_provider: StorageProvider,
+    input: web::Json<MultipartCompleteInput>,
+) -> Result<FileOutput, ApiError> {
+    let settings = data.settings();
+    let s3_base_url = format!(
+        "{}/{}",
+        settings.storage_base_url(),
+        settings.storage().s3().buckets().file()
+    );
+
+    let model = data
+        .conn()
+        .find_file(api_key.namespace(), &path.into_inner())
+        .await?;
+
+    if *model.status() == FileStatus::Uploaded {
+        return Ok(FileOutput::from_model(&model, s3_base_url.as_str()));
+    }
+    let upload_id = model.upload_id().clone().ok_or(ApiError::NotFound)?;
+
+    let storage = storage_provider.provide();
+    complete_multipart_upload(
+        storage.as_ref(),
+        settings.storage().s3().buckets().file(),
+        model.storage_key(),
+        &upload_id,
+        input.parts(),
+    )
+    .await?;
+    check_uploaded_object(
+        storage.as_ref(),
+        settings.storage().s3().buckets().file(),
+        &model,
+    )
+    .await?;
+
+    let model = data.conn().confirm_file_upload(model).await?;
+
+    Ok(FileOutput::from_model(&model, s3_base_url.as_str()))
+}
+
+/// Discards the uploaded parts along with the pending file
+#[delete("/{id}/multipart")]
+pub async fn abort_multipart_file(
     data: web::Data<AppState>,
     api_key: WriteApiKey,
     path: web::Path<i32>,
-    s3_provider: S3ClientProvider,
+    storage_provider: StorageProvider,
 ) -> Result<FileDeleteResponse, ApiError> {
     let model = data
         .conn()
-        .delete_file(api_key.namespace(), &path.into_inner())
+        .find_file(api_key.namespace(), &path.into_inner())
         .await?;
+    let upload_id = model.upload_id().clone().ok_or(ApiError::NotFound)?;
 
-    let s3_client = s3_provider.provide();
+    abort_multipart_upload(
+        storage_provider.provide().as_ref(),
+        data.settings().storage().s3().buckets().file(),
+        model.storage_key(),
+        &upload_id,
+    )
+    .await?;
 
-    s3_client
-        .delete_object()
-        .bucket(data.settings().s3().buckets().file())
-        .key(model.storage_key())
-        .send()
-        .await
-        .map_err(|_e| ApiError::InternalServerError)?;
+    let id = model.id;
+    data.conn().remove_pending_file(model).await?;
 
-    return Ok(FileDeleteResponse { id: *model.id() });
+    Ok(FileDeleteResponse { id })
 }
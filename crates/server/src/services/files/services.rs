@@ -0,0 +1,208 @@
+use entity::file::{FileVisibility, Model};
+
+use crate::{
+    errors::ApiError,
+    services::files::models::{CompletedPartInput, FilePayload},
+    storage::{ObjectOptions, Storage, StorageError, UploadConditions},
+};
+
+/// Largest object S3 accepts in a single PUT
+pub const MAX_SINGLE_UPLOAD_SIZE: u64 = 5_000_000_000;
+/// Smallest part size, S3 requires at least 5 MiB for every part but the last
+const MIN_PART_SIZE: u64 = 8 * 1024 * 1024;
+/// Most parts S3 accepts in a multipart upload
+const MAX_PART_COUNT: u64 = 10_000;
+
+/// Private objects can only be read from presigned URLs
+fn is_public(visibility: FileVisibility) -> bool {
+    matches!(visibility, FileVisibility::Public)
+}
+
+/// Size of the parts a file is uploaded in, the smallest one keeping the
+/// number of parts under the S3 limit
+pub fn multipart_part_size(content_length: u64) -> u64 {
+    MIN_PART_SIZE.max(content_length.div_ceil(MAX_PART_COUNT))
+}
+
+pub fn multipart_part_count(content_length: u64) -> u32 {
+    content_length
+        .div_ceil(multipart_part_size(content_length))
+        .max(1) as u32
+}
+
+/// Presigned URL the client uploads the file to with a single PUT. When a
+/// SHA-256 is declared, the storage rejects other contents (on S3, the client
+/// must send it in the `x-amz-checksum-sha256` header).
+pub async fn presign_upload(
+    storage: &dyn Storage,
+    bucket: &str,
+    key: &str,
+    file: &FilePayload,
+    visibility: FileVisibility,
+    url_ttl: u64,
+) -> Result<String, ApiError> {
+    let conditions = UploadConditions {
+        content_length: *file.content_length(),
+        content_type: file.content_type().clone(),
+        sha256: file.validated_sha256()?,
+        public: is_public(visibility),
+    };
+
+    storage
+        .presign_upload(bucket, key, &conditions, url_ttl)
+        .await
+        .map_err(|e| e.into_api_error("Cannot presign file upload"))
+}
+
+/// Starts a multipart upload, returns its ID
+pub async fn create_multipart_upload(
+    storage: &dyn Storage,
+    bucket: &str,
+    key: &str,
+    file: &FilePayload,
+    visibility: FileVisibility,
+) -> Result<String, ApiError> {
+    let options = ObjectOptions {
+        content_type: file.content_type().clone(),
+        public: is_public(visibility),
+        metadata: Vec::new(),
+    };
+
+    storage
+        .create_multipart_upload(bucket, key, options)
+        .await
+        .map_err(|e| e.into_api_error("Cannot create multipart upload"))
+}
+
+/// Presigned URL the object of a file can be downloaded from
+pub async fn presign_download(
+    storage: &dyn Storage,
+    bucket: &str,
+    key: &str,
+    url_ttl: u64,
+) -> Result<String, ApiError> {
+    storage
+        .presign_download(bucket, key, url_ttl)
+        .await
+        .map_err(|e| e.into_api_error("Cannot presign file download"))
+}
+
+/// Applies the visibility of a file to its already uploaded object
+pub async fn set_object_visibility(
+    storage: &dyn Storage,
+    bucket: &str,
+    key: &str,
+    visibility: FileVisibility,
+) -> Result<(), ApiError> {
+    storage
+        .set_object_visibility(bucket, key, is_public(visibility))
+        .await
+        .map_err(|e| e.into_api_error("Cannot update file visibility"))
+}
+
+/// Presigned URL the client uploads a part to, its ETag must be sent back
+/// to complete the upload
+pub async fn presign_upload_part(
+    storage: &dyn Storage,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    part_number: u32,
+    url_ttl: u64,
+) -> Result<String, ApiError> {
+    storage
+        .presign_upload_part(bucket, key, upload_id, part_number, url_ttl)
+        .await
+        .map_err(|e| e.into_api_error("Cannot presign part upload"))
+}
+
+/// Assembles the uploaded parts into the object. Fails when a part is
+/// missing, too small or does not match its ETag.
+pub async fn complete_multipart_upload(
+    storage: &dyn Storage,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+    parts: &[CompletedPartInput],
+) -> Result<(), ApiError> {
+    let mut parts = parts.to_vec();
+    parts.sort_by_key(|part| part.part_number);
+
+    storage
+        .complete_multipart_upload(
+            bucket,
+            key,
+            upload_id,
+            parts
+                .into_iter()
+                .map(|part| (part.part_number, part.etag))
+                .collect(),
+        )
+        .await
+        .map_err(|e| e.into_api_error("Cannot complete multipart upload"))
+}
+
+/// Discards a multipart upload along with its uploaded parts
+pub async fn abort_multipart_upload(
+    storage: &dyn Storage,
+    bucket: &str,
+    key: &str,
+    upload_id: &str,
+) -> Result<(), ApiError> {
+    storage
+        .abort_multipart_upload(bucket, key, upload_id)
+        .await
+        .map_err(|e| e.into_api_error("Cannot abort multipart upload"))
+}
+
+/// Checks that the object uploaded by the client is the one declared when
+/// requesting the upload URL
+pub async fn check_uploaded_object(
+    storage: &dyn Storage,
+    bucket: &str,
+    model: &Model,
+) -> Result<(), ApiError> {
+    let object = storage
+        .head_object(bucket, model.storage_key())
+        .await
+        .map_err(|e| match e {
+            StorageError::NotFound => ApiError::FileNotUploaded,
+            e => e.into_api_error("Cannot get uploaded file from storage"),
+        })?;
+
+    if let Some(content_length) = model.content_length() {
+        if *content_length != object.content_length {
+            return Err(ApiError::FileUploadMismatch(format!(
+                "its size is {} bytes but {content_length} bytes were declared",
+                object.content_length
+            )));
+        }
+    }
+
+    if let Some(content_type) = model.content_type() {
+        if Some(content_type) != object.content_type.as_ref() {
+            return Err(ApiError::FileUploadMismatch(format!(
+                "its content type is \"{}\" but \"{content_type}\" was declared",
+                object.content_type.unwrap_or_default()
+            )));
+        }
+    }
+
+    if let Some(sha256) = model.sha256() {
+        let uploaded = match object.sha256 {
+            Some(uploaded) => uploaded,
+            // Stores without checksum support do not return it
+            None => storage
+                .object_sha256(bucket, model.storage_key())
+                .await
+                .map_err(|e| e.into_api_error("Cannot read uploaded file from storage"))?,
+        };
+        if uploaded != *sha256 {
+            return Err(ApiError::FileUploadMismatch(format!(
+                "its SHA-256 is {uploaded} but {sha256} was declared"
+            )));
+        }
+    }
+
+    Ok(())
+}
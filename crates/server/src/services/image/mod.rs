@@ -1,12 +1,25 @@
-use crate::services::image::routes::{delete_image, list_images, upload_image};
+use crate::services::image::routes::{
+    delete_image, get_image, list_images, patch_image, replace_image, reprocess_image,
+    reprocess_images, restore_image, transform_image, update_image, upload_image,
+};
 use actix_web::{web::scope, Scope};
 
 mod models;
 mod routes;
+pub(crate) mod services;
+mod svg;
 
 pub fn image_service() -> Scope {
     scope("/image")
         .service(list_images)
+        .service(reprocess_images)
+        .service(get_image)
         .service(upload_image)
+        .service(update_image)
+        .service(patch_image)
+        .service(replace_image)
+        .service(reprocess_image)
         .service(delete_image)
+        .service(restore_image)
+        .service(transform_image)
 }
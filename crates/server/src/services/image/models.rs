@@ -1,20 +1,54 @@
 use crate::{
     config::SETTINGS,
-    errors::{utils::try_unwrap_active_value, ApiError},
+    errors::ApiError,
+    services::image::services::{format_mime, is_encodable, storage_key_with_format},
+    utils::serde_json_patch::Patch,
 };
 use chrono::{DateTime, Utc};
+use entity::{
+    image::ImageStatus,
+    image_profile::{ImageFit, ImageFormat},
+    image_variant,
+};
 use getset::Getters;
+use sea_orm::{prelude::Json, ActiveEnum};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 #[derive(Serialize, Clone, Getters)]
 #[serde(rename_all = "camelCase")]
 #[getset(get = "pub")]
 pub struct ImageOutput {
     id: i32,
-    public_url: String,
-    lazy_image: LazyImageOutput,
+    /// Unknown until the renditions of the image are first generated
+    public_url: Option<String>,
+    status: ImageStatus,
+    width: Option<i32>,
+    height: Option<i32>,
+    original_width: Option<i32>,
+    original_height: Option<i32>,
+    /// MIME type and size in bytes of the upload
+    original_mime_type: Option<String>,
+    original_size: Option<i64>,
+    /// EXIF orientation of the upload, already applied to every rendition
+    orientation: Option<i16>,
+    /// Most common color of the image as `#rrggbb`
+    dominant_color: Option<String>,
+    focal_point: Option<FocalPointOutput>,
+    /// Placeholder to render while the image loads, unknown for images
+    /// uploaded before it was computed
+    blurhash: Option<String>,
+    lazy_image: Option<LazyImageOutput>,
+    /// Every encoding of the image, in `<picture>` order: the format of
+    /// `public_url` comes last as the fallback
+    sources: Vec<ImageSourceOutput>,
+    /// Variants generated from the namespace image profiles, by ascending width
+    variants: Vec<ImageVariantOutput>,
+    /// Media library folder, at the root when unset
+    folder_id: Option<i32>,
     alt: Option<String>,
+    tags: Vec<String>,
+    metadata: Json,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
@@ -25,75 +59,157 @@ pub struct ImageOutput {
 pub struct LazyImageOutput {
     id: i32,
     public_url: String,
+    width: Option<i32>,
+    height: Option<i32>,
     alt: Option<String>,
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 }
 
-impl
-    TryFrom<(
-        Arc<String>,
-        entity::image::ActiveModel,
-        entity::image::ActiveModel,
-    )> for ImageOutput
-{
-    type Error = ApiError;
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct ImageVariantOutput {
+    name: String,
+    public_url: String,
+    width: i32,
+    height: i32,
+    format: ImageFormat,
+}
 
-    fn try_from(
-        (bucket, image, lazy_image): (
-            Arc<String>,
-            entity::image::ActiveModel,
-            entity::image::ActiveModel,
-        ),
-    ) -> Result<Self, Self::Error> {
-        Ok(ImageOutput {
-            id: try_unwrap_active_value(image.id)?,
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct FocalPointOutput {
+    x: f64,
+    y: f64,
+}
+
+impl FocalPointOutput {
+    fn from_coordinates(x: Option<f64>, y: Option<f64>) -> Option<Self> {
+        Some(Self { x: x?, y: y? })
+    }
+}
+
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct ImageSourceOutput {
+    format: ImageFormat,
+    mime_type: &'static str,
+    public_url: String,
+}
+
+/// Formats are recorded main format first, images uploaded before formats
+/// were recorded only exist as JPEG
+fn image_sources(bucket: &str, storage_key: &str, formats: &[String]) -> Vec<ImageSourceOutput> {
+    let mut formats: Vec<ImageFormat> = formats
+        .iter()
+        .filter_map(|format| ImageFormat::try_from_value(format).ok())
+        .collect();
+
+    if formats.is_empty() {
+        formats.push(ImageFormat::Jpeg);
+    }
+    formats.rotate_left(1);
+
+    formats
+        .into_iter()
+        .map(|format| ImageSourceOutput {
+            format,
+            mime_type: format_mime(format),
             public_url: format!(
                 "{base_url}/{bucket}/{id}",
-                base_url = (*SETTINGS).s3().base_url(),
-                id = try_unwrap_active_value(image.storage_key)?
+                base_url = (*SETTINGS).storage_base_url(),
+                id = storage_key_with_format(storage_key, format)
             ),
-            lazy_image: LazyImageOutput {
-                id: try_unwrap_active_value(lazy_image.id)?,
+        })
+        .collect()
+}
+
+impl ImageOutput {
+    pub fn with_variants(mut self, bucket: &str, variants: Vec<image_variant::Model>) -> Self {
+        let mut variants: Vec<ImageVariantOutput> = variants
+            .into_iter()
+            .map(|variant| ImageVariantOutput {
+                name: variant.name,
                 public_url: format!(
                     "{base_url}/{bucket}/{id}",
-                    base_url = (*SETTINGS).s3().base_url(),
-                    id = try_unwrap_active_value(lazy_image.storage_key)?
+                    base_url = (*SETTINGS).storage_base_url(),
+                    id = variant.storage_key
                 ),
-                alt: try_unwrap_active_value(lazy_image.alt)?,
-                created_at: try_unwrap_active_value(lazy_image.created_at)?,
-                updated_at: try_unwrap_active_value(lazy_image.updated_at)?,
-            },
-            alt: try_unwrap_active_value(image.alt)?,
-            created_at: try_unwrap_active_value(image.created_at)?,
-            updated_at: try_unwrap_active_value(image.updated_at)?,
-        })
+                width: variant.width,
+                height: variant.height,
+                format: variant.format,
+            })
+            .collect();
+        variants.sort_by_key(|variant| (variant.width, variant.format.to_value()));
+
+        self.variants = variants;
+        self
     }
 }
 
-impl From<(Arc<String>, entity::image::Model, entity::image::Model)> for ImageOutput {
+impl
+    From<(
+        Arc<String>,
+        entity::image::Model,
+        Option<entity::image::Model>,
+    )> for ImageOutput
+{
+    /// Images without lazy image are processed for the first time, they only
+    /// point to their private original
     fn from(
-        (bucket, image, lazy_image): (Arc<String>, entity::image::Model, entity::image::Model),
+        (bucket, image, lazy_image): (
+            Arc<String>,
+            entity::image::Model,
+            Option<entity::image::Model>,
+        ),
     ) -> Self {
+        let sources = match lazy_image {
+            Some(_) => image_sources(&bucket, &image.storage_key, &image.formats),
+            None => Vec::new(),
+        };
+
         ImageOutput {
             id: image.id,
-            public_url: format!(
-                "{base_url}/{bucket}/{id}",
-                base_url = (*SETTINGS).s3().base_url(),
-                id = image.storage_key
-            ),
-            lazy_image: LazyImageOutput {
+            public_url: lazy_image.as_ref().map(|_| {
+                format!(
+                    "{base_url}/{bucket}/{id}",
+                    base_url = (*SETTINGS).storage_base_url(),
+                    id = image.storage_key
+                )
+            }),
+            status: image.status,
+            width: image.width,
+            height: image.height,
+            original_width: image.original_width,
+            original_height: image.original_height,
+            original_mime_type: image.original_mime_type,
+            original_size: image.original_size,
+            orientation: image.orientation,
+            dominant_color: image.dominant_color,
+            focal_point: FocalPointOutput::from_coordinates(image.focal_x, image.focal_y),
+            blurhash: image.blurhash,
+            lazy_image: lazy_image.map(|lazy_image| LazyImageOutput {
                 id: lazy_image.id,
                 public_url: format!(
                     "{base_url}/{bucket}/{id}",
-                    base_url = (*SETTINGS).s3().base_url(),
+                    base_url = (*SETTINGS).storage_base_url(),
                     id = lazy_image.storage_key
                 ),
+                width: lazy_image.width,
+                height: lazy_image.height,
                 alt: lazy_image.alt,
                 created_at: lazy_image.created_at,
                 updated_at: lazy_image.updated_at,
-            },
+            }),
+            sources,
+            variants: Vec::new(),
+            folder_id: image.folder_id,
             alt: image.alt,
+            tags: image.tags,
+            metadata: image.metadata,
             created_at: image.created_at,
             updated_at: image.updated_at,
         }
@@ -101,7 +217,149 @@ impl From<(Arc<String>, entity::image::Model, entity::image::Model)> for ImageOu
 }
 
 #[derive(Deserialize, Serialize, Getters)]
+#[serde(rename_all = "camelCase")]
 #[getset(get = "pub")]
 pub struct ImageUploadQuery {
     alt: Option<String>,
+    /// Comma separated formats the image is also encoded to, e.g. `webp,avif`
+    formats: Option<String>,
+    /// How the main and lazy images fit their box, `contain` by default
+    fit: Option<ImageFit>,
+    focal_x: Option<f64>,
+    focal_y: Option<f64>,
+}
+
+impl ImageUploadQuery {
+    pub fn focal_point(&self) -> Result<Option<(f64, f64)>, ApiError> {
+        for (field, value) in [("focalX", self.focal_x), ("focalY", self.focal_y)] {
+            if value.is_some_and(|value| !(0.0..=1.0).contains(&value)) {
+                return Err(ApiError::InvalidField(
+                    field.to_string(),
+                    "expected a value between 0 and 1".to_string(),
+                ));
+            }
+        }
+
+        match (self.focal_x, self.focal_y) {
+            (Some(x), Some(y)) => Ok(Some((x, y))),
+            (None, None) => Ok(None),
+            (Some(_), None) => Err(ApiError::MissingField("focalY".to_string())),
+            (None, Some(_)) => Err(ApiError::MissingField("focalX".to_string())),
+        }
+    }
+
+    pub fn alternate_formats(&self) -> Result<Vec<ImageFormat>, ApiError> {
+        let Some(formats) = &self.formats else {
+            return Ok(Vec::new());
+        };
+
+        formats
+            .split(',')
+            .map(str::trim)
+            .filter(|format| !format.is_empty())
+            .map(|format| {
+                ImageFormat::try_from_value(&format.to_string())
+                    .ok()
+                    .filter(|format| is_encodable(*format))
+                    .ok_or_else(|| {
+                        ApiError::InvalidField(
+                            "formats".to_string(),
+                            format!(
+                                "unsupported format \"{format}\", expected jpeg, png, webp or avif"
+                            ),
+                        )
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Number of images queued for reprocessing
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct ImageReprocessOutput {
+    queued: u64,
+}
+
+impl From<u64> for ImageReprocessOutput {
+    fn from(queued: u64) -> Self {
+        Self { queued }
+    }
+}
+
+#[derive(Debug, Deserialize, Getters)]
+#[getset(get = "pub")]
+pub struct ImageFilter {
+    tag: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct ImageInput {
+    alt: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct ImagePatchInput {
+    #[serde(default)]
+    alt: Patch<String>,
+    #[serde(default)]
+    tags: Patch<Vec<String>>,
+    #[serde(default)]
+    metadata: Patch<HashMap<String, String>>,
+}
+
+/// Metadata is stored as a JSON object of strings, as for files
+pub fn metadata_value(metadata: &HashMap<String, String>) -> Json {
+    Json::Object(
+        metadata
+            .iter()
+            .map(|(key, value)| (key.clone(), Json::String(value.clone())))
+            .collect(),
+    )
+}
+
+#[derive(Deserialize, Getters)]
+#[getset(get = "pub")]
+pub struct ImageTransformQuery {
+    width: u32,
+    height: u32,
+    /// `cover` by default
+    fit: Option<ImageFit>,
+    /// Main format of the image by default
+    #[getset(skip)]
+    format: Option<ImageFormat>,
+    #[getset(skip)]
+    quality: Option<u8>,
+}
+
+impl ImageTransformQuery {
+    pub fn format(&self) -> Result<Option<ImageFormat>, ApiError> {
+        match self.format {
+            Some(format) if !is_encodable(format) => Err(ApiError::InvalidField(
+                "format".to_string(),
+                "expected jpeg, png, webp or avif".to_string(),
+            )),
+            format => Ok(format),
+        }
+    }
+
+    pub fn quality(&self) -> Result<u8, ApiError> {
+        match self.quality {
+            None => Ok(75),
+            Some(quality) if (1..=100).contains(&quality) => Ok(quality),
+            Some(_) => Err(ApiError::InvalidField(
+                "quality".to_string(),
+                "expected a value between 1 and 100".to_string(),
+            )),
+        }
+    }
 }
@@ -1,142 +1,565 @@
 use crate::{
     config::Settings,
     errors::{utils::MapApiError, ApiError},
+    jobs::{
+        images::{enqueue_image_job, has_pending_image_job, ImageJobOptions},
+        objects::delete_objects,
+    },
     middlewares::{
         api_key::{ApiKey, WriteApiKey},
-        s3::{S3ClientExt, S3ClientProvider},
+        storage::StorageProvider,
     },
     server::AppState,
-    services::image::models::{ImageOutput, ImageUploadQuery},
+    services::{
+        image::{
+            models::{
+                metadata_value, ImageFilter, ImageInput, ImageOutput, ImagePatchInput,
+                ImageReprocessOutput, ImageTransformQuery, ImageUploadQuery,
+            },
+            services::{
+                download_image, download_original, find_image_variants, is_encodable, main_images,
+                probe_upload, run_blocking, store_original, transform_and_upload,
+                transform_storage_key, Rendition,
+            },
+        },
+        media::{
+            models::{MediaDeleteQuery, MediaType},
+            services::check_media_unused,
+        },
+        usage::services::check_quota,
+    },
+    storage::StorageExt,
+    utils::serde_json_patch::Patch::Value,
 };
 use actix_multipart::Multipart;
-use actix_web::{delete, get, post, web, Error as ActixError, HttpResponse};
-use aws_sdk_s3::{model::ObjectCannedAcl::PublicRead, types::ByteStream};
-use aws_smithy_http::body::SdkBody;
-use deunicode::deunicode;
-use entity::image::{Column, Entity, LazyImageLink, Model};
-use futures::{future::ready, StreamExt, TryFutureExt};
-use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage};
-use sea_orm::{prelude::*, ActiveValue::Set};
-use std::{ffi::OsStr, path::Path, sync::Arc};
-use tracing::{error, info_span, warn, Instrument};
+use actix_web::{
+    delete, get, http::header::LOCATION, patch, post, put, web, Error as ActixError, HttpResponse,
+};
+use chrono::{DateTime, Utc};
+use entity::{
+    image::{ActiveModel, Column, Entity, ImageStatus, LazyImageLink, Model},
+    image_profile::{ImageFit, ImageFormat},
+    image_transform, image_transform_size,
+};
+use futures::StreamExt;
+use sea_orm::{
+    prelude::*,
+    sea_query::{Expr, OnConflict},
+    ActiveEnum,
+    ActiveValue::Set,
+    IntoActiveModel, TransactionTrait,
+};
+use std::sync::Arc;
+use tracing::{info_span, Instrument};
 use uuid::Uuid;
 
 #[get("")]
 pub async fn list_images(
     data: web::Data<AppState>,
     api_key: ApiKey,
+    filter: web::Query<ImageFilter>,
 ) -> Result<HttpResponse, ActixError> {
     let settings = data.settings();
+    let bucket = settings.storage().s3().buckets().image();
 
-    let images: Vec<ImageOutput> = Entity::find()
+    let mut query = Entity::find()
         .filter(Column::Namespace.eq(api_key.namespace().to_string()))
-        .filter(Column::LazyImageId.is_not_null())
+        .filter(Column::DeletedAt.is_null())
+        .filter(main_images());
+
+    if let Some(tag) = filter.tag() {
+        // Qualified as lazy images are joined from the same table
+        query = query.filter(Expr::cust_with_values(
+            r#"$1 = any("images"."tags")"#,
+            vec![tag.to_string()],
+        ));
+    }
+
+    let images: Vec<(Model, Option<Model>)> = query
         .find_also_linked(LazyImageLink)
         .all(data.conn())
         .await
-        .map_api_err()?
-        .iter()
-        .map(|(img, lz_img_opt): &(Model, Option<Model>)| {
-            ImageOutput::from((
-                Arc::new(settings.s3().buckets().image().to_string()),
-                img.clone(),
-                lz_img_opt
-                    .clone()
-                    .expect("Query should not return null lazy image"),
-            ))
+        .map_api_err()?;
+
+    let mut variants =
+        find_image_variants(data.conn(), images.iter().map(|(img, _)| img.id).collect()).await?;
+
+    let images: Vec<ImageOutput> = images
+        .into_iter()
+        .map(|(img, lz_img_opt): (Model, Option<Model>)| {
+            let img_variants = variants.remove(&img.id).unwrap_or_default();
+            ImageOutput::from((Arc::new(bucket.to_string()), img, lz_img_opt))
+                .with_variants(bucket, img_variants)
         })
         .collect::<Vec<_>>();
 
     Ok(HttpResponse::Ok().json(images))
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn compress_and_upload(
-    s3: S3ClientExt,
-    bucket: Arc<String>,
-    image: Arc<DynamicImage>,
-    width: u32,
-    height: u32,
-    filter: FilterType,
-    lazy: bool,
-    id: Arc<String>,
-    filename: Arc<String>,
-    content_type: Arc<mime::Mime>,
-) -> Result<String, ApiError> {
-    let p_filename = Path::new(filename.as_str());
-
-    let resize = image.width() > width || image.height() > height;
-    let image = image.resize_to_fill(
-        if resize { width } else { image.width() },
-        if resize { height } else { image.height() },
-        filter,
-    );
-
-    let image = {
-        let mut image_output: Vec<u8> = Vec::new();
-        JpegEncoder::new_with_quality(&mut image_output, 75)
-            .encode_image(&image)
-            .map_err(|e| {
-                error!(
-                    error_message = format!("{:?}", e).as_str(),
-                    "An error occured while encoding image to json"
-                );
-                ApiError::InternalServerError
-            })?;
-        image_output
-    };
+#[get("/{id}")]
+pub async fn get_image(
+    data: web::Data<AppState>,
+    api_key: ApiKey,
+    path_id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    let (image, lz_image) =
+        find_image_with_lazy(data.conn(), api_key.namespace(), path_id.into_inner()).await?;
 
-    let file_stem = p_filename
-        .file_stem()
-        .and_then(OsStr::to_str)
-        .map(|v| {
-            // Cleanup filename
-            deunicode(v)
-                .chars()
-                .filter(|c| c.is_ascii())
-                .map(|c| match c {
-                    ' ' => '_',
-                    _ => c,
-                })
-                .collect::<String>()
-        })
-        .unwrap_or_else(|| "unknown".to_string());
-
-    let key = format!("{id}__{file_stem}{}.jpeg", if lazy { "__lazy" } else { "" },);
-
-    s3.put_object()
-        .bucket(bucket.to_string())
-        .content_type(content_type.to_string())
-        .metadata("s3_id", id.to_string())
-        .metadata("filename", filename.to_string())
-        .metadata("lazy", if lazy { "true" } else { "false" })
-        .key(&key)
-        .body(ByteStream::new(SdkBody::from(image)))
-        .acl(PublicRead)
-        .send()
-        .await
-        .map(|_res| key)
-        .map_err(|e| {
-            error!(
-                error_message = format!("{:?}", e).as_str(),
-                "An error occured while uploading object to S3"
-            );
-            ApiError::InternalServerError
-        })
+    image_response(&data, HttpResponse::Ok(), image, lz_image).await
 }
 
+/// Stores the uploaded original and queues the generation of its renditions,
+/// the image is `processing` until they are generated
 #[post("")]
 pub async fn upload_image(
     data: web::Data<AppState>,
-    s3_provider: S3ClientProvider,
+    storage_provider: StorageProvider,
     query: web::Query<ImageUploadQuery>,
     mut payload: Multipart,
     api_key: WriteApiKey,
+) -> Result<HttpResponse, ActixError> {
+    let focal_point = query.focal_point()?;
+    let alternate_formats = query.alternate_formats()?;
+
+    let upload = process_upload(
+        &mut payload,
+        storage_provider.provide(),
+        &data,
+        api_key.namespace(),
+    )
+    .await?;
+
+    let txn = data.conn().begin().await.map_api_err()?;
+    let image = entity::image::ActiveModel {
+        namespace: Set(api_key.namespace().to_string()),
+        storage_key: Set(upload.key.clone()),
+        original_storage_key: Set(Some(upload.key.clone())),
+        status: Set(ImageStatus::Processing),
+        alt: Set(query.alt().clone()),
+        original_mime_type: Set(Some(upload.mime_type.to_string())),
+        original_size: Set(Some(upload.size as i64)),
+        focal_x: Set(focal_point.map(|(x, _)| x)),
+        focal_y: Set(focal_point.map(|(_, y)| y)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_api_err()?;
+
+    enqueue_image_job(
+        &txn,
+        image.id,
+        &upload.job_options(query.fit().unwrap_or(ImageFit::Contain), alternate_formats),
+    )
+    .await?;
+    txn.commit().await.map_api_err()?;
+
+    image_response(&data, HttpResponse::Accepted(), image, None).await
+}
+
+/// Replaces the alt text, tags and metadata of an image
+#[put("/{id}")]
+pub async fn update_image(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path_id: web::Path<i32>,
+    body: web::Json<ImageInput>,
+) -> Result<HttpResponse, ActixError> {
+    let (image, lz_image) =
+        find_image_with_lazy(data.conn(), api_key.namespace(), path_id.into_inner()).await?;
+
+    let mut image = image.into_active_model();
+    image.alt = Set(body.alt().clone());
+    image.tags = Set(body.tags().clone());
+    image.metadata = Set(metadata_value(body.metadata()));
+
+    save_image_fields(&data, image, lz_image).await
+}
+
+#[patch("/{id}")]
+pub async fn patch_image(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path_id: web::Path<i32>,
+    body: web::Json<ImagePatchInput>,
+) -> Result<HttpResponse, ActixError> {
+    if body.tags().is_null() {
+        return Err(ApiError::PatchNotNullable(String::from("tags")).into());
+    }
+
+    if body.metadata().is_null() {
+        return Err(ApiError::PatchNotNullable(String::from("metadata")).into());
+    }
+
+    if body.alt().is_missing() && body.tags().is_missing() && body.metadata().is_missing() {
+        return Err(ApiError::PatchAtLeastOneField.into());
+    }
+
+    let (image, lz_image) =
+        find_image_with_lazy(data.conn(), api_key.namespace(), path_id.into_inner()).await?;
+
+    let mut image = image.into_active_model();
+
+    if !body.alt().is_missing() {
+        image.alt = Set(match body.alt() {
+            Value(alt) => Some(alt.clone()),
+            _ => None,
+        });
+    }
+
+    if let Value(tags) = body.tags() {
+        image.tags = Set(tags.clone());
+    }
+
+    if let Value(metadata) = body.metadata() {
+        image.metadata = Set(metadata_value(metadata));
+    }
+
+    save_image_fields(&data, image, lz_image).await
+}
+
+/// Stores a new original for an image and queues the generation of its
+/// renditions, keeping its ID so that references to it stay valid. The
+/// previous renditions are served until the new ones are generated.
+#[post("/{id}/replace")]
+pub async fn replace_image(
+    data: web::Data<AppState>,
+    storage_provider: StorageProvider,
+    query: web::Query<ImageUploadQuery>,
+    mut payload: Multipart,
+    api_key: WriteApiKey,
+    path_id: web::Path<i32>,
 ) -> Result<HttpResponse, ActixError> {
     let settings = data.settings();
-    let s3_client = s3_provider.provide();
-    let s3_bucket = Arc::new(settings.s3().buckets().image().clone());
-    let mut image_upload_result: Option<(String, String)> = None;
+    let storage = storage_provider.provide();
+    let bucket = settings.storage().s3().buckets().image();
+    let focal_point = query.focal_point()?;
+    let alternate_formats = query.alternate_formats()?;
+
+    let (image, lz_image) =
+        find_image_with_lazy(data.conn(), api_key.namespace(), path_id.into_inner()).await?;
+
+    // Checked before storing the upload, and again when queuing the job
+    if has_pending_image_job(data.conn(), image.id).await? {
+        return Err(ApiError::ImageProcessing.into());
+    }
+
+    let upload = process_upload(&mut payload, storage.clone(), &data, api_key.namespace()).await?;
+
+    let previous_original = image.original_storage_key.clone();
+    let image_id = image.id;
+
+    let txn = data.conn().begin().await.map_api_err()?;
+    let mut image = image.into_active_model();
+    // Images never processed only point to their original
+    if lz_image.is_none() {
+        image.storage_key = Set(upload.key.clone());
+    }
+    image.original_storage_key = Set(Some(upload.key.clone()));
+    image.status = Set(ImageStatus::Processing);
+    image.original_mime_type = Set(Some(upload.mime_type.to_string()));
+    image.original_size = Set(Some(upload.size as i64));
+    image.focal_x = Set(focal_point.map(|(x, _)| x));
+    image.focal_y = Set(focal_point.map(|(_, y)| y));
+    if let Some(alt) = query.alt() {
+        image.alt = Set(Some(alt.clone()));
+    }
+    let image = image.update(&txn).await.map_api_err()?;
+
+    enqueue_image_job(
+        &txn,
+        image_id,
+        &upload.job_options(query.fit().unwrap_or(ImageFit::Contain), alternate_formats),
+    )
+    .await?;
+    txn.commit().await.map_api_err()?;
+
+    // Objects which cannot be deleted are queued for retry
+    if let Some(previous_original) = previous_original {
+        delete_objects(
+            data.conn(),
+            storage.as_ref(),
+            bucket,
+            vec![previous_original],
+        )
+        .await?;
+    }
+
+    image_response(&data, HttpResponse::Accepted(), image, lz_image).await
+}
+
+/// Queues the generation of the renditions of an image from its original,
+/// e.g. after its namespace image profiles changed or its processing failed
+#[post("/{id}/reprocess")]
+pub async fn reprocess_image(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path_id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    let (image, lz_image) =
+        find_image_with_lazy(data.conn(), api_key.namespace(), path_id.into_inner()).await?;
+
+    let image = queue_reprocess(data.conn(), image).await?;
+
+    image_response(&data, HttpResponse::Accepted(), image, lz_image).await
+}
+
+/// Queues the generation of the renditions of every image of the namespace
+/// which is not already being processed
+#[post("/reprocess")]
+pub async fn reprocess_images(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+) -> Result<HttpResponse, ActixError> {
+    let images = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_string()))
+        .filter(Column::DeletedAt.is_null())
+        .filter(main_images())
+        .all(data.conn())
+        .await
+        .map_api_err()?;
+
+    let mut queued = 0;
+    for image in images {
+        match queue_reprocess(data.conn(), image).await {
+            Ok(_) => queued += 1,
+            Err(ApiError::ImageProcessing) => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(HttpResponse::Accepted().json(ImageReprocessOutput::from(queued)))
+}
+
+/// Images used by bloks or posts are only deleted when forced
+#[delete("/{id}")]
+pub async fn delete_image(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path_id: web::Path<i32>,
+    query: web::Query<MediaDeleteQuery>,
+) -> Result<HttpResponse, ActixError> {
+    let (image, lz_image) =
+        find_image_with_lazy(data.conn(), api_key.namespace(), path_id.into_inner()).await?;
+    check_media_unused(
+        data.conn(),
+        api_key.namespace(),
+        MediaType::Image,
+        image.id,
+        *query.force(),
+    )
+    .await?;
+
+    let (image, lz_image) =
+        set_images_deleted_at(data.conn(), image, lz_image, Some(Utc::now())).await?;
+
+    image_response(&data, HttpResponse::Ok(), image, lz_image).await
+}
+
+#[post("/{id}/restore")]
+pub async fn restore_image(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path_id: web::Path<i32>,
+) -> Result<HttpResponse, ActixError> {
+    let (image, lz_image) = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_string()))
+        .filter(Column::DeletedAt.is_not_null())
+        .filter(Column::Id.eq(path_id.into_inner()))
+        .filter(main_images())
+        .find_also_linked(LazyImageLink)
+        .one(data.conn())
+        .await
+        .map_api_err()?
+        .ok_or(ApiError::NotFound)?;
+
+    let (image, lz_image) = set_images_deleted_at(data.conn(), image, lz_image, None).await?;
+
+    image_response(&data, HttpResponse::Ok(), image, lz_image).await
+}
+
+/// Redirects to the image transformed according to the query, generating it
+/// on first request. Only sizes allowed for the namespace can be requested.
+#[get("/{id}/transform")]
+pub async fn transform_image(
+    data: web::Data<AppState>,
+    storage_provider: StorageProvider,
+    api_key: ApiKey,
+    path_id: web::Path<i32>,
+    query: web::Query<ImageTransformQuery>,
+) -> Result<HttpResponse, ActixError> {
+    let settings: &Settings = data.settings();
+    let bucket = settings.storage().s3().buckets().image();
+    let id = path_id.into_inner();
+    let quality = query.quality()?;
+
+    let size_allowed = image_transform_size::Entity::find()
+        .filter(image_transform_size::Column::Namespace.eq(api_key.namespace().to_string()))
+        .filter(image_transform_size::Column::Width.eq(*query.width() as i32))
+        .filter(image_transform_size::Column::Height.eq(*query.height() as i32))
+        .count(data.conn())
+        .await
+        .map_api_err()?
+        > 0;
+
+    if !size_allowed {
+        return Err(ApiError::ImageSizeNotAllowed(*query.width(), *query.height()).into());
+    }
+
+    let image: Model = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_string()))
+        .filter(Column::DeletedAt.is_null())
+        .filter(Column::Id.eq(id))
+        .filter(Column::LazyImageId.is_not_null())
+        .one(data.conn())
+        .await
+        .map_api_err()?
+        .ok_or(ApiError::NotFound)?;
+
+    // Images stored as uploaded are transformed to PNG, which keeps their
+    // transparency
+    let format = query
+        .format()?
+        .or_else(|| {
+            image
+                .formats
+                .first()
+                .and_then(|format| ImageFormat::try_from_value(format).ok())
+        })
+        .map(|format| match is_encodable(format) {
+            true => format,
+            false => ImageFormat::Png,
+        })
+        .unwrap_or(ImageFormat::Jpeg);
+
+    let rendition = Rendition::transform(
+        *query.width(),
+        *query.height(),
+        query.fit().unwrap_or(ImageFit::Cover),
+        format,
+        quality,
+    )
+    .with_focal_point(image.focal_x.zip(image.focal_y));
+    let key = transform_storage_key(&image.storage_key, &rendition);
+
+    let cached = image_transform::Entity::find()
+        .filter(image_transform::Column::StorageKey.eq(key.as_str()))
+        .count(data.conn())
+        .await
+        .map_api_err()?
+        > 0;
+
+    if !cached {
+        let storage = storage_provider.provide();
+        // The main image is already downscaled and compressed, transforms are
+        // generated from the original when it is kept
+        let original = image.original_storage_key.as_ref().zip(
+            image
+                .original_mime_type
+                .as_ref()
+                .and_then(|mime_type| mime_type.parse::<mime::Mime>().ok()),
+        );
+        let source = match original {
+            Some((original_key, content_type)) => {
+                download_original(storage.as_ref(), bucket, original_key, content_type).await?
+            }
+            None => download_image(storage.as_ref(), bucket, &image.storage_key).await?,
+        };
+        let size = transform_and_upload(storage.as_ref(), bucket, source, &rendition, &key)
+            .instrument(
+                info_span!(
+                    "IMAGE_TRANSFORM",
+                    image_id = image.id,
+                    image_key = key.as_str()
+                )
+                .or_current(),
+            )
+            .await?;
+
+        // Concurrent requests may have cached the same transformation
+        image_transform::Entity::insert(image_transform::ActiveModel {
+            image_id: Set(image.id),
+            storage_key: Set(key.clone()),
+            size: Set(Some(size)),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::column(image_transform::Column::StorageKey)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(data.conn())
+        .await
+        .map_api_err()?;
+    }
+
+    Ok(HttpResponse::Found()
+        .insert_header((
+            LOCATION,
+            format!(
+                "{base_url}/{bucket}/{key}",
+                base_url = settings.storage_base_url()
+            ),
+        ))
+        .finish())
+}
+
+/// Trashes (or restores) an image along with its lazy image
+async fn set_images_deleted_at(
+    conn: &DatabaseConnection,
+    image: Model,
+    lz_image: Option<Model>,
+    deleted_at: Option<DateTime<Utc>>,
+) -> Result<(Model, Option<Model>), ApiError> {
+    let lz_image = match lz_image {
+        Some(lz_image) => {
+            let mut lz_image = lz_image.into_active_model();
+            lz_image.deleted_at = Set(deleted_at);
+            Some(lz_image.update(conn).await.map_api_err()?)
+        }
+        None => None,
+    };
+
+    let mut image = image.into_active_model();
+    image.deleted_at = Set(deleted_at);
+    let image = image.update(conn).await.map_api_err()?;
+
+    Ok((image, lz_image))
+}
+
+/// Original stored from the `image` field of an upload
+struct StoredUpload {
+    key: String,
+    /// Prefix of the storage keys of the upload
+    id: String,
+    filename: String,
+    mime_type: &'static str,
+    size: usize,
+}
+
+impl StoredUpload {
+    fn job_options(&self, fit: ImageFit, alternate_formats: Vec<ImageFormat>) -> ImageJobOptions {
+        ImageJobOptions {
+            id: self.id.clone(),
+            filename: self.filename.clone(),
+            fit,
+            alternate_formats,
+        }
+    }
+}
+
+/// Reads the `image` field of the payload, checks it is a supported image
+/// fitting in the namespace quota and stores it as the original renditions
+/// are generated from
+async fn process_upload(
+    payload: &mut Multipart,
+    storage: StorageExt,
+    data: &AppState,
+    namespace: &str,
+) -> Result<StoredUpload, ActixError> {
+    let s3_bucket = data.settings().storage().s3().buckets().image();
+    let limits = data.settings().storage().upload().image();
+    let mut stored_upload: Option<StoredUpload> = None;
 
     while let Some(Ok(field)) = payload.next().await {
         match field.name() {
@@ -163,164 +586,140 @@ pub async fn upload_image(
                     )),
                 }?;
 
-                let mut image: Vec<u8> = Vec::new();
-                while let Some(bytes) = field.next().await {
-                    for byte in bytes? {
-                        image.push(byte)
+                // The upload is rejected as soon as it goes over the limit
+                let max_size = *limits.max_size();
+                let mut bytes: Vec<u8> = Vec::new();
+                while let Some(chunk) = field.next().await {
+                    let chunk = chunk?;
+                    let size = bytes.len() + chunk.len();
+                    if size > max_size as usize {
+                        return Err(ApiError::FileTooBig(max_size.into(), size as u64).into());
                     }
+                    bytes.extend_from_slice(&chunk);
                 }
 
-                let image = image::load_from_memory(image.as_slice()).map_err(|e| {
-                    warn!(
-                        error_message = format!("{:?}", e).as_str(),
-                        "Cannot load image"
-                    );
-                    ApiError::ImageNotDecodable
-                })?;
+                let size = bytes.len();
+                check_quota(
+                    data.conn(),
+                    data.settings().storage().quota(),
+                    namespace,
+                    size as u64,
+                )
+                .await?;
+                let (max_width, max_height) = (*limits.max_width(), *limits.max_height());
+                let original =
+                    run_blocking(move || probe_upload(bytes, &content_type, max_width, max_height))
+                        .await?;
 
-                let s3_id: Arc<String> = Uuid::new_v4().to_string().into();
-                let arc_filename: Arc<String> = field
+                let id = Uuid::new_v4().to_string();
+                let filename = field
                     .content_disposition()
                     .get_filename()
                     .map(ToString::to_string)
-                    .unwrap_or_else(|| "unknown".to_string())
-                    .into();
-
-                let arc_image = Arc::new(image);
-                let arc_content_type = Arc::new(content_type);
-                let result: (String, String) = tokio::try_join!(
-                    tokio::spawn(
-                        compress_and_upload(
-                            s3_client.clone(),
-                            s3_bucket.clone(),
-                            arc_image.clone(),
-                            1920,
-                            1080,
-                            FilterType::Triangle,
-                            false,
-                            s3_id.clone(),
-                            arc_filename.clone(),
-                            arc_content_type.clone(),
-                        )
-                        .instrument(
-                            info_span!(
-                                "MAIN_IMAGE_PROCESSING",
-                                image_bucket = format!("{:?}", s3_bucket).as_str(),
-                                image_width = 1920_u32,
-                                image_height = 1080_u32,
-                                image_filter = format!("{:?}", FilterType::Triangle).as_str(),
-                                image_lazy = false,
-                                image_filename = format!("{:?}", arc_filename).as_str(),
-                                image_content_type = format!("{:?}", arc_content_type).as_str()
-                            )
-                            .or_current()
-                        )
-                    )
-                    .map_err(|e| {
-                        error!(
-                            error_message = format!("{:?}", e).as_str(),
-                            "An error occured while joining async task compress and upload"
-                        );
-                        ApiError::InternalServerError
-                    })
-                    .and_then(ready),
-                    tokio::spawn(
-                        compress_and_upload(
-                            s3_client.clone(),
-                            s3_bucket.clone(),
-                            arc_image.clone(),
-                            64,
-                            36,
-                            FilterType::Nearest,
-                            true,
-                            s3_id.clone(),
-                            arc_filename.clone(),
-                            arc_content_type.clone(),
-                        )
-                        .instrument(
-                            info_span!(
-                                "LAZY_IMAGE_PROCESSING",
-                                image_bucket = format!("{:?}", s3_bucket).as_str(),
-                                image_width = 64_u32,
-                                image_height = 36_u32,
-                                image_filter = format!("{:?}", FilterType::Nearest).as_str(),
-                                image_lazy = true,
-                                image_filename = format!("{:?}", arc_filename).as_str(),
-                                image_content_type = format!("{:?}", arc_content_type).as_str()
-                            )
-                            .or_current()
+                    .unwrap_or_else(|| "unknown".to_string());
+                let mime_type = original.mime_type;
+
+                let key = store_original(storage.as_ref(), s3_bucket, original, &id, &filename)
+                    .instrument(
+                        info_span!(
+                            "IMAGE_UPLOAD",
+                            image_bucket = s3_bucket,
+                            image_filename = filename.as_str(),
+                            image_content_type = mime_type
                         )
+                        .or_current(),
                     )
-                    .map_err(|e| {
-                        error!(
-                            error_message = format!("{:?}", e).as_str(),
-                            "An error occured while joining async task compress and upload"
-                        );
-                        ApiError::InternalServerError
-                    })
-                    .and_then(ready)
-                )?;
-                image_upload_result = Some(result);
+                    .await?;
+
+                stored_upload = Some(StoredUpload {
+                    key,
+                    id,
+                    filename,
+                    mime_type,
+                    size,
+                });
             }
             _ => continue,
         }
     }
 
-    let (res, res_lazy) =
-        image_upload_result.ok_or_else(|| ApiError::MissingField("image".to_string()))?;
+    Ok(stored_upload.ok_or_else(|| ApiError::MissingField("image".to_string()))?)
+}
 
-    let image_query = query.into_inner();
-    let image_lazy = entity::image::ActiveModel {
-        namespace: Set(api_key.namespace().to_string()),
-        storage_key: Set(res_lazy),
-        alt: Set(image_query.alt().clone()),
-        ..Default::default()
+/// Queues the generation of the renditions of an image with the options of
+/// its last job
+async fn queue_reprocess(conn: &DatabaseConnection, image: Model) -> Result<Model, ApiError> {
+    if has_pending_image_job(conn, image.id).await? {
+        return Err(ApiError::ImageProcessing);
     }
-    .save(data.conn())
-    .await
-    .map_api_err()?;
+    let options = ImageJobOptions::for_reprocess(conn, &image).await?;
 
-    let image = entity::image::ActiveModel {
-        namespace: Set(api_key.namespace().to_string()),
-        storage_key: Set(res),
-        alt: Set(image_query.alt().clone()),
-        lazy_image_id: Set(Some(image_lazy.id.clone().unwrap())),
-        ..Default::default()
-    }
-    .save(data.conn())
-    .await
-    .map_api_err()?;
+    let txn = conn.begin().await.map_api_err()?;
+    enqueue_image_job(&txn, image.id, &options).await?;
 
-    Ok(HttpResponse::Ok().json(ImageOutput::try_from((s3_bucket, image, image_lazy))?))
-}
+    let mut image = image.into_active_model();
+    image.status = Set(ImageStatus::Processing);
+    let image = image.update(&txn).await.map_api_err()?;
+    txn.commit().await.map_api_err()?;
 
-#[delete("/{id}")]
-pub async fn delete_image(
-    data: web::Data<AppState>,
-    api_key: WriteApiKey,
-    path_id: web::Path<i32>,
-) -> Result<HttpResponse, ActixError> {
-    let settings: &Settings = data.settings();
-    let id = path_id.into_inner();
+    Ok(image)
+}
 
-    let (image, lz_image) = Entity::find()
-        .filter(Column::Namespace.eq(api_key.namespace().to_string()))
+/// Finds a non trashed image along with its lazy image, unknown until its
+/// renditions are first generated
+async fn find_image_with_lazy(
+    conn: &DatabaseConnection,
+    namespace: &str,
+    id: i32,
+) -> Result<(Model, Option<Model>), ApiError> {
+    Entity::find()
+        .filter(Column::Namespace.eq(namespace.to_string()))
+        .filter(Column::DeletedAt.is_null())
         .filter(Column::Id.eq(id))
-        .filter(Column::LazyImageId.is_not_null())
+        .filter(main_images())
         .find_also_linked(LazyImageLink)
-        .one(data.conn())
+        .one(conn)
         .await
         .map_api_err()?
-        .and_then(|(img, lz_img_opt): (Model, Option<Model>)| {
-            lz_img_opt.map(|lz_img| (img, lz_img))
-        })
-        .ok_or(ApiError::NotFound)?;
+        .ok_or(ApiError::NotFound)
+}
+
+/// Responds with the image along with its variants
+async fn image_response(
+    data: &AppState,
+    mut response: actix_web::HttpResponseBuilder,
+    image: Model,
+    lz_image: Option<Model>,
+) -> Result<HttpResponse, ActixError> {
+    let bucket = data.settings().storage().s3().buckets().image();
+    let variants = find_image_variants(data.conn(), vec![image.id])
+        .await?
+        .remove(&image.id)
+        .unwrap_or_default();
 
-    image.clone().delete(data.conn()).await.map_api_err()?;
+    Ok(response.json(
+        ImageOutput::from((Arc::new(bucket.clone()), image, lz_image))
+            .with_variants(bucket, variants),
+    ))
+}
+
+/// Saves the edited fields of an image, the lazy image keeping the same alt
+/// text
+async fn save_image_fields(
+    data: &AppState,
+    image: ActiveModel,
+    lz_image: Option<Model>,
+) -> Result<HttpResponse, ActixError> {
+    let image = image.update(data.conn()).await.map_api_err()?;
+
+    let lz_image = match lz_image {
+        Some(lz_image) => {
+            let mut lz_image = lz_image.into_active_model();
+            lz_image.alt = Set(image.alt.clone());
+            Some(lz_image.update(data.conn()).await.map_api_err()?)
+        }
+        None => None,
+    };
 
-    Ok(HttpResponse::Ok().json(ImageOutput::from((
-        Arc::new(settings.s3().buckets().image().clone()),
-        image,
-        lz_image,
-    ))))
+    image_response(data, HttpResponse::Ok(), image, lz_image).await
 }
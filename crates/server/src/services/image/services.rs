@@ -0,0 +1,1093 @@
+use crate::{
+    errors::{utils::MapApiError, ApiError},
+    services::image::svg::{rasterize_svg, sanitize_svg, svg_size},
+    storage::{ObjectOptions, Storage, StorageExt},
+};
+use deunicode::deunicode;
+use entity::{
+    image::{Column as ImageColumn, ImageStatus, Model as ImageModel},
+    image_profile::{self, ImageFit, ImageFormat},
+    image_variant,
+};
+use futures::{
+    future::{ready, try_join_all},
+    TryFutureExt,
+};
+use image::{
+    codecs::{
+        gif::GifDecoder,
+        jpeg::JpegEncoder,
+        png::PngEncoder,
+        webp::{WebPEncoder, WebPQuality},
+    },
+    imageops::FilterType,
+    io::Reader as ImageReader,
+    AnimationDecoder, ColorType, DynamicImage, GenericImageView, ImageEncoder, ImageError,
+};
+use ravif::{Img, RGBA8};
+use sea_orm::{prelude::*, ActiveEnum, ActiveValue::Set, Condition, ConnectionTrait};
+use std::{collections::HashMap, ffi::OsStr, io::Cursor, path::Path, sync::Arc};
+use tracing::{error, info_span, warn, Instrument};
+
+/// AVIF encoding speed, from 1 (slowest, smallest files) to 10
+const AVIF_SPEED: u8 = 6;
+/// Largest side of the image the blurhash is computed on
+const BLURHASH_THUMBNAIL_SIZE: u32 = 64;
+/// Largest side of the image the dominant color is computed on
+const COLOR_THUMBNAIL_SIZE: u32 = 128;
+/// Largest side SVG uploads are rasterized at to generate their renditions
+const SVG_RASTER_SIZE: u32 = 1920;
+
+#[derive(Clone, Debug)]
+pub enum RenditionKind {
+    Main,
+    Lazy,
+    /// Generated from the image profile with the given name
+    Variant(String),
+    /// Generated on demand from the main image
+    Transform,
+}
+
+/// Describes how an uploaded image is resized and encoded
+#[derive(Clone, Debug)]
+pub struct Rendition {
+    pub kind: RenditionKind,
+    pub width: u32,
+    pub height: u32,
+    pub filter: FilterType,
+    pub fit: ImageFit,
+    /// Formats the rendition is encoded to, the first one being its main format
+    pub formats: Vec<ImageFormat>,
+    pub quality: u8,
+    /// Point to keep in view when cropping to cover the box, from 0 to 1
+    /// along each axis, the center by default
+    pub focal_point: Option<(f64, f64)>,
+}
+
+impl Rendition {
+    pub fn main(format: ImageFormat, fit: ImageFit) -> Self {
+        Self {
+            kind: RenditionKind::Main,
+            width: 1920,
+            height: 1080,
+            filter: FilterType::Triangle,
+            fit,
+            formats: vec![format],
+            quality: 75,
+            focal_point: None,
+        }
+    }
+
+    pub fn lazy(format: ImageFormat, fit: ImageFit) -> Self {
+        Self {
+            kind: RenditionKind::Lazy,
+            width: 64,
+            height: 36,
+            filter: FilterType::Nearest,
+            fit,
+            formats: vec![format],
+            quality: 75,
+            focal_point: None,
+        }
+    }
+
+    pub fn transform(
+        width: u32,
+        height: u32,
+        fit: ImageFit,
+        format: ImageFormat,
+        quality: u8,
+    ) -> Self {
+        Self {
+            kind: RenditionKind::Transform,
+            width,
+            height,
+            filter: FilterType::Triangle,
+            fit,
+            formats: vec![format],
+            quality,
+            focal_point: None,
+        }
+    }
+
+    pub fn with_focal_point(mut self, focal_point: Option<(f64, f64)>) -> Self {
+        self.focal_point = focal_point;
+        self
+    }
+
+    /// Also encodes the rendition to the given formats
+    pub fn with_alternate_formats(mut self, formats: &[ImageFormat]) -> Self {
+        for format in formats {
+            if !self.formats.contains(format) {
+                self.formats.push(*format);
+            }
+        }
+        self
+    }
+
+    fn key_suffix(&self) -> String {
+        match &self.kind {
+            RenditionKind::Main => String::new(),
+            RenditionKind::Lazy => String::from("__lazy"),
+            RenditionKind::Variant(name) => format!("__v_{name}"),
+            RenditionKind::Transform => format!(
+                "__t_{}x{}_{}_q{}",
+                self.width,
+                self.height,
+                self.fit.to_value(),
+                self.quality
+            ),
+        }
+    }
+}
+
+impl From<&image_profile::Model> for Rendition {
+    fn from(profile: &image_profile::Model) -> Self {
+        Self {
+            kind: RenditionKind::Variant(profile.name.clone()),
+            width: profile.width.unsigned_abs(),
+            height: profile.height.unsigned_abs(),
+            filter: FilterType::Triangle,
+            fit: profile.fit,
+            formats: vec![profile.format],
+            quality: profile.quality.clamp(1, 100) as u8,
+            focal_point: None,
+        }
+    }
+}
+
+/// Storage keys and sizes in bytes, in the order of the rendition formats,
+/// and actual dimensions of an uploaded rendition
+#[derive(Clone, Debug)]
+pub struct UploadedRendition {
+    pub rendition: Rendition,
+    pub keys: Vec<String>,
+    pub sizes: Vec<i64>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl UploadedRendition {
+    /// Size in bytes of the rendition in all of its formats
+    pub fn size(&self) -> i64 {
+        self.sizes.iter().sum()
+    }
+}
+
+pub fn format_extension(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Jpeg => "jpeg",
+        ImageFormat::Png => "png",
+        ImageFormat::Webp => "webp",
+        ImageFormat::Avif => "avif",
+        ImageFormat::Svg => "svg",
+        ImageFormat::Gif => "gif",
+    }
+}
+
+pub fn format_mime(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Jpeg => "image/jpeg",
+        ImageFormat::Png => "image/png",
+        ImageFormat::Webp => "image/webp",
+        ImageFormat::Avif => "image/avif",
+        ImageFormat::Svg => "image/svg+xml",
+        ImageFormat::Gif => "image/gif",
+    }
+}
+
+/// SVG and GIF images are only ever stored as uploaded, renditions cannot be
+/// encoded to these formats
+pub fn is_encodable(format: ImageFormat) -> bool {
+    !matches!(format, ImageFormat::Svg | ImageFormat::Gif)
+}
+
+/// Encodings of a rendition only differ by the extension of their storage key
+pub fn storage_key_with_format(storage_key: &str, format: ImageFormat) -> String {
+    format!("{}.{}", key_stem(storage_key), format_extension(format))
+}
+
+pub fn key_stem(storage_key: &str) -> &str {
+    storage_key
+        .rsplit_once('.')
+        .map(|(stem, _)| stem)
+        .unwrap_or(storage_key)
+}
+
+/// Lazy images are stored in the same table, main images either point to
+/// theirs or are processed for the first time
+pub fn main_images() -> Condition {
+    Condition::any()
+        .add(ImageColumn::LazyImageId.is_not_null())
+        .add(ImageColumn::Status.ne(ImageStatus::Ready))
+}
+
+/// Storage keys of every format an image is stored in, along with its
+/// original
+pub fn image_storage_keys(image: &ImageModel) -> Vec<String> {
+    let mut keys = vec![image.storage_key.clone()];
+
+    // Images being processed for the first time point to their original
+    if let Some(original_key) = &image.original_storage_key {
+        if original_key != &image.storage_key {
+            keys.push(original_key.clone());
+        }
+    }
+
+    keys.extend(
+        image
+            .formats
+            .iter()
+            .filter_map(|format| ImageFormat::try_from_value(format).ok())
+            .map(|format| storage_key_with_format(&image.storage_key, format))
+            .filter(|key| key != &image.storage_key),
+    );
+
+    keys
+}
+
+/// Transformations are cached under a key derived from their source image key
+/// and parameters, so that identical requests share the same object
+pub fn transform_storage_key(source_key: &str, rendition: &Rendition) -> String {
+    format!(
+        "{}{}.{}",
+        key_stem(source_key),
+        rendition.key_suffix(),
+        format_extension(rendition.formats[0])
+    )
+}
+
+/// Only actually transparent images are kept in a format supporting alpha
+pub fn has_transparency(image: &DynamicImage) -> bool {
+    image.color().has_alpha() && image.to_rgba8().pixels().any(|pixel| pixel[3] < u8::MAX)
+}
+
+/// Runs CPU bound image processing on the blocking thread pool, so that it
+/// does not stall the async workers serving requests
+pub async fn run_blocking<T, F>(f: F) -> Result<T, ApiError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ApiError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await.map_err(|e| {
+        error!(
+            error_message = format!("{:?}", e).as_str(),
+            "An error occured while joining blocking image processing task"
+        );
+        ApiError::InternalServerError
+    })?
+}
+
+/// Upload stored before its renditions are generated, SVG documents being
+/// sanitized first
+#[derive(Clone, Debug)]
+pub struct UploadOriginal {
+    pub body: Vec<u8>,
+    pub extension: &'static str,
+    pub mime_type: &'static str,
+}
+
+/// Checks that an upload is an image in a supported format and within the
+/// given dimensions, only reading its header
+pub fn probe_upload(
+    bytes: Vec<u8>,
+    content_type: &mime::Mime,
+    max_width: u32,
+    max_height: u32,
+) -> Result<UploadOriginal, ApiError> {
+    let (body, (width, height), extension, mime_type) =
+        if content_type.essence_str() == mime::IMAGE_SVG.essence_str() {
+            let svg = sanitize_svg(&bytes)?;
+            let dimensions = svg_size(&svg)?;
+
+            (
+                svg,
+                dimensions,
+                format_extension(ImageFormat::Svg),
+                format_mime(ImageFormat::Svg),
+            )
+        } else {
+            let not_decodable = |e: ImageError| {
+                warn!(
+                    error_message = format!("{:?}", e).as_str(),
+                    "Cannot load image"
+                );
+                ApiError::ImageNotDecodable
+            };
+
+            let reader = ImageReader::new(Cursor::new(&bytes))
+                .with_guessed_format()
+                .map_err(|e| not_decodable(e.into()))?;
+            let format = reader.format().ok_or_else(|| {
+                not_decodable(ImageError::Unsupported(
+                    image::error::ImageFormatHint::Unknown.into(),
+                ))
+            })?;
+            let dimensions = reader.into_dimensions().map_err(not_decodable)?;
+
+            (
+                bytes,
+                dimensions,
+                format.extensions_str().first().copied().unwrap_or("bin"),
+                format.to_mime_type(),
+            )
+        };
+
+    if width > max_width || height > max_height {
+        return Err(ApiError::ImageDimensionsTooLarge(
+            (max_width, max_height),
+            (width, height),
+        ));
+    }
+
+    Ok(UploadOriginal {
+        body,
+        extension,
+        mime_type,
+    })
+}
+
+/// Stores the original of an upload, returning its storage key. Originals
+/// are private as they keep the metadata of the upload, such as GPS tags.
+pub async fn store_original(
+    storage: &dyn Storage,
+    bucket: &str,
+    original: UploadOriginal,
+    id: &str,
+    filename: &str,
+) -> Result<String, ApiError> {
+    let key = format!(
+        "{id}__{}__original.{}",
+        clean_file_stem(filename),
+        original.extension
+    );
+
+    storage
+        .put_object(
+            bucket,
+            &key,
+            original.body,
+            ObjectOptions::new(original.mime_type, false)
+                .with_metadata("s3_id", id)
+                .with_metadata("filename", filename)
+                .with_metadata("original", "true"),
+        )
+        .await
+        .map(|_res| key)
+        .map_err(|e| e.into_api_error("An error occured while uploading object"))
+}
+
+/// Upload decoded to generate renditions from
+#[derive(Clone, Debug)]
+pub struct DecodedUpload {
+    /// Raster image renditions are generated from, the first frame of
+    /// animated images
+    pub image: DynamicImage,
+    pub metadata: ImageMetadata,
+    /// Format and content the main rendition is stored as instead of being
+    /// encoded, for SVG and animated GIF uploads
+    pub original: Option<(ImageFormat, Vec<u8>)>,
+}
+
+/// Decodes an upload into an upright image, failing without decoding it when
+/// its header announces dimensions above the given maximum
+pub fn decode_upload(
+    bytes: &[u8],
+    content_type: &mime::Mime,
+    max_width: u32,
+    max_height: u32,
+) -> Result<DecodedUpload, ApiError> {
+    let not_decodable = |e: ImageError| {
+        warn!(
+            error_message = format!("{:?}", e).as_str(),
+            "Cannot load image"
+        );
+        ApiError::ImageNotDecodable
+    };
+
+    let check_dimensions =
+        |(width, height): (u32, u32)| match width > max_width || height > max_height {
+            true => Err(ApiError::ImageDimensionsTooLarge(
+                (max_width, max_height),
+                (width, height),
+            )),
+            false => Ok(()),
+        };
+
+    if content_type.essence_str() == mime::IMAGE_SVG.essence_str() {
+        let svg = sanitize_svg(bytes)?;
+        let (width, height) = svg_size(&svg)?;
+        check_dimensions((width, height))?;
+
+        let image = rasterize_svg(&svg, SVG_RASTER_SIZE, SVG_RASTER_SIZE)?;
+        let metadata = ImageMetadata {
+            width,
+            height,
+            mime_type: Some(format_mime(ImageFormat::Svg)),
+            ..ImageMetadata::read(bytes, &image, None)
+        };
+
+        return Ok(DecodedUpload {
+            image,
+            metadata,
+            original: Some((ImageFormat::Svg, svg)),
+        });
+    }
+
+    let reader = || {
+        ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|e| not_decodable(e.into()))
+    };
+
+    check_dimensions(reader()?.into_dimensions().map_err(not_decodable)?)?;
+
+    let image = reader()?.decode().map_err(not_decodable)?;
+
+    // Renditions are generated upright as they do not keep the EXIF tags
+    let orientation = exif_orientation(bytes);
+    let image = apply_orientation(image, orientation);
+    let metadata = ImageMetadata::read(bytes, &image, orientation);
+
+    // Re-encoding would only keep the first frame of animations
+    let original = is_animated_gif(bytes).then(|| (ImageFormat::Gif, bytes.to_vec()));
+
+    Ok(DecodedUpload {
+        image,
+        metadata,
+        original,
+    })
+}
+
+fn is_animated_gif(bytes: &[u8]) -> bool {
+    GifDecoder::new(Cursor::new(bytes))
+        .map(|decoder| decoder.into_frames().take(2).count() > 1)
+        .unwrap_or(false)
+}
+
+/// Metadata read from an uploaded image, before any rendition is generated
+#[derive(Clone, Debug)]
+pub struct ImageMetadata {
+    /// Dimensions once the EXIF orientation is applied
+    pub width: u32,
+    pub height: u32,
+    /// MIME type of the format the upload was decoded from
+    pub mime_type: Option<&'static str>,
+    /// Size of the upload in bytes
+    pub size: usize,
+    /// EXIF orientation, from 1 to 8, already applied to the decoded image
+    pub orientation: Option<u32>,
+    /// Most common color as `#rrggbb`, unknown for fully transparent images
+    pub dominant_color: Option<String>,
+    pub blurhash: Option<String>,
+}
+
+impl ImageMetadata {
+    pub fn read(bytes: &[u8], image: &DynamicImage, orientation: Option<u32>) -> Self {
+        Self {
+            width: image.width(),
+            height: image.height(),
+            mime_type: image::guess_format(bytes)
+                .ok()
+                .map(|format| format.to_mime_type()),
+            size: bytes.len(),
+            orientation,
+            dominant_color: dominant_color(image),
+            blurhash: compute_blurhash(image),
+        }
+    }
+}
+
+/// EXIF orientation of the upload, if it has any EXIF data
+fn exif_orientation(bytes: &[u8]) -> Option<u32> {
+    exif::Reader::new()
+        .read_from_container(&mut Cursor::new(bytes))
+        .ok()?
+        .get_field(exif::Tag::Orientation, exif::In::PRIMARY)?
+        .value
+        .get_uint(0)
+        .filter(|orientation| (1..=8).contains(orientation))
+}
+
+/// Rotates and flips the image so that it is displayed upright without its
+/// EXIF orientation, which is never written to renditions
+fn apply_orientation(image: DynamicImage, orientation: Option<u32>) -> DynamicImage {
+    match orientation {
+        Some(2) => image.fliph(),
+        Some(3) => image.rotate180(),
+        Some(4) => image.flipv(),
+        Some(5) => image.rotate90().fliph(),
+        Some(6) => image.rotate90(),
+        Some(7) => image.rotate270().fliph(),
+        Some(8) => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// Average color of the most populated bucket of a 4 bits per channel
+/// histogram, computed on a thumbnail and ignoring transparent pixels
+fn dominant_color(image: &DynamicImage) -> Option<String> {
+    let thumbnail = image
+        .thumbnail(COLOR_THUMBNAIL_SIZE, COLOR_THUMBNAIL_SIZE)
+        .to_rgba8();
+
+    let mut buckets: HashMap<(u8, u8, u8), (u32, [u32; 3])> = HashMap::new();
+    for pixel in thumbnail.pixels().filter(|pixel| pixel[3] >= 128) {
+        let (count, sum) = buckets
+            .entry((pixel[0] >> 4, pixel[1] >> 4, pixel[2] >> 4))
+            .or_default();
+        *count += 1;
+        for (channel, value) in sum.iter_mut().zip(pixel.0) {
+            *channel += value as u32;
+        }
+    }
+
+    let (count, [r, g, b]) = buckets.into_values().max_by_key(|(count, _)| *count)?;
+
+    Some(format!(
+        "#{:02x}{:02x}{:02x}",
+        r / count,
+        g / count,
+        b / count
+    ))
+}
+
+/// BlurHash of the image, computed on a thumbnail as the hash only keeps a
+/// handful of components anyway
+fn compute_blurhash(image: &DynamicImage) -> Option<String> {
+    let thumbnail = image.thumbnail(BLURHASH_THUMBNAIL_SIZE, BLURHASH_THUMBNAIL_SIZE);
+    let (components_x, components_y) = match thumbnail.width() >= thumbnail.height() {
+        true => (4, 3),
+        false => (3, 4),
+    };
+
+    blurhash::encode(
+        components_x,
+        components_y,
+        thumbnail.width(),
+        thumbnail.height(),
+        thumbnail.to_rgba8().as_raw(),
+    )
+    .map_err(|e| {
+        warn!(
+            error_message = format!("{:?}", e).as_str(),
+            "Cannot compute image blurhash"
+        );
+    })
+    .ok()
+}
+
+/// Images fitting in the rendition box are never upscaled, unless filled
+fn resize(image: &DynamicImage, rendition: &Rendition) -> DynamicImage {
+    let (width, height) = (rendition.width, rendition.height);
+    let fits = image.width() <= width && image.height() <= height;
+
+    match rendition.fit {
+        ImageFit::Cover if fits => image.clone(),
+        ImageFit::Cover => {
+            let (focal_x, focal_y) = rendition.focal_point.unwrap_or((0.5, 0.5));
+            let scale = f64::max(
+                width as f64 / image.width() as f64,
+                height as f64 / image.height() as f64,
+            );
+            let scaled_width = ((image.width() as f64 * scale).round() as u32).max(width);
+            let scaled_height = ((image.height() as f64 * scale).round() as u32).max(height);
+
+            // The crop box is centered on the focal point, as far as the image
+            // edges allow
+            let x = (focal_x * scaled_width as f64 - width as f64 / 2.0)
+                .clamp(0.0, (scaled_width - width) as f64);
+            let y = (focal_y * scaled_height as f64 - height as f64 / 2.0)
+                .clamp(0.0, (scaled_height - height) as f64);
+
+            image
+                .resize_exact(scaled_width, scaled_height, rendition.filter)
+                .crop_imm(x.round() as u32, y.round() as u32, width, height)
+        }
+        ImageFit::Contain if fits => image.clone(),
+        ImageFit::Contain => image.resize(width, height, rendition.filter),
+        ImageFit::Fill => image.resize_exact(width, height, rendition.filter),
+    }
+}
+
+/// Encoders only write pixels, so metadata of the upload such as EXIF and GPS
+/// tags never makes it to published renditions
+fn encode(image: &DynamicImage, format: ImageFormat, quality: u8) -> Result<Vec<u8>, ApiError> {
+    let mut image_output: Vec<u8> = Vec::new();
+    let (width, height) = image.dimensions();
+
+    let result =
+        match format {
+            ImageFormat::Jpeg => {
+                JpegEncoder::new_with_quality(&mut image_output, quality).encode_image(image)
+            }
+            ImageFormat::Png => {
+                let image = image.to_rgba8();
+                PngEncoder::new(&mut image_output).write_image(
+                    image.as_raw(),
+                    width,
+                    height,
+                    ColorType::Rgba8,
+                )
+            }
+            ImageFormat::Webp if image.color().has_alpha() => {
+                WebPEncoder::new_with_quality(&mut image_output, WebPQuality::lossy(quality))
+                    .encode(image.to_rgba8().as_raw(), width, height, ColorType::Rgba8)
+            }
+            ImageFormat::Webp => {
+                WebPEncoder::new_with_quality(&mut image_output, WebPQuality::lossy(quality))
+                    .encode(image.to_rgb8().as_raw(), width, height, ColorType::Rgb8)
+            }
+            ImageFormat::Avif => {
+                let pixels: Vec<RGBA8> = image
+                    .to_rgba8()
+                    .pixels()
+                    .map(|pixel| RGBA8::new(pixel[0], pixel[1], pixel[2], pixel[3]))
+                    .collect();
+
+                return ravif::Encoder::new()
+                    .with_quality(quality as f32)
+                    .with_speed(AVIF_SPEED)
+                    .encode_rgba(Img::new(pixels.as_slice(), width as usize, height as usize))
+                    .map(|encoded| encoded.avif_file)
+                    .map_err(|e| {
+                        error!(
+                            error_message = format!("{:?}", e).as_str(),
+                            "An error occured while encoding image"
+                        );
+                        ApiError::InternalServerError
+                    });
+            }
+            ImageFormat::Svg | ImageFormat::Gif => {
+                error!(
+                    image_format = format!("{:?}", format).as_str(),
+                    "Cannot encode image to a format only stored as uploaded"
+                );
+                return Err(ApiError::InternalServerError);
+            }
+        };
+
+    result.map_err(|e| {
+        error!(
+            error_message = format!("{:?}", e).as_str(),
+            "An error occured while encoding image"
+        );
+        ApiError::InternalServerError
+    })?;
+
+    Ok(image_output)
+}
+
+/// Stem of the uploaded filename, as used in storage keys
+fn clean_file_stem(filename: &str) -> String {
+    Path::new(filename)
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .map(|v| {
+            // Cleanup filename
+            deunicode(v)
+                .chars()
+                .filter(|c| c.is_ascii())
+                .map(|c| match c {
+                    ' ' => '_',
+                    _ => c,
+                })
+                .collect::<String>()
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Resizes the image once and uploads it in every format of the rendition
+pub async fn compress_and_upload(
+    storage: StorageExt,
+    bucket: Arc<String>,
+    image: Arc<DynamicImage>,
+    rendition: Rendition,
+    id: Arc<String>,
+    filename: Arc<String>,
+) -> Result<UploadedRendition, ApiError> {
+    let (width, height, bodies) = {
+        let rendition = rendition.clone();
+        run_blocking(move || {
+            let image = resize(&image, &rendition);
+            let bodies = rendition
+                .formats
+                .iter()
+                .map(|format| encode(&image, *format, rendition.quality))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok((image.width(), image.height(), bodies))
+        })
+        .await?
+    };
+
+    upload_rendition(
+        storage,
+        bucket,
+        rendition,
+        bodies,
+        (width, height),
+        id,
+        filename,
+    )
+    .await
+}
+
+/// Uploads the main rendition as it was uploaded, once sanitized, for images
+/// which cannot be re-encoded without losing their vectors or animation
+pub async fn upload_original(
+    storage: StorageExt,
+    bucket: Arc<String>,
+    original: Arc<(ImageFormat, Vec<u8>)>,
+    dimensions: (u32, u32),
+    id: Arc<String>,
+    filename: Arc<String>,
+) -> Result<UploadedRendition, ApiError> {
+    let (format, body) = original.as_ref();
+    let rendition = Rendition {
+        kind: RenditionKind::Main,
+        width: dimensions.0,
+        height: dimensions.1,
+        filter: FilterType::Triangle,
+        fit: ImageFit::Contain,
+        formats: vec![*format],
+        quality: 100,
+        focal_point: None,
+    };
+
+    upload_rendition(
+        storage,
+        bucket,
+        rendition,
+        vec![body.clone()],
+        dimensions,
+        id,
+        filename,
+    )
+    .await
+}
+
+/// Uploads the encoded bodies of a rendition, in the order of its formats
+async fn upload_rendition(
+    storage: StorageExt,
+    bucket: Arc<String>,
+    rendition: Rendition,
+    bodies: Vec<Vec<u8>>,
+    (width, height): (u32, u32),
+    id: Arc<String>,
+    filename: Arc<String>,
+) -> Result<UploadedRendition, ApiError> {
+    let file_stem = clean_file_stem(&filename);
+    let sizes = bodies.iter().map(|body| body.len() as i64).collect();
+
+    let mut uploads = Vec::with_capacity(rendition.formats.len());
+    for (format, body) in rendition.formats.iter().copied().zip(bodies) {
+        let key = format!(
+            "{id}__{file_stem}{}.{}",
+            rendition.key_suffix(),
+            format_extension(format)
+        );
+
+        let mut options = ObjectOptions::new(format_mime(format), true)
+            .with_metadata("s3_id", id.to_string())
+            .with_metadata("filename", filename.to_string())
+            .with_metadata(
+                "lazy",
+                if matches!(rendition.kind, RenditionKind::Lazy) {
+                    "true"
+                } else {
+                    "false"
+                },
+            );
+
+        if let RenditionKind::Variant(name) = &rendition.kind {
+            options = options.with_metadata("variant", name);
+        }
+
+        let storage = storage.clone();
+        let bucket = bucket.clone();
+        uploads.push(async move {
+            storage
+                .put_object(&bucket, &key, body, options)
+                .await
+                .map(|_res| key)
+                .map_err(|e| e.into_api_error("An error occured while uploading object"))
+        });
+    }
+
+    Ok(UploadedRendition {
+        keys: try_join_all(uploads).await?,
+        sizes,
+        rendition,
+        width,
+        height,
+    })
+}
+
+/// Renditions generated from an upload, along with the metadata read from it
+#[derive(Clone, Debug)]
+pub struct ProcessedImage {
+    pub main: UploadedRendition,
+    pub lazy: UploadedRendition,
+    pub variants: Vec<UploadedRendition>,
+    pub metadata: ImageMetadata,
+}
+
+/// How the renditions of an image are generated
+#[derive(Clone, Debug)]
+pub struct ImageProcessingOptions {
+    /// How the main and lazy images fit their box
+    pub fit: ImageFit,
+    /// Formats the main image and variants are also encoded to
+    pub alternate_formats: Vec<ImageFormat>,
+    pub focal_point: Option<(f64, f64)>,
+    /// Prefix of the storage keys
+    pub id: Arc<String>,
+    pub filename: Arc<String>,
+}
+
+/// Decodes an original and uploads its main, lazy and profile renditions
+pub async fn generate_renditions(
+    storage: StorageExt,
+    bucket: Arc<String>,
+    original: Vec<u8>,
+    content_type: mime::Mime,
+    limits: (u32, u32),
+    options: &ImageProcessingOptions,
+    profiles: &[image_profile::Model],
+) -> Result<ProcessedImage, ApiError> {
+    let (decoded, format) = {
+        let content_type = content_type.clone();
+        run_blocking(move || {
+            let decoded = decode_upload(&original, &content_type, limits.0, limits.1)?;
+            let format = match has_transparency(&decoded.image) {
+                true => ImageFormat::Png,
+                false => ImageFormat::Jpeg,
+            };
+
+            Ok((decoded, format))
+        })
+        .await?
+    };
+    let metadata = decoded.metadata;
+    let original = decoded.original.map(Arc::new);
+    let image = Arc::new(decoded.image);
+
+    let renditions = [
+        Rendition::main(format, options.fit),
+        Rendition::lazy(format, options.fit),
+    ]
+    .into_iter()
+    .chain(profiles.iter().map(Rendition::from))
+    .map(|rendition| rendition.with_focal_point(options.focal_point))
+    .map(|rendition| match rendition.kind {
+        RenditionKind::Lazy => rendition,
+        // Images stored as uploaded have no alternate formats
+        RenditionKind::Main if original.is_some() => rendition,
+        _ => rendition.with_alternate_formats(&options.alternate_formats),
+    });
+
+    let uploaded: Vec<UploadedRendition> = try_join_all(renditions.map(|rendition| {
+        let span = info_span!(
+            "IMAGE_PROCESSING",
+            image_bucket = format!("{:?}", bucket).as_str(),
+            image_rendition = format!("{:?}", rendition.kind).as_str(),
+            image_width = rendition.width,
+            image_height = rendition.height,
+            image_filter = format!("{:?}", rendition.filter).as_str(),
+            image_fit = format!("{:?}", rendition.fit).as_str(),
+            image_formats = format!("{:?}", rendition.formats).as_str(),
+            image_filename = format!("{:?}", options.filename).as_str(),
+            image_content_type = format!("{:?}", content_type).as_str()
+        )
+        .or_current();
+
+        let task = match (&rendition.kind, &original) {
+            (RenditionKind::Main, Some(original)) => tokio::spawn(
+                upload_original(
+                    storage.clone(),
+                    bucket.clone(),
+                    original.clone(),
+                    (metadata.width, metadata.height),
+                    options.id.clone(),
+                    options.filename.clone(),
+                )
+                .instrument(span),
+            ),
+            _ => tokio::spawn(
+                compress_and_upload(
+                    storage.clone(),
+                    bucket.clone(),
+                    image.clone(),
+                    rendition,
+                    options.id.clone(),
+                    options.filename.clone(),
+                )
+                .instrument(span),
+            ),
+        };
+
+        task.map_err(|e| {
+            error!(
+                error_message = format!("{:?}", e).as_str(),
+                "An error occured while joining async task compress and upload"
+            );
+            ApiError::InternalServerError
+        })
+        .and_then(ready)
+    }))
+    .await?;
+
+    let mut uploaded = uploaded.into_iter();
+    let (Some(main), Some(lazy)) = (uploaded.next(), uploaded.next()) else {
+        return Err(ApiError::InternalServerError);
+    };
+
+    Ok(ProcessedImage {
+        main,
+        lazy,
+        variants: uploaded.collect(),
+        metadata,
+    })
+}
+
+pub async fn download_object(
+    storage: &dyn Storage,
+    bucket: &str,
+    key: &str,
+) -> Result<Vec<u8>, ApiError> {
+    storage.get_object(bucket, key).await.map_err(|e| {
+        error!(
+            error_message = format!("{:?}", e).as_str(),
+            "An error occured while downloading object"
+        );
+        ApiError::InternalServerError
+    })
+}
+
+/// Downloads and decodes a stored image, SVG images being rasterized as on
+/// upload
+pub async fn download_image(
+    storage: &dyn Storage,
+    bucket: &str,
+    key: &str,
+) -> Result<DynamicImage, ApiError> {
+    let bytes = download_object(storage, bucket, key).await?;
+    let is_svg = key.ends_with(&format!(".{}", format_extension(ImageFormat::Svg)));
+
+    run_blocking(move || {
+        if is_svg {
+            return rasterize_svg(&bytes, SVG_RASTER_SIZE, SVG_RASTER_SIZE);
+        }
+
+        image::load_from_memory(&bytes).map_err(|e| {
+            error!(
+                error_message = format!("{:?}", e).as_str(),
+                "Cannot load stored image"
+            );
+            ApiError::InternalServerError
+        })
+    })
+    .await
+}
+
+/// Downloads and decodes the original of an image as on upload, so that it
+/// is upright
+pub async fn download_original(
+    storage: &dyn Storage,
+    bucket: &str,
+    key: &str,
+    content_type: mime::Mime,
+) -> Result<DynamicImage, ApiError> {
+    let bytes = download_object(storage, bucket, key).await?;
+
+    // Its dimensions were checked when it was uploaded
+    run_blocking(move || Ok(decode_upload(&bytes, &content_type, u32::MAX, u32::MAX)?.image)).await
+}
+
+/// Transforms the image according to the rendition and uploads it in its
+/// first format under the given key, returns its size in bytes
+pub async fn transform_and_upload(
+    storage: &dyn Storage,
+    bucket: &str,
+    image: DynamicImage,
+    rendition: &Rendition,
+    key: &str,
+) -> Result<i64, ApiError> {
+    let format = rendition.formats[0];
+    let body = {
+        let rendition = rendition.clone();
+        run_blocking(move || encode(&resize(&image, &rendition), format, rendition.quality)).await?
+    };
+    let size = body.len() as i64;
+
+    storage
+        .put_object(
+            bucket,
+            key,
+            body,
+            ObjectOptions::new(format_mime(format), true)
+                .with_metadata("transform", rendition.key_suffix()),
+        )
+        .await
+        .map(|_res| size)
+        .map_err(|e| e.into_api_error("An error occured while uploading object"))
+}
+
+pub fn format_values(formats: &[ImageFormat]) -> Vec<String> {
+    formats.iter().map(ActiveEnum::to_value).collect()
+}
+
+/// Stores each format of the variant renditions as its own row
+pub async fn insert_image_variants<C: ConnectionTrait>(
+    conn: &C,
+    image_id: i32,
+    variants: Vec<UploadedRendition>,
+) -> Result<Vec<image_variant::Model>, ApiError> {
+    let mut image_variants = Vec::new();
+    for uploaded in variants {
+        let RenditionKind::Variant(name) = uploaded.rendition.kind else {
+            continue;
+        };
+
+        let formats = uploaded.rendition.formats.into_iter();
+        for ((format, key), size) in formats.zip(uploaded.keys).zip(uploaded.sizes) {
+            let variant = image_variant::ActiveModel {
+                image_id: Set(image_id),
+                name: Set(name.clone()),
+                storage_key: Set(key),
+                width: Set(uploaded.width as i32),
+                height: Set(uploaded.height as i32),
+                format: Set(format),
+                size: Set(Some(size)),
+                ..Default::default()
+            };
+            image_variants.push(variant.insert(conn).await.map_api_err()?);
+        }
+    }
+
+    Ok(image_variants)
+}
+
+/// Fetches the variants of the given images, grouped by image id
+pub async fn find_image_variants<C: ConnectionTrait>(
+    conn: &C,
+    image_ids: Vec<i32>,
+) -> Result<HashMap<i32, Vec<image_variant::Model>>, ApiError> {
+    let mut variants: HashMap<i32, Vec<image_variant::Model>> = HashMap::new();
+
+    if image_ids.is_empty() {
+        return Ok(variants);
+    }
+
+    for variant in image_variant::Entity::find()
+        .filter(image_variant::Column::ImageId.is_in(image_ids))
+        .all(conn)
+        .await
+        .map_api_err()?
+    {
+        variants.entry(variant.image_id).or_default().push(variant);
+    }
+
+    Ok(variants)
+}
@@ -0,0 +1,219 @@
+use crate::errors::ApiError;
+use image::{DynamicImage, RgbaImage};
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader, Writer,
+};
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{self, TreeParsing},
+};
+use tracing::warn;
+
+/// Elements removed along with their content, as they can run scripts or
+/// embed arbitrary documents
+const FORBIDDEN_ELEMENTS: [&str; 3] = ["script", "foreignobject", "iframe"];
+
+fn not_decodable(e: impl std::fmt::Debug) -> ApiError {
+    warn!(
+        error_message = format!("{:?}", e).as_str(),
+        "Cannot load SVG image"
+    );
+    ApiError::ImageNotDecodable
+}
+
+fn local_name(element: &BytesStart) -> String {
+    String::from_utf8_lossy(element.local_name().as_ref()).to_ascii_lowercase()
+}
+
+/// Whether a `url(...)` of the value points outside of the document
+fn has_external_url(value: &str) -> bool {
+    value
+        .to_ascii_lowercase()
+        .split("url(")
+        .skip(1)
+        .any(|url| !url.trim_start_matches(['\'', '"', ' ']).starts_with('#'))
+}
+
+/// Only references to elements of the document are kept, so that rendering
+/// the image never fetches anything
+fn is_safe_attribute(element: &str, key: &str, value: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    let local_key = key.rsplit(':').next().unwrap_or(&key);
+
+    if local_key.starts_with("on") {
+        return false;
+    }
+
+    if local_key == "href" {
+        return value.trim_start().starts_with('#');
+    }
+
+    // Animations could otherwise set a link to a script
+    if matches!(element, "animate" | "set") && local_key == "attributename" {
+        return !value.to_ascii_lowercase().ends_with("href");
+    }
+
+    !has_external_url(value) && !value.to_ascii_lowercase().contains("javascript:")
+}
+
+fn sanitize_element(element: &BytesStart) -> Result<BytesStart<'static>, ApiError> {
+    let name = local_name(element);
+    let mut sanitized = element.to_owned();
+    sanitized.clear_attributes();
+
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(not_decodable)?;
+        let key = String::from_utf8_lossy(attribute.key.as_ref()).to_string();
+        let value = attribute.unescape_value().map_err(not_decodable)?;
+
+        if is_safe_attribute(&name, &key, &value) {
+            sanitized.push_attribute(attribute);
+        }
+    }
+
+    Ok(sanitized.into_owned())
+}
+
+/// Removes scripts, event handlers and references to external resources from
+/// an SVG document. Fails when the document is not an SVG.
+pub fn sanitize_svg(bytes: &[u8]) -> Result<Vec<u8>, ApiError> {
+    let mut reader = Reader::from_reader(bytes);
+    let mut writer = Writer::new(Vec::new());
+    let mut has_root = false;
+    // Depth of the forbidden element being skipped
+    let mut skipped_depth = 0_usize;
+    // Content of the `<style>` element being read, written once checked
+    let mut style: Option<Vec<Event<'static>>> = None;
+
+    loop {
+        let event = reader.read_event().map_err(not_decodable)?;
+
+        if skipped_depth > 0 {
+            match event {
+                Event::Start(_) => skipped_depth += 1,
+                Event::End(_) => skipped_depth -= 1,
+                Event::Eof => return Err(not_decodable("Unexpected end of document")),
+                _ => {}
+            }
+            continue;
+        }
+
+        if let Some(events) = style.as_mut() {
+            let end = matches!(&event, Event::End(_));
+            events.push(event.into_owned());
+
+            if end {
+                let content = events
+                    .iter()
+                    .filter_map(|event| match event {
+                        Event::Text(text) => Some(String::from_utf8_lossy(text).to_string()),
+                        Event::CData(data) => Some(String::from_utf8_lossy(data).to_string()),
+                        _ => None,
+                    })
+                    .collect::<String>()
+                    .to_ascii_lowercase();
+
+                let events = style.take().unwrap_or_default();
+                if !content.contains("@import") && !has_external_url(&content) {
+                    for event in events {
+                        writer.write_event(event).map_err(not_decodable)?;
+                    }
+                }
+            }
+            continue;
+        }
+
+        match event {
+            Event::Start(element)
+                if FORBIDDEN_ELEMENTS.contains(&local_name(&element).as_str()) =>
+            {
+                skipped_depth = 1
+            }
+            Event::Empty(element)
+                if FORBIDDEN_ELEMENTS.contains(&local_name(&element).as_str()) => {}
+            Event::Start(element) => {
+                let name = local_name(&element);
+                if !has_root && name != "svg" {
+                    return Err(not_decodable("Root element is not an SVG"));
+                }
+                has_root = true;
+
+                let element = sanitize_element(&element)?;
+                match name.as_str() {
+                    "style" => style = Some(vec![Event::Start(element)]),
+                    _ => writer
+                        .write_event(Event::Start(element))
+                        .map_err(not_decodable)?,
+                }
+            }
+            Event::Empty(element) => {
+                if !has_root && local_name(&element) != "svg" {
+                    return Err(not_decodable("Root element is not an SVG"));
+                }
+                has_root = true;
+
+                writer
+                    .write_event(Event::Empty(sanitize_element(&element)?))
+                    .map_err(not_decodable)?;
+            }
+            // Doctypes may declare entities, processing instructions may link
+            // stylesheets
+            Event::DocType(_) | Event::PI(_) | Event::Comment(_) => {}
+            Event::Eof => break,
+            event => writer.write_event(event).map_err(not_decodable)?,
+        }
+    }
+
+    match has_root {
+        true => Ok(writer.into_inner()),
+        false => Err(not_decodable("Document has no root element")),
+    }
+}
+
+/// Intrinsic size of an SVG document
+pub fn svg_size(svg: &[u8]) -> Result<(u32, u32), ApiError> {
+    let tree = usvg::Tree::from_data(svg, &usvg::Options::default()).map_err(not_decodable)?;
+    let size = tree.size.to_int_size();
+
+    Ok((size.width(), size.height()))
+}
+
+/// Renders an SVG document to fit the given box, keeping its aspect ratio.
+/// Vector images are scaled up as well as down.
+pub fn rasterize_svg(svg: &[u8], width: u32, height: u32) -> Result<DynamicImage, ApiError> {
+    let mut tree = usvg::Tree::from_data(svg, &usvg::Options::default()).map_err(not_decodable)?;
+    tree.calculate_abs_transforms();
+    tree.calculate_bounding_boxes();
+
+    let size = tree.size.to_int_size().scale_to(
+        usvg::Size::from_wh(width as f32, height as f32)
+            .ok_or_else(|| not_decodable("Invalid raster size"))?
+            .to_int_size(),
+    );
+    let mut pixmap = Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| not_decodable("Invalid raster size"))?;
+
+    resvg::render(
+        &tree,
+        Transform::from_scale(
+            size.width() as f32 / tree.size.width(),
+            size.height() as f32 / tree.size.height(),
+        ),
+        &mut pixmap.as_mut(),
+    );
+
+    // Pixmaps hold premultiplied colors
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    RgbaImage::from_raw(size.width(), size.height(), pixels)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| not_decodable("Invalid raster size"))
+}
@@ -0,0 +1,14 @@
+use crate::services::image_profile::routes::{
+    delete_image_profile, list_image_profiles, put_image_profile,
+};
+use actix_web::{web::scope, Scope};
+
+mod models;
+mod routes;
+
+pub fn image_profile_service() -> Scope {
+    scope("/image-profile")
+        .service(list_image_profiles)
+        .service(put_image_profile)
+        .service(delete_image_profile)
+}
@@ -0,0 +1,128 @@
+use crate::{errors::ApiError, services::image::services::is_encodable};
+use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use entity::image_profile::{self, ImageFit, ImageFormat};
+use getset::Getters;
+use sea_orm::ActiveValue::Set;
+use serde::{Deserialize, Serialize};
+
+const MAX_DIMENSION: i32 = 4096;
+
+fn default_fit() -> ImageFit {
+    ImageFit::Cover
+}
+
+fn default_format() -> ImageFormat {
+    ImageFormat::Jpeg
+}
+
+fn default_quality() -> i32 {
+    75
+}
+
+/// Profile names end up in storage keys, so they are restricted to lowercase
+/// alphanumeric characters, `-` and `_`
+pub fn validate_profile_name(name: &str) -> Result<(), ApiError> {
+    let valid = !name.is_empty()
+        && name.len() <= 32
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+
+    match valid {
+        true => Ok(()),
+        false => Err(ApiError::InvalidField(
+            "name".to_string(),
+            "expected 1 to 32 lowercase alphanumeric characters, \"-\" or \"_\"".to_string(),
+        )),
+    }
+}
+
+#[derive(Deserialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct ImageProfileInput {
+    width: i32,
+    height: i32,
+    #[serde(default = "default_fit")]
+    fit: ImageFit,
+    #[serde(default = "default_format")]
+    format: ImageFormat,
+    #[serde(default = "default_quality")]
+    quality: i32,
+}
+
+impl ImageProfileInput {
+    pub fn validate(&self) -> Result<(), ApiError> {
+        for (field, value) in [("width", self.width), ("height", self.height)] {
+            if !(1..=MAX_DIMENSION).contains(&value) {
+                return Err(ApiError::InvalidField(
+                    field.to_string(),
+                    format!("expected a value between 1 and {MAX_DIMENSION}"),
+                ));
+            }
+        }
+
+        if !is_encodable(self.format) {
+            return Err(ApiError::InvalidField(
+                "format".to_string(),
+                "expected jpeg, png, webp or avif".to_string(),
+            ));
+        }
+
+        if !(1..=100).contains(&self.quality) {
+            return Err(ApiError::InvalidField(
+                "quality".to_string(),
+                "expected a value between 1 and 100".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn apply(&self, model: &mut image_profile::ActiveModel) {
+        model.width = Set(self.width);
+        model.height = Set(self.height);
+        model.fit = Set(self.fit);
+        model.format = Set(self.format);
+        model.quality = Set(self.quality);
+    }
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageProfileOutput {
+    id: i32,
+    name: String,
+    width: i32,
+    height: i32,
+    fit: ImageFit,
+    format: ImageFormat,
+    quality: i32,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<image_profile::Model> for ImageProfileOutput {
+    fn from(model: image_profile::Model) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            width: model.width,
+            height: model.height,
+            fit: model.fit,
+            format: model.format,
+            quality: model.quality,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+impl Responder for ImageProfileOutput {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{
+    errors::{utils::MapApiError, ApiError},
+    middlewares::api_key::{ApiKey, WriteApiKey},
+    server::AppState,
+    services::image_profile::models::{
+        validate_profile_name, ImageProfileInput, ImageProfileOutput,
+    },
+};
+use actix_web::{delete, get, put, web, Error as ActixError, HttpResponse};
+use entity::image_profile::{ActiveModel, Column, Entity, Model};
+use sea_orm::{prelude::*, ActiveValue::Set, IntoActiveModel, QueryOrder, TryIntoModel};
+
+#[get("")]
+pub async fn list_image_profiles(
+    data: web::Data<AppState>,
+    api_key: ApiKey,
+) -> Result<HttpResponse, ActixError> {
+    let profiles: Vec<ImageProfileOutput> = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .order_by_asc(Column::Name)
+        .all(data.conn())
+        .await
+        .map_api_err()?
+        .into_iter()
+        .map(ImageProfileOutput::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(profiles))
+}
+
+/// Creates or replaces a profile, existing images are not regenerated
+#[put("/{name}")]
+pub async fn put_image_profile(
+    data: web::Data<AppState>,
+    path_name: web::Path<String>,
+    body: web::Json<ImageProfileInput>,
+    api_key: WriteApiKey,
+) -> Result<ImageProfileOutput, ApiError> {
+    let name = path_name.into_inner();
+    validate_profile_name(&name)?;
+    body.validate()?;
+
+    let current: Option<Model> = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::Name.eq(name.as_str()))
+        .one(data.conn())
+        .await
+        .map_api_err()?;
+
+    let mut model = match current {
+        Some(current) => current.into_active_model(),
+        None => ActiveModel {
+            namespace: Set(api_key.namespace().to_owned()),
+            name: Set(name),
+            ..Default::default()
+        },
+    };
+    body.apply(&mut model);
+
+    Ok(model
+        .save(data.conn())
+        .await
+        .map_api_err()?
+        .try_into_model()?
+        .into())
+}
+
+#[delete("/{name}")]
+pub async fn delete_image_profile(
+    data: web::Data<AppState>,
+    path_name: web::Path<String>,
+    api_key: WriteApiKey,
+) -> Result<ImageProfileOutput, ApiError> {
+    let profile: Model = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::Name.eq(path_name.into_inner()))
+        .one(data.conn())
+        .await
+        .map_api_err()?
+        .ok_or(ApiError::NotFound)?;
+
+    profile.clone().delete(data.conn()).await.map_api_err()?;
+
+    Ok(profile.into())
+}
@@ -0,0 +1,14 @@
+use crate::services::image_transform_size::routes::{
+    delete_image_transform_size, list_image_transform_sizes, put_image_transform_size,
+};
+use actix_web::{web::scope, Scope};
+
+mod models;
+mod routes;
+
+pub fn image_transform_size_service() -> Scope {
+    scope("/image-transform-size")
+        .service(list_image_transform_sizes)
+        .service(put_image_transform_size)
+        .service(delete_image_transform_size)
+}
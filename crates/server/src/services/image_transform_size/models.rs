@@ -0,0 +1,50 @@
+use crate::errors::ApiError;
+use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use entity::image_transform_size;
+use serde::Serialize;
+
+const MAX_DIMENSION: u32 = 4096;
+
+pub fn validate_size(width: u32, height: u32) -> Result<(), ApiError> {
+    for (field, value) in [("width", width), ("height", height)] {
+        if !(1..=MAX_DIMENSION).contains(&value) {
+            return Err(ApiError::InvalidField(
+                field.to_string(),
+                format!("expected a value between 1 and {MAX_DIMENSION}"),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageTransformSizeOutput {
+    id: i32,
+    width: i32,
+    height: i32,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<image_transform_size::Model> for ImageTransformSizeOutput {
+    fn from(model: image_transform_size::Model) -> Self {
+        Self {
+            id: model.id,
+            width: model.width,
+            height: model.height,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+impl Responder for ImageTransformSizeOutput {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{
+    errors::{utils::MapApiError, ApiError},
+    middlewares::api_key::{ApiKey, WriteApiKey},
+    server::AppState,
+    services::image_transform_size::models::{validate_size, ImageTransformSizeOutput},
+};
+use actix_web::{delete, get, put, web, Error as ActixError, HttpResponse};
+use entity::image_transform_size::{ActiveModel, Column, Entity, Model};
+use sea_orm::{prelude::*, ActiveValue::Set, QueryOrder};
+
+#[get("")]
+pub async fn list_image_transform_sizes(
+    data: web::Data<AppState>,
+    api_key: ApiKey,
+) -> Result<HttpResponse, ActixError> {
+    let sizes: Vec<ImageTransformSizeOutput> = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .order_by_asc(Column::Width)
+        .order_by_asc(Column::Height)
+        .all(data.conn())
+        .await
+        .map_api_err()?
+        .into_iter()
+        .map(ImageTransformSizeOutput::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(sizes))
+}
+
+/// Allows images of the namespace to be transformed to the given size
+#[put(r"/{width:\d+}x{height:\d+}")]
+pub async fn put_image_transform_size(
+    data: web::Data<AppState>,
+    path: web::Path<(u32, u32)>,
+    api_key: WriteApiKey,
+) -> Result<ImageTransformSizeOutput, ApiError> {
+    let (width, height) = path.into_inner();
+    validate_size(width, height)?;
+
+    let current: Option<Model> = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::Width.eq(width as i32))
+        .filter(Column::Height.eq(height as i32))
+        .one(data.conn())
+        .await
+        .map_api_err()?;
+
+    if let Some(current) = current {
+        return Ok(current.into());
+    }
+
+    Ok(ActiveModel {
+        namespace: Set(api_key.namespace().to_owned()),
+        width: Set(width as i32),
+        height: Set(height as i32),
+        ..Default::default()
+    }
+    .insert(data.conn())
+    .await
+    .map_api_err()?
+    .into())
+}
+
+/// Already cached transformations to this size are kept
+#[delete(r"/{width:\d+}x{height:\d+}")]
+pub async fn delete_image_transform_size(
+    data: web::Data<AppState>,
+    path: web::Path<(u32, u32)>,
+    api_key: WriteApiKey,
+) -> Result<ImageTransformSizeOutput, ApiError> {
+    let (width, height) = path.into_inner();
+
+    let size: Model = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::Width.eq(width as i32))
+        .filter(Column::Height.eq(height as i32))
+        .one(data.conn())
+        .await
+        .map_api_err()?
+        .ok_or(ApiError::NotFound)?;
+
+    size.clone().delete(data.conn()).await.map_api_err()?;
+
+    Ok(size.into())
+}
@@ -1,20 +1,23 @@
 use crate::errors::{utils::TryUnwrapActiveValue, ApiError};
 use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use entity::{
     locale::Model as LocaleModel,
     locale_data::{ActiveModel as LocalDataActiveModel, Model as LocaleDataModel},
 };
 use serde::Serialize;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[derive(Serialize)]
-pub struct LocalesMessages(HashMap<String, Value>);
+#[serde(transparent)]
+pub struct LocalesMessages {
+    messages: BTreeMap<String, Value>,
+}
 
 impl From<Vec<(LocaleModel, Option<LocaleDataModel>)>> for LocalesMessages {
     fn from(entities: Vec<(LocaleModel, Option<LocaleDataModel>)>) -> Self {
-        let mut locales_messages = HashMap::new();
+        let mut locales_messages = BTreeMap::new();
 
         for entity in entities {
             if let Some(locale_data) = entity.1 {
@@ -24,7 +27,9 @@ impl From<Vec<(LocaleModel, Option<LocaleDataModel>)>> for LocalesMessages {
             }
         }
 
-        LocalesMessages(locales_messages)
+        LocalesMessages {
+            messages: locales_messages,
+        }
     }
 }
 
@@ -43,8 +48,8 @@ pub struct LocaleOutput {
     namespace: String,
     lang: String,
     messages: Value,
-    created_at: chrono::DateTime<Utc>,
-    updated_at: chrono::DateTime<Utc>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
 }
 
 impl TryFrom<(LocaleModel, LocalDataActiveModel)> for LocaleOutput {
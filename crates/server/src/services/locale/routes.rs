@@ -6,6 +6,7 @@ use crate::{
         models::{LocaleOutput, LocalesMessages},
         repository::LocaleRepository,
     },
+    utils::http_cache::Cached,
 };
 use actix_web::{get, put, web, web::Path};
 use serde_json::Value;
@@ -14,10 +15,17 @@ use serde_json::Value;
 pub async fn get_locales(
     data: web::Data<AppState>,
     api_key: ApiKey,
-) -> Result<LocalesMessages, ApiError> {
+) -> Result<Cached<LocalesMessages>, ApiError> {
     let namespace = api_key.namespace().to_owned();
     let locales = data.conn().get_all_locales_by_namespace(namespace).await?;
-    Ok(locales)
+
+    // Removed locales do not move the latest update forward, locales are only
+    // validated by their ETag
+    Ok(Cached::new(
+        locales,
+        None,
+        data.settings().cache_control().locale(),
+    ))
 }
 
 #[put("/{lang}")]
@@ -0,0 +1,20 @@
+use crate::services::media::routes::{
+    create_folder, delete_folder, list_folders, list_media, list_media_references,
+    move_media_items, update_folder,
+};
+use actix_web::{web::scope, Scope};
+
+pub mod models;
+mod routes;
+pub mod services;
+
+pub fn media_service() -> Scope {
+    scope("/media")
+        .service(list_media)
+        .service(move_media_items)
+        .service(list_folders)
+        .service(create_folder)
+        .service(update_folder)
+        .service(delete_folder)
+        .service(list_media_references)
+}
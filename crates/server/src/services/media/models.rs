@@ -0,0 +1,237 @@
+use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use entity::media_folder;
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::ApiError;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaType {
+    Image,
+    File,
+}
+
+/// Folder the listed media must be in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FolderFilter {
+    Root,
+    Folder(i32),
+}
+
+#[derive(Debug, Deserialize, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct MediaFilter {
+    /// Comma separated media types, e.g. `image,file`, every type when unset
+    r#type: Option<String>,
+    /// `root` or the ID of a folder, media of every folder are listed when
+    /// unset
+    folder: Option<String>,
+    tag: Option<String>,
+}
+
+impl MediaFilter {
+    /// Media of every type in the folder
+    pub fn in_folder(folder: FolderFilter) -> Self {
+        Self {
+            r#type: None,
+            folder: Some(match folder {
+                FolderFilter::Root => "root".to_string(),
+                FolderFilter::Folder(id) => id.to_string(),
+            }),
+            tag: None,
+        }
+    }
+
+    pub fn types(&self) -> Result<Vec<MediaType>, ApiError> {
+        let Some(types) = &self.r#type else {
+            return Ok(vec![MediaType::Image, MediaType::File]);
+        };
+
+        types
+            .split(',')
+            .map(str::trim)
+            .filter(|media_type| !media_type.is_empty())
+            .map(|media_type| match media_type {
+                "image" => Ok(MediaType::Image),
+                "file" => Ok(MediaType::File),
+                _ => Err(ApiError::InvalidField(
+                    "type".to_string(),
+                    "expected \"image\" or \"file\"".to_string(),
+                )),
+            })
+            .collect()
+    }
+
+    pub fn folder_filter(&self) -> Result<Option<FolderFilter>, ApiError> {
+        match self.folder.as_deref().map(str::trim) {
+            None => Ok(None),
+            Some("root") => Ok(Some(FolderFilter::Root)),
+            Some(id) => id
+                .parse()
+                .map(|id| Some(FolderFilter::Folder(id)))
+                .map_err(|_| {
+                    ApiError::InvalidField(
+                        "folder".to_string(),
+                        "expected \"root\" or a folder ID".to_string(),
+                    )
+                }),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct FolderInput {
+    name: String,
+    /// Created at the root when unset
+    parent_id: Option<i32>,
+}
+
+impl FolderInput {
+    /// Names are trimmed, they cannot contain slashes so that folders can be
+    /// displayed as paths
+    pub fn validated_name(&self) -> Result<String, ApiError> {
+        let name = self.name.trim();
+        match !name.is_empty() && name.chars().count() <= 255 && !name.contains('/') {
+            true => Ok(name.to_string()),
+            false => Err(ApiError::InvalidField(
+                "name".to_string(),
+                "expected 1 to 255 characters, without \"/\"".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct FolderOutput {
+    id: i32,
+    parent_id: Option<i32>,
+    name: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<media_folder::Model> for FolderOutput {
+    fn from(model: media_folder::Model) -> Self {
+        Self {
+            id: model.id,
+            parent_id: model.parent_id,
+            name: model.name,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+impl Responder for FolderOutput {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
+
+pub struct FolderOutputList(pub Vec<FolderOutput>);
+
+impl Responder for FolderOutputList {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self.0)
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct MediaItemInput {
+    r#type: MediaType,
+    id: i32,
+}
+
+#[derive(Deserialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct MoveMediaInput {
+    items: Vec<MediaItemInput>,
+    /// Moved to the root when unset
+    folder_id: Option<i32>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct MediaDeleteQuery {
+    /// Deletes the media even though bloks or posts use it
+    #[serde(default)]
+    force: bool,
+}
+
+/// An image or a file of the media library
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct MediaItemOutput {
+    pub r#type: MediaType,
+    pub id: i32,
+    /// Name the media was uploaded with
+    pub name: String,
+    pub folder_id: Option<i32>,
+    /// Unknown for private files and images processed for the first time
+    pub public_url: Option<String>,
+    /// Content type and size in bytes as uploaded
+    pub content_type: Option<String>,
+    pub size: Option<i64>,
+    pub tags: Vec<String>,
+    pub metadata: Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub struct MediaItemOutputList(pub Vec<MediaItemOutput>);
+
+impl Responder for MediaItemOutputList {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self.0)
+    }
+}
+
+/// Kind of content using a media
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaReferenceKind {
+    Blok,
+    Post,
+}
+
+/// A blok or a post referencing a media
+#[derive(Serialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct MediaReferenceOutput {
+    pub kind: MediaReferenceKind,
+    pub id: i32,
+    /// Component ID of bloks, title of posts
+    pub label: String,
+    /// Page of bloks
+    pub page_id: Option<i32>,
+}
+
+pub struct MediaReferenceOutputList(pub Vec<MediaReferenceOutput>);
+
+impl Responder for MediaReferenceOutputList {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self.0)
+    }
+}
@@ -0,0 +1,182 @@
+use actix_web::{delete, get, post, put, web};
+use entity::media_folder::{ActiveModel, Column, Entity};
+use sea_orm::{prelude::*, ActiveValue::Set, IntoActiveModel, QueryOrder, TransactionTrait};
+
+use crate::{
+    config::Settings,
+    errors::{utils::MapApiError, ApiError},
+    middlewares::api_key::{ApiKey, WriteApiKey},
+    server::AppState,
+    services::media::{
+        models::{
+            FolderFilter, FolderInput, FolderOutput, FolderOutputList, MediaFilter,
+            MediaItemOutputList, MediaReferenceOutputList, MediaType, MoveMediaInput,
+        },
+        services::{
+            check_folder_name, check_media_exists, check_parent_folder, find_folder, find_media,
+            find_references, is_folder_empty, move_media,
+        },
+    },
+};
+
+/// URLs of the image and file buckets
+fn bucket_urls(settings: &Settings) -> (String, String) {
+    let base_url = settings.storage_base_url();
+    (
+        format!("{base_url}/{}", settings.storage().s3().buckets().image()),
+        format!("{base_url}/{}", settings.storage().s3().buckets().file()),
+    )
+}
+
+/// Images and files, across folders unless one is given
+#[get("")]
+pub async fn list_media(
+    data: web::Data<AppState>,
+    api_key: ApiKey,
+    filter: web::Query<MediaFilter>,
+) -> Result<MediaItemOutputList, ApiError> {
+    let (image_url, file_url) = bucket_urls(data.settings());
+    let items = find_media(
+        data.conn(),
+        api_key.namespace(),
+        &filter,
+        (&image_url, &file_url),
+    )
+    .await?;
+
+    Ok(MediaItemOutputList(items))
+}
+
+/// Moves images and files to a folder, or to the root, responds with the
+/// content of the folder
+#[post("/move")]
+pub async fn move_media_items(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    body: web::Json<MoveMediaInput>,
+) -> Result<MediaItemOutputList, ApiError> {
+    let namespace = api_key.namespace().as_str();
+    let folder_id = *body.folder_id();
+    if let Some(folder_id) = folder_id {
+        check_parent_folder(data.conn(), namespace, folder_id, None).await?;
+    }
+
+    let items: Vec<(MediaType, i32)> = body
+        .items()
+        .iter()
+        .map(|item| (*item.r#type(), *item.id()))
+        .collect();
+    let txn = data.conn().begin().await.map_api_err()?;
+    move_media(&txn, namespace, &items, folder_id).await?;
+    txn.commit().await.map_api_err()?;
+
+    let (image_url, file_url) = bucket_urls(data.settings());
+    let folder = MediaFilter::in_folder(match folder_id {
+        Some(folder_id) => FolderFilter::Folder(folder_id),
+        None => FolderFilter::Root,
+    });
+    let items = find_media(data.conn(), namespace, &folder, (&image_url, &file_url)).await?;
+
+    Ok(MediaItemOutputList(items))
+}
+
+/// Bloks and posts whose content uses the media
+#[get("/{type}/{id}/references")]
+pub async fn list_media_references(
+    data: web::Data<AppState>,
+    api_key: ApiKey,
+    path: web::Path<(MediaType, i32)>,
+) -> Result<MediaReferenceOutputList, ApiError> {
+    let (media_type, id) = path.into_inner();
+    let namespace = api_key.namespace().as_str();
+    check_media_exists(data.conn(), namespace, media_type, id).await?;
+
+    Ok(MediaReferenceOutputList(
+        find_references(data.conn(), namespace, media_type, id).await?,
+    ))
+}
+
+#[get("/folders")]
+pub async fn list_folders(
+    data: web::Data<AppState>,
+    api_key: ApiKey,
+) -> Result<FolderOutputList, ApiError> {
+    let folders = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .order_by_asc(Column::Name)
+        .order_by_asc(Column::Id)
+        .all(data.conn())
+        .await
+        .map_api_err()?;
+
+    Ok(FolderOutputList(
+        folders.into_iter().map(FolderOutput::from).collect(),
+    ))
+}
+
+#[post("/folders")]
+pub async fn create_folder(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    body: web::Json<FolderInput>,
+) -> Result<FolderOutput, ApiError> {
+    let namespace = api_key.namespace().as_str();
+    let name = body.validated_name()?;
+    let parent_id = *body.parent_id();
+    if let Some(parent_id) = parent_id {
+        check_parent_folder(data.conn(), namespace, parent_id, None).await?;
+    }
+    check_folder_name(data.conn(), namespace, parent_id, &name, None).await?;
+
+    Ok(ActiveModel {
+        namespace: Set(namespace.to_owned()),
+        parent_id: Set(parent_id),
+        name: Set(name),
+        ..Default::default()
+    }
+    .insert(data.conn())
+    .await
+    .map_api_err()?
+    .into())
+}
+
+/// Renames a folder or moves it to another parent, along with its content
+#[put("/folders/{id}")]
+pub async fn update_folder(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path_id: web::Path<i32>,
+    body: web::Json<FolderInput>,
+) -> Result<FolderOutput, ApiError> {
+    let namespace = api_key.namespace().as_str();
+    let folder = find_folder(data.conn(), namespace, path_id.into_inner()).await?;
+    let name = body.validated_name()?;
+    let parent_id = *body.parent_id();
+    if let Some(parent_id) = parent_id {
+        check_parent_folder(data.conn(), namespace, parent_id, Some(folder.id)).await?;
+    }
+    check_folder_name(data.conn(), namespace, parent_id, &name, Some(folder.id)).await?;
+
+    let mut folder = folder.into_active_model();
+    folder.name = Set(name);
+    folder.parent_id = Set(parent_id);
+
+    Ok(folder.update(data.conn()).await.map_api_err()?.into())
+}
+
+/// Only empty folders can be deleted
+#[delete("/folders/{id}")]
+pub async fn delete_folder(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+    path_id: web::Path<i32>,
+) -> Result<FolderOutput, ApiError> {
+    let folder = find_folder(data.conn(), api_key.namespace(), path_id.into_inner()).await?;
+    if !is_folder_empty(data.conn(), folder.id).await? {
+        return Err(ApiError::FolderNotEmpty);
+    }
+
+    folder.clone().delete(data.conn()).await.map_api_err()?;
+
+    Ok(folder.into())
+}
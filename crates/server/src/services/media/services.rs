@@ -0,0 +1,542 @@
+use std::{cmp::Reverse, collections::HashSet};
+
+use entity::{
+    blok,
+    file::{self, FileStatus, FileVisibility},
+    image,
+    image_profile::ImageFormat,
+    media_folder, media_reference, page, post,
+};
+use sea_orm::{
+    prelude::*, sea_query::Expr, ActiveEnum, ActiveValue::Set, Condition, ConnectionTrait,
+    QueryOrder, QuerySelect,
+};
+
+use crate::{
+    errors::{utils::MapApiError, ApiError},
+    services::{
+        image::services::{format_mime, key_stem, main_images},
+        media::models::{
+            FolderFilter, MediaFilter, MediaItemOutput, MediaReferenceKind, MediaReferenceOutput,
+            MediaType,
+        },
+    },
+};
+
+pub async fn find_folder<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+    id: i32,
+) -> Result<media_folder::Model, ApiError> {
+    media_folder::Entity::find()
+        .filter(media_folder::Column::Namespace.eq(namespace.to_owned()))
+        .filter(media_folder::Column::Id.eq(id))
+        .one(conn)
+        .await
+        .map_api_err()?
+        .ok_or(ApiError::NotFound)
+}
+
+/// Checks that a parent folder exists, the parent of a moved folder cannot be
+/// the folder itself or one of its subfolders
+pub async fn check_parent_folder<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+    parent_id: i32,
+    moved_id: Option<i32>,
+) -> Result<(), ApiError> {
+    let mut ancestor = Some(parent_id);
+    while let Some(id) = ancestor {
+        if Some(id) == moved_id {
+            return Err(ApiError::InvalidField(
+                "parentId".to_string(),
+                "a folder cannot be moved into itself or one of its subfolders".to_string(),
+            ));
+        }
+
+        ancestor = match find_folder(conn, namespace, id).await {
+            Ok(folder) => folder.parent_id,
+            Err(ApiError::NotFound) => {
+                return Err(ApiError::ReferenceNotFound(format!("folder {id}")))
+            }
+            Err(e) => return Err(e),
+        };
+    }
+
+    Ok(())
+}
+
+/// Folder names are unique among the folders of a same parent
+pub async fn check_folder_name<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+    parent_id: Option<i32>,
+    name: &str,
+    except_id: Option<i32>,
+) -> Result<(), ApiError> {
+    let mut query = media_folder::Entity::find()
+        .filter(media_folder::Column::Namespace.eq(namespace.to_owned()))
+        .filter(media_folder::Column::Name.eq(name.to_owned()))
+        .filter(match parent_id {
+            Some(parent_id) => media_folder::Column::ParentId.eq(parent_id),
+            None => media_folder::Column::ParentId.is_null(),
+        });
+    if let Some(except_id) = except_id {
+        query = query.filter(media_folder::Column::Id.ne(except_id));
+    }
+
+    match query.count(conn).await.map_api_err()? {
+        0 => Ok(()),
+        _ => Err(ApiError::FolderNameTaken(name.to_string())),
+    }
+}
+
+/// Whether a folder has no subfolder and no media, media in the trash are
+/// moved to the root along with it
+pub async fn is_folder_empty<C: ConnectionTrait>(conn: &C, id: i32) -> Result<bool, ApiError> {
+    let folders = media_folder::Entity::find()
+        .filter(media_folder::Column::ParentId.eq(id))
+        .count(conn)
+        .await
+        .map_api_err()?;
+    let images = image::Entity::find()
+        .filter(image::Column::FolderId.eq(id))
+        .filter(image::Column::DeletedAt.is_null())
+        .count(conn)
+        .await
+        .map_api_err()?;
+    let files = file::Entity::find()
+        .filter(file::Column::FolderId.eq(id))
+        .filter(file::Column::DeletedAt.is_null())
+        .count(conn)
+        .await
+        .map_api_err()?;
+
+    Ok(folders + images + files == 0)
+}
+
+/// Name a media was uploaded with, its storage key without its unique prefix
+fn media_name(storage_key: &str, separator: &str) -> String {
+    storage_key
+        .split_once(separator)
+        .map(|(_, name)| name)
+        .unwrap_or(storage_key)
+        .to_string()
+}
+
+fn folder_condition<C: ColumnTrait>(column: C, folder: Option<FolderFilter>) -> Condition {
+    match folder {
+        None => Condition::all(),
+        Some(FolderFilter::Root) => Condition::all().add(column.is_null()),
+        Some(FolderFilter::Folder(id)) => Condition::all().add(column.eq(id)),
+    }
+}
+
+fn image_item(model: image::Model, base_url: &str) -> MediaItemOutput {
+    let format = model
+        .formats
+        .first()
+        .and_then(|format| ImageFormat::try_from_value(format).ok());
+
+    MediaItemOutput {
+        r#type: MediaType::Image,
+        id: model.id,
+        name: media_name(key_stem(&model.storage_key), "__"),
+        folder_id: model.folder_id,
+        // Images processed for the first time only point to their original
+        public_url: model
+            .lazy_image_id
+            .map(|_| format!("{base_url}/{}", model.storage_key)),
+        content_type: model
+            .original_mime_type
+            .or_else(|| format.map(|format| format_mime(format).to_string())),
+        size: model.original_size,
+        tags: model.tags,
+        metadata: model.metadata,
+        created_at: model.created_at,
+        updated_at: model.updated_at,
+    }
+}
+
+fn file_item(model: file::Model, base_url: &str) -> MediaItemOutput {
+    MediaItemOutput {
+        r#type: MediaType::File,
+        id: model.id,
+        name: media_name(&model.storage_key, "_"),
+        folder_id: model.folder_id,
+        public_url: (model.visibility == FileVisibility::Public)
+            .then(|| format!("{base_url}/{}", model.storage_key)),
+        content_type: model.content_type,
+        size: model.content_length,
+        tags: model.tags,
+        metadata: model.metadata,
+        created_at: model.created_at,
+        updated_at: model.updated_at,
+    }
+}
+
+/// Images and uploaded files of a namespace, most recent first. `base_urls`
+/// are the URLs of the image and file buckets.
+pub async fn find_media<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+    filter: &MediaFilter,
+    base_urls: (&str, &str),
+) -> Result<Vec<MediaItemOutput>, ApiError> {
+    let types = filter.types()?;
+    let folder = filter.folder_filter()?;
+    let mut items = Vec::new();
+
+    if types.contains(&MediaType::Image) {
+        let mut query = image::Entity::find()
+            .filter(image::Column::Namespace.eq(namespace.to_owned()))
+            .filter(image::Column::DeletedAt.is_null())
+            .filter(main_images())
+            .filter(folder_condition(image::Column::FolderId, folder));
+        if let Some(tag) = filter.tag() {
+            query = query.filter(Expr::cust_with_values(
+                "$1 = any(tags)",
+                vec![tag.to_string()],
+            ));
+        }
+
+        items.extend(
+            query
+                .all(conn)
+                .await
+                .map_api_err()?
+                .into_iter()
+                .map(|model| image_item(model, base_urls.0)),
+        );
+    }
+
+    if types.contains(&MediaType::File) {
+        let mut query = file::Entity::find()
+            .filter(file::Column::Namespace.eq(namespace.to_owned()))
+            .filter(file::Column::DeletedAt.is_null())
+            .filter(file::Column::Status.eq(FileStatus::Uploaded))
+            .filter(folder_condition(file::Column::FolderId, folder));
+        if let Some(tag) = filter.tag() {
+            query = query.filter(Expr::cust_with_values(
+                "$1 = any(tags)",
+                vec![tag.to_string()],
+            ));
+        }
+
+        items.extend(
+            query
+                .all(conn)
+                .await
+                .map_api_err()?
+                .into_iter()
+                .map(|model| file_item(model, base_urls.1)),
+        );
+    }
+
+    // Images first among media created at once
+    items.sort_by_key(|item| {
+        (
+            Reverse(item.created_at),
+            item.r#type == MediaType::File,
+            Reverse(item.id),
+        )
+    });
+
+    Ok(items)
+}
+
+/// Moves media of a namespace to a folder, or to the root. Fails without
+/// moving any of them when one cannot be found.
+pub async fn move_media<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+    items: &[(MediaType, i32)],
+    folder_id: Option<i32>,
+) -> Result<(), ApiError> {
+    let ids = |media_type: MediaType| -> HashSet<i32> {
+        items
+            .iter()
+            .filter(|(item_type, _)| *item_type == media_type)
+            .map(|(_, id)| *id)
+            .collect()
+    };
+    let (image_ids, file_ids) = (ids(MediaType::Image), ids(MediaType::File));
+
+    if !image_ids.is_empty() {
+        let moved = image::Entity::update_many()
+            .col_expr(image::Column::FolderId, Expr::value(folder_id))
+            .filter(image::Column::Namespace.eq(namespace.to_owned()))
+            .filter(image::Column::DeletedAt.is_null())
+            .filter(main_images())
+            .filter(image::Column::Id.is_in(image_ids.iter().copied()))
+            .exec(conn)
+            .await
+            .map_api_err()?;
+        if moved.rows_affected != image_ids.len() as u64 {
+            return Err(ApiError::NotFound);
+        }
+    }
+
+    if !file_ids.is_empty() {
+        let moved = file::Entity::update_many()
+            .col_expr(file::Column::FolderId, Expr::value(folder_id))
+            .filter(file::Column::Namespace.eq(namespace.to_owned()))
+            .filter(file::Column::DeletedAt.is_null())
+            .filter(file::Column::Id.is_in(file_ids.iter().copied()))
+            .exec(conn)
+            .await
+            .map_api_err()?;
+        if moved.rows_affected != file_ids.len() as u64 {
+            return Err(ApiError::NotFound);
+        }
+    }
+
+    Ok(())
+}
+
+/// Fails when the media cannot be found in the namespace
+pub async fn check_media_exists<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+    media_type: MediaType,
+    id: i32,
+) -> Result<(), ApiError> {
+    let count = match media_type {
+        MediaType::Image => {
+            image::Entity::find()
+                .filter(image::Column::Namespace.eq(namespace.to_owned()))
+                .filter(image::Column::DeletedAt.is_null())
+                .filter(main_images())
+                .filter(image::Column::Id.eq(id))
+                .count(conn)
+                .await
+        }
+        MediaType::File => {
+            file::Entity::find()
+                .filter(file::Column::Namespace.eq(namespace.to_owned()))
+                .filter(file::Column::DeletedAt.is_null())
+                .filter(file::Column::Id.eq(id))
+                .count(conn)
+                .await
+        }
+    }
+    .map_api_err()?;
+
+    match count {
+        0 => Err(ApiError::NotFound),
+        _ => Ok(()),
+    }
+}
+
+/// Text and media IDs of a content, IDs are the integers under `imageId` or
+/// `fileId` keys
+#[derive(Default)]
+struct ContentMedia {
+    text: String,
+    image_ids: HashSet<i32>,
+    file_ids: HashSet<i32>,
+}
+
+impl ContentMedia {
+    fn scan(&mut self, content: &Json) {
+        match content {
+            Json::String(text) => {
+                self.text.push_str(text);
+                self.text.push('\n');
+            }
+            Json::Array(values) => values.iter().for_each(|value| self.scan(value)),
+            Json::Object(fields) => {
+                for (key, value) in fields {
+                    let id = value.as_i64().and_then(|id| i32::try_from(id).ok());
+                    match (key.as_str(), id) {
+                        ("imageId", Some(id)) => {
+                            self.image_ids.insert(id);
+                        }
+                        ("fileId", Some(id)) => {
+                            self.file_ids.insert(id);
+                        }
+                        _ => self.scan(value),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Images and files of a namespace a content uses, either by their ID or by
+/// one of their URLs. Renditions, formats and transformations of an image
+/// share the stem of its main key.
+async fn find_used_media<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+    content: &Json,
+) -> Result<(Vec<i32>, Vec<i32>), ApiError> {
+    let mut media = ContentMedia::default();
+    media.scan(content);
+
+    let image_ids = image::Entity::find()
+        .select_only()
+        .column(image::Column::Id)
+        .filter(image::Column::Namespace.eq(namespace.to_owned()))
+        .filter(main_images())
+        .filter(
+            Condition::any()
+                .add(image::Column::Id.is_in(media.image_ids))
+                .add(Expr::cust_with_values(
+                    r#"strpos($1, left("images"."storage_key", length("images"."storage_key") - strpos(reverse("images"."storage_key"), '.'))) > 0"#,
+                    vec![media.text.clone()],
+                )),
+        )
+        .into_tuple::<i32>()
+        .all(conn)
+        .await
+        .map_api_err()?;
+
+    let file_ids = file::Entity::find()
+        .select_only()
+        .column(file::Column::Id)
+        .filter(file::Column::Namespace.eq(namespace.to_owned()))
+        .filter(
+            Condition::any()
+                .add(file::Column::Id.is_in(media.file_ids))
+                .add(Expr::cust_with_values(
+                    r#"strpos($1, "files"."storage_key") > 0"#,
+                    vec![media.text],
+                )),
+        )
+        .into_tuple::<i32>()
+        .all(conn)
+        .await
+        .map_api_err()?;
+
+    Ok((image_ids, file_ids))
+}
+
+/// Replaces the references of a blok or a post by the media its content uses
+pub async fn sync_media_references<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+    kind: MediaReferenceKind,
+    id: i32,
+    content: &Json,
+) -> Result<(), ApiError> {
+    let content_column = match kind {
+        MediaReferenceKind::Blok => media_reference::Column::BlokId,
+        MediaReferenceKind::Post => media_reference::Column::PostId,
+    };
+    media_reference::Entity::delete_many()
+        .filter(content_column.eq(id))
+        .exec(conn)
+        .await
+        .map_api_err()?;
+
+    let (image_ids, file_ids) = find_used_media(conn, namespace, content).await?;
+    let references: Vec<media_reference::ActiveModel> = image_ids
+        .into_iter()
+        .map(|image_id| media_reference::ActiveModel {
+            image_id: Set(Some(image_id)),
+            ..Default::default()
+        })
+        .chain(
+            file_ids
+                .into_iter()
+                .map(|file_id| media_reference::ActiveModel {
+                    file_id: Set(Some(file_id)),
+                    ..Default::default()
+                }),
+        )
+        .map(|mut reference| {
+            match kind {
+                MediaReferenceKind::Blok => reference.blok_id = Set(Some(id)),
+                MediaReferenceKind::Post => reference.post_id = Set(Some(id)),
+            }
+            reference
+        })
+        .collect();
+
+    if !references.is_empty() {
+        media_reference::Entity::insert_many(references)
+            .exec(conn)
+            .await
+            .map_api_err()?;
+    }
+
+    Ok(())
+}
+
+/// Bloks and posts of a namespace using a media, trashed ones excluded
+pub async fn find_references<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+    media_type: MediaType,
+    id: i32,
+) -> Result<Vec<MediaReferenceOutput>, ApiError> {
+    let references = media_reference::Entity::find()
+        .filter(match media_type {
+            MediaType::Image => media_reference::Column::ImageId.eq(id),
+            MediaType::File => media_reference::Column::FileId.eq(id),
+        })
+        .all(conn)
+        .await
+        .map_api_err()?;
+    let blok_ids: Vec<i32> = references.iter().filter_map(|r| r.blok_id).collect();
+    let post_ids: Vec<i32> = references.iter().filter_map(|r| r.post_id).collect();
+
+    let bloks = blok::Entity::find()
+        .find_also_related(page::Entity)
+        .filter(page::Column::Namespace.eq(namespace.to_owned()))
+        .filter(page::Column::DeletedAt.is_null())
+        .filter(blok::Column::DeletedAt.is_null())
+        .filter(blok::Column::Id.is_in(blok_ids))
+        .order_by_asc(blok::Column::Id)
+        .all(conn)
+        .await
+        .map_api_err()?;
+
+    let posts = post::Entity::find()
+        .filter(post::Column::Namespace.eq(namespace.to_owned()))
+        .filter(post::Column::DeletedAt.is_null())
+        .filter(post::Column::Id.is_in(post_ids))
+        .order_by_asc(post::Column::Id)
+        .all(conn)
+        .await
+        .map_api_err()?;
+
+    Ok(bloks
+        .into_iter()
+        .map(|(blok, _)| MediaReferenceOutput {
+            kind: MediaReferenceKind::Blok,
+            id: blok.id,
+            label: blok.component_id,
+            page_id: Some(blok.page_id),
+        })
+        .chain(posts.into_iter().map(|post| MediaReferenceOutput {
+            kind: MediaReferenceKind::Post,
+            id: post.id,
+            label: post.title,
+            page_id: None,
+        }))
+        .collect())
+}
+
+/// Fails when bloks or posts use the media, unless its deletion is forced
+pub async fn check_media_unused<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+    media_type: MediaType,
+    id: i32,
+    force: bool,
+) -> Result<(), ApiError> {
+    if force {
+        return Ok(());
+    }
+
+    match find_references(conn, namespace, media_type, id)
+        .await?
+        .len()
+    {
+        0 => Ok(()),
+        count => Err(ApiError::MediaInUse(count)),
+    }
+}
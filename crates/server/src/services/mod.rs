@@ -2,18 +2,26 @@ pub mod blok;
 pub mod files;
 pub mod git_json_file;
 pub mod image;
+pub mod image_profile;
+pub mod image_transform_size;
 pub mod locale;
+pub mod media;
 pub mod page;
 pub mod post;
 pub mod quote;
+pub mod trash;
+pub mod usage;
 
 use crate::{
-    middlewares::{api_key::ApiKeyMiddlewareFactory, s3::S3ProviderMiddlewareFactory},
+    middlewares::{api_key::ApiKeyMiddlewareFactory, storage::StorageProviderMiddlewareFactory},
     services::{
         blok::blok_service, files::file_service, git_json_file::git_json_file_service,
-        image::image_service, locale::locale_service, page::page_service, post::post_service,
-        quote::quote_service,
+        image::image_service, image_profile::image_profile_service,
+        image_transform_size::image_transform_size_service, locale::locale_service,
+        media::media_service, page::page_service, post::post_service, quote::quote_service,
+        trash::trash_service, usage::usage_service,
     },
+    storage::StorageExt,
 };
 use actix_web::{
     dev::{ServiceFactory, ServiceRequest, ServiceResponse},
@@ -28,7 +36,9 @@ async fn ping() -> impl Responder {
     }))
 }
 
-pub fn api_services() -> Scope<
+pub fn api_services(
+    storage: StorageExt,
+) -> Scope<
     impl ServiceFactory<
         ServiceRequest,
         Response = ServiceResponse,
@@ -48,14 +58,19 @@ pub fn api_services() -> Scope<
             .into()
         }))
         .wrap(ApiKeyMiddlewareFactory::new())
-        .wrap(S3ProviderMiddlewareFactory::new())
+        .wrap(StorageProviderMiddlewareFactory::new(storage))
         .service(ping)
         .service(page_service())
         .service(blok_service())
         .service(image_service())
+        .service(image_profile_service())
+        .service(image_transform_size_service())
         .service(post_service())
         .service(quote_service())
         .service(locale_service())
         .service(git_json_file_service())
         .service(file_service())
+        .service(media_service())
+        .service(trash_service())
+        .service(usage_service())
 }
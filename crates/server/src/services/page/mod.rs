@@ -1,5 +1,6 @@
 use crate::services::page::routes::{
-    create_page, delete_page, get_page_with_blok, list_pages, update_page,
+    create_page, delete_page, get_page, get_page_with_blok, list_pages, patch_page, restore_page,
+    update_page,
 };
 use actix_web::{web::scope, Scope};
 
@@ -10,7 +11,10 @@ pub fn page_service() -> Scope {
     scope("/page")
         .service(list_pages)
         .service(get_page_with_blok)
+        .service(get_page)
         .service(create_page)
         .service(update_page)
+        .service(patch_page)
         .service(delete_page)
+        .service(restore_page)
 }
@@ -1,7 +1,11 @@
-use crate::services::blok::models::BlokOutput;
+use crate::{
+    services::blok::models::BlokOutput,
+    utils::{http_cache::json_response_with_etag, serde_json_patch::Patch},
+};
 use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
 use chrono::{DateTime, Utc};
 use entity::page;
+use getset::Getters;
 use sea_orm::ActiveValue::Set;
 use serde::{Deserialize, Serialize};
 
@@ -24,6 +28,18 @@ impl PageInput {
     }
 }
 
+#[derive(Deserialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct PagePatchInput {
+    #[serde(default)]
+    title: Patch<String>,
+    #[serde(default)]
+    description: Patch<String>,
+    #[serde(default)]
+    path: Patch<String>,
+}
+
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PageOutput {
@@ -84,6 +100,6 @@ impl Responder for PageOutput {
     type Body = BoxBody;
 
     fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
-        HttpResponse::Ok().json(self)
+        json_response_with_etag(&self)
     }
 }
@@ -1,33 +1,76 @@
 use crate::{
-    errors::utils::MapApiError, middlewares::api_key::ApiKey,
-    services::page::models::PageOutputWithBloks,
+    errors::utils::MapApiError,
+    middlewares::api_key::ApiKey,
+    services::page::models::{PageOutputWithBloks, PagePatchInput},
+    utils::{
+        http_cache::{check_if_match, Cached},
+        serde_json_patch::Patch::Value,
+    },
 };
 pub use crate::{
-    errors::{utils::db_err_into_api_err, ApiError},
+    errors::ApiError,
     middlewares::api_key::WriteApiKey,
     server::AppState,
     services::page::models::{PageInput, PageOutput},
 };
-use actix_web::{delete, get, post, put, web, Error as ActixError, HttpResponse};
+use actix_web::{
+    delete, get, http::header::IfMatch, patch, post, put, web, Error as ActixError, HttpResponse,
+};
+use chrono::Utc;
 use entity::page::{Column, Entity, Model};
-use sea_orm::{prelude::*, ActiveValue::Set, Order, QueryOrder, TryIntoModel};
+use sea_orm::{
+    prelude::*, ActiveValue::Set, IntoActiveModel, Order, QueryOrder, QuerySelect,
+    TransactionTrait, TryIntoModel,
+};
 
 #[get("")]
 pub async fn list_pages(
     data: web::Data<AppState>,
     api_key: ApiKey,
-) -> Result<HttpResponse, ActixError> {
+) -> Result<Cached<Vec<PageOutput>>, ApiError> {
     let pages: Vec<Model> = Entity::find()
         .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
         .all(data.conn())
         .await
         .map_api_err()?;
 
-    Ok(HttpResponse::Ok().json(
+    // Removed pages do not move the latest update forward, lists are only
+    // validated by their ETag
+    Ok(Cached::new(
         pages
             .into_iter()
             .map(PageOutput::from)
             .collect::<Vec<PageOutput>>(),
+        None,
+        data.settings().cache_control().page(),
+    ))
+}
+
+#[get("/{id}")]
+pub async fn get_page(
+    data: web::Data<AppState>,
+    path_id: web::Path<i32>,
+    api_key: ApiKey,
+) -> Result<Cached<PageOutput>, ApiError> {
+    let id = path_id.into_inner();
+
+    let page: Model = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
+        .filter(Column::Id.eq(id))
+        .one(data.conn())
+        .await
+        .map_api_err()?
+        .ok_or(ApiError::NotFound)?;
+
+    let last_modified = Some(page.updated_at);
+
+    // Served as on writes, so that its ETag can be sent back in If-Match
+    Ok(Cached::new(
+        PageOutput::from(page),
+        last_modified,
+        data.settings().cache_control().page(),
     ))
 }
 
@@ -36,19 +79,29 @@ pub async fn get_page_with_blok(
     data: web::Data<AppState>,
     path: web::Path<String>,
     api_key: ApiKey,
-) -> Result<HttpResponse, ActixError> {
+) -> Result<Cached<PageOutputWithBloks>, ApiError> {
     let path = format!("/{}", path);
 
     let q = Entity::find()
         .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
         .filter(Column::Path.eq(path.as_str()))
         .find_with_related(entity::blok::Entity)
         .order_by(entity::blok::Column::Priority, Order::Desc);
     let result = q.all(data.conn()).await.map_api_err()?;
 
-    let result: &(Model, Vec<entity::blok::Model>) = result.get(0).ok_or(ApiError::NotFound)?;
+    let (page, mut bloks): (Model, Vec<entity::blok::Model>) =
+        result.into_iter().next().ok_or(ApiError::NotFound)?;
+
+    bloks.retain(|blok| blok.deleted_at.is_none());
 
-    Ok(HttpResponse::Ok().json(PageOutputWithBloks::from(result.to_owned())))
+    // Removed bloks do not move the latest update forward, the page is only
+    // validated by its ETag
+    Ok(Cached::new(
+        PageOutputWithBloks::from((page, bloks)),
+        None,
+        data.settings().cache_control().page(),
+    ))
 }
 
 #[post("")]
@@ -74,28 +127,93 @@ pub async fn update_page(
     path_id: web::Path<i32>,
     body: web::Json<PageInput>,
     api_key: WriteApiKey,
+    if_match: Option<web::Header<IfMatch>>,
 ) -> Result<PageOutput, ApiError> {
     let id = path_id.into_inner();
 
+    // The page stays locked until it is written, so that no other write can
+    // slip in after its precondition was checked
+    let txn = data.conn().begin().await.map_api_err()?;
+
     // Page must exists to be replaced
-    Entity::find()
+    let current: Model = Entity::find()
         .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
         .filter(Column::Id.eq(id))
-        .one(data.conn())
+        .lock_exclusive()
+        .one(&txn)
         .await
         .map_api_err()?
         .ok_or(ApiError::NotFound)?;
 
+    check_if_match(&if_match, &PageOutput::from(current))?;
+
     let mut model = body.active_model();
     model.namespace = Set(api_key.namespace().into());
     model.id = Set(id);
 
-    Ok(model
-        .save(data.conn())
+    let page: Model = model.save(&txn).await.map_api_err()?.try_into_model()?;
+    txn.commit().await.map_api_err()?;
+
+    Ok(page.into())
+}
+
+#[patch("/{id}")]
+pub async fn patch_page(
+    data: web::Data<AppState>,
+    path_id: web::Path<i32>,
+    body: web::Json<PagePatchInput>,
+    api_key: WriteApiKey,
+    if_match: Option<web::Header<IfMatch>>,
+) -> Result<PageOutput, ApiError> {
+    let id = path_id.into_inner();
+
+    if body.title().is_null() {
+        return Err(ApiError::PatchNotNullable(String::from("title")));
+    }
+
+    if body.path().is_null() {
+        return Err(ApiError::PatchNotNullable(String::from("path")));
+    }
+
+    if body.title().is_missing() && body.description().is_missing() && body.path().is_missing() {
+        return Err(ApiError::PatchAtLeastOneField);
+    }
+
+    let txn = data.conn().begin().await.map_api_err()?;
+    let current: Model = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
+        .filter(Column::Id.eq(id))
+        .lock_exclusive()
+        .one(&txn)
         .await
         .map_api_err()?
-        .try_into_model()?
-        .into())
+        .ok_or(ApiError::NotFound)?;
+
+    check_if_match(&if_match, &PageOutput::from(current.clone()))?;
+
+    let mut page = current.into_active_model();
+
+    if let Value(title) = body.title() {
+        page.title = Set(title.clone());
+    }
+
+    if !body.description().is_missing() {
+        page.description = Set(match body.description() {
+            Value(description) => Some(description.clone()),
+            _ => None,
+        });
+    }
+
+    if let Value(path) = body.path() {
+        page.path = Set(path.clone());
+    }
+
+    let page: Model = page.save(&txn).await.map_api_err()?.try_into_model()?;
+    txn.commit().await.map_api_err()?;
+
+    Ok(page.into())
 }
 
 #[delete("/{id}")]
@@ -103,18 +221,65 @@ pub async fn delete_page(
     data: web::Data<AppState>,
     path_id: web::Path<i32>,
     api_key: WriteApiKey,
+    if_match: Option<web::Header<IfMatch>>,
 ) -> Result<HttpResponse, ActixError> {
     let id = path_id.into_inner();
 
+    let txn = data.conn().begin().await.map_api_err()?;
     let page: Model = Entity::find()
         .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
         .filter(Column::Id.eq(id))
-        .one(data.conn())
+        .lock_exclusive()
+        .one(&txn)
         .await
         .map_api_err()?
         .ok_or(ApiError::NotFound)?;
 
-    page.clone().delete(data.conn()).await.map_api_err()?;
+    check_if_match(&if_match, &PageOutput::from(page.clone()))?;
+
+    // Bloks are left untouched, they are hidden along with their trashed page
+    let mut page = page.into_active_model();
+    page.deleted_at = Set(Some(Utc::now()));
+
+    let page = page.update(&txn).await.map_api_err()?;
+    txn.commit().await.map_api_err()?;
 
     Ok(HttpResponse::Ok().json(PageOutput::from(page)))
 }
+
+#[post("/{id}/restore")]
+pub async fn restore_page(
+    data: web::Data<AppState>,
+    path_id: web::Path<i32>,
+    api_key: WriteApiKey,
+) -> Result<PageOutput, ApiError> {
+    let id = path_id.into_inner();
+
+    let page: Model = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_not_null())
+        .filter(Column::Id.eq(id))
+        .one(data.conn())
+        .await
+        .map_api_err()?
+        .ok_or(ApiError::NotFound)?;
+
+    let path_taken = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
+        .filter(Column::Path.eq(page.path.as_str()))
+        .count(data.conn())
+        .await
+        .map_api_err()?
+        > 0;
+
+    if path_taken {
+        return Err(ApiError::RestoreConflict(String::from("path")));
+    }
+
+    let mut page = page.into_active_model();
+    page.deleted_at = Set(None);
+
+    Ok(page.update(data.conn()).await.map_api_err()?.into())
+}
@@ -1,5 +1,6 @@
 use crate::services::post::routes::{
-    create_post, delete_post, get_post, get_post_by_slug, list_posts, update_post,
+    create_post, delete_post, get_post, get_post_by_slug, list_posts, patch_post, restore_post,
+    update_post,
 };
 use actix_web::{web::scope, Scope};
 
@@ -13,5 +14,7 @@ pub fn post_service() -> Scope {
         .service(get_post_by_slug)
         .service(create_post)
         .service(update_post)
+        .service(patch_post)
         .service(delete_post)
+        .service(restore_post)
 }
@@ -1,6 +1,8 @@
+use crate::utils::{http_cache::json_response_with_etag, serde_json_patch::Patch};
 use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
 use chrono::{DateTime, Utc};
 use entity::post;
+use getset::Getters;
 use sea_orm::ActiveValue::Set;
 use serde::{Deserialize, Serialize};
 
@@ -25,6 +27,20 @@ impl PostInput {
     }
 }
 
+#[derive(Deserialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct PostPatchInput {
+    #[serde(default)]
+    title: Patch<String>,
+    #[serde(default)]
+    description: Patch<String>,
+    #[serde(default)]
+    slug: Patch<String>,
+    #[serde(default)]
+    body: Patch<serde_json::Value>,
+}
+
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PostOutput {
@@ -57,6 +73,6 @@ impl Responder for PostOutput {
     type Body = BoxBody;
 
     fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
-        HttpResponse::Ok().json(self)
+        json_response_with_etag(&self)
     }
 }
@@ -1,30 +1,67 @@
-use crate::{errors::utils::MapApiError, middlewares::api_key::ApiKey};
+use crate::{
+    errors::utils::MapApiError,
+    middlewares::api_key::ApiKey,
+    services::{
+        media::{models::MediaReferenceKind, services::sync_media_references},
+        post::models::PostPatchInput,
+    },
+    utils::{
+        http_cache::{check_if_match, Cached},
+        serde_json_patch::Patch::Value,
+    },
+};
 pub use crate::{
-    errors::{utils::db_err_into_api_err, ApiError},
+    errors::ApiError,
     middlewares::api_key::WriteApiKey,
     server::AppState,
     services::post::models::{PostInput, PostOutput},
 };
-use actix_web::{delete, get, post, put, web, Error as ActixError, HttpResponse};
-use entity::post::{Column, Entity, Model};
-use sea_orm::{prelude::*, ActiveValue::Set, TryIntoModel};
+use actix_web::{
+    delete, get, http::header::IfMatch, patch, post, put, web, Error as ActixError, HttpResponse,
+};
+use chrono::Utc;
+use entity::post::{ActiveModel, Column, Entity, Model};
+use sea_orm::{
+    prelude::*, ActiveValue::Set, ConnectionTrait, IntoActiveModel, QuerySelect, TransactionTrait,
+    TryIntoModel,
+};
+
+/// Saves a post along with the references of its body to media
+async fn save_post<C: ConnectionTrait>(conn: &C, post: ActiveModel) -> Result<Model, ApiError> {
+    let post: Model = post.save(conn).await.map_api_err()?.try_into_model()?;
+    sync_media_references(
+        conn,
+        &post.namespace,
+        MediaReferenceKind::Post,
+        post.id,
+        &post.body,
+    )
+    .await?;
+
+    Ok(post)
+}
 
 #[get("")]
 pub async fn list_posts(
     data: web::Data<AppState>,
     api_key: ApiKey,
-) -> Result<HttpResponse, ActixError> {
+) -> Result<Cached<Vec<PostOutput>>, ApiError> {
     let posts: Vec<Model> = Entity::find()
         .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
         .all(data.conn())
         .await
         .map_api_err()?;
 
-    Ok(HttpResponse::Ok().json(
+    // Removed posts do not move the latest update forward, lists are only
+    // validated by their ETag
+    Ok(Cached::new(
         posts
             .into_iter()
             .map(PostOutput::from)
             .collect::<Vec<PostOutput>>(),
+        None,
+        data.settings().cache_control().post(),
     ))
 }
 
@@ -33,18 +70,25 @@ pub async fn get_post(
     data: web::Data<AppState>,
     path_id: web::Path<i32>,
     api_key: ApiKey,
-) -> Result<HttpResponse, ActixError> {
+) -> Result<Cached<PostOutput>, ApiError> {
     let id = path_id.into_inner();
 
     let post = Entity::find()
         .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
         .filter(Column::Id.eq(id))
         .one(data.conn())
         .await
         .map_api_err()?
         .ok_or(ApiError::NotFound)?;
 
-    Ok(HttpResponse::Ok().json(PostOutput::from(post)))
+    let last_modified = Some(post.updated_at);
+
+    Ok(Cached::new(
+        PostOutput::from(post),
+        last_modified,
+        data.settings().cache_control().post(),
+    ))
 }
 
 #[get("/s/{slug}")]
@@ -52,18 +96,25 @@ pub async fn get_post_by_slug(
     data: web::Data<AppState>,
     path_slug: web::Path<String>,
     api_key: ApiKey,
-) -> Result<HttpResponse, ActixError> {
+) -> Result<Cached<PostOutput>, ApiError> {
     let slug = path_slug.into_inner();
 
     let post = Entity::find()
         .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
         .filter(Column::Slug.eq(slug))
         .one(data.conn())
         .await
         .map_api_err()?
         .ok_or(ApiError::NotFound)?;
 
-    Ok(HttpResponse::Ok().json(PostOutput::from(post)))
+    let last_modified = Some(post.updated_at);
+
+    Ok(Cached::new(
+        PostOutput::from(post),
+        last_modified,
+        data.settings().cache_control().post(),
+    ))
 }
 
 #[post("")]
@@ -75,12 +126,11 @@ pub async fn create_post(
     let mut model = body.active_model();
     model.namespace = Set(api_key.namespace().into());
 
-    Ok(model
-        .save(data.conn())
-        .await
-        .map_api_err()?
-        .try_into_model()?
-        .into())
+    let txn = data.conn().begin().await.map_api_err()?;
+    let post = save_post(&txn, model).await?;
+    txn.commit().await.map_api_err()?;
+
+    Ok(post.into())
 }
 
 #[put("/{id}")]
@@ -89,28 +139,105 @@ pub async fn update_post(
     path_id: web::Path<i32>,
     body: web::Json<PostInput>,
     api_key: WriteApiKey,
+    if_match: Option<web::Header<IfMatch>>,
 ) -> Result<PostOutput, ApiError> {
     let id = path_id.into_inner();
 
+    // The post stays locked until it is written, so that no other write can
+    // slip in after its precondition was checked
+    let txn = data.conn().begin().await.map_api_err()?;
+
     // Page must exists to be replaced
-    Entity::find()
+    let current: Model = Entity::find()
         .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
         .filter(Column::Id.eq(id))
-        .one(data.conn())
+        .lock_exclusive()
+        .one(&txn)
         .await
         .map_api_err()?
         .ok_or(ApiError::NotFound)?;
 
+    check_if_match(&if_match, &PostOutput::from(current))?;
+
     let mut model = body.active_model();
     model.namespace = Set(api_key.namespace().into());
     model.id = Set(id);
 
-    Ok(model
-        .save(data.conn())
+    let post = save_post(&txn, model).await?;
+    txn.commit().await.map_api_err()?;
+
+    Ok(post.into())
+}
+
+#[patch("/{id}")]
+pub async fn patch_post(
+    data: web::Data<AppState>,
+    path_id: web::Path<i32>,
+    body: web::Json<PostPatchInput>,
+    api_key: WriteApiKey,
+    if_match: Option<web::Header<IfMatch>>,
+) -> Result<PostOutput, ApiError> {
+    let id = path_id.into_inner();
+
+    if body.title().is_null() {
+        return Err(ApiError::PatchNotNullable(String::from("title")));
+    }
+
+    if body.slug().is_null() {
+        return Err(ApiError::PatchNotNullable(String::from("slug")));
+    }
+
+    if body.body().is_null() {
+        return Err(ApiError::PatchNotNullable(String::from("body")));
+    }
+
+    if body.title().is_missing()
+        && body.description().is_missing()
+        && body.slug().is_missing()
+        && body.body().is_missing()
+    {
+        return Err(ApiError::PatchAtLeastOneField);
+    }
+
+    let txn = data.conn().begin().await.map_api_err()?;
+    let current: Model = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
+        .filter(Column::Id.eq(id))
+        .lock_exclusive()
+        .one(&txn)
         .await
         .map_api_err()?
-        .try_into_model()?
-        .into())
+        .ok_or(ApiError::NotFound)?;
+
+    check_if_match(&if_match, &PostOutput::from(current.clone()))?;
+
+    let mut post = current.into_active_model();
+
+    if let Value(title) = body.title() {
+        post.title = Set(title.clone());
+    }
+
+    if !body.description().is_missing() {
+        post.description = Set(match body.description() {
+            Value(description) => Some(description.clone()),
+            _ => None,
+        });
+    }
+
+    if let Value(slug) = body.slug() {
+        post.slug = Set(slug.clone());
+    }
+
+    if let Value(post_body) = body.body() {
+        post.body = Set(post_body.clone());
+    }
+
+    let post = save_post(&txn, post).await?;
+    txn.commit().await.map_api_err()?;
+
+    Ok(post.into())
 }
 
 #[delete("/{id}")]
@@ -118,18 +245,64 @@ pub async fn delete_post(
     data: web::Data<AppState>,
     path_id: web::Path<i32>,
     api_key: WriteApiKey,
+    if_match: Option<web::Header<IfMatch>>,
 ) -> Result<HttpResponse, ActixError> {
     let id = path_id.into_inner();
 
+    let txn = data.conn().begin().await.map_api_err()?;
     let post: Model = Entity::find()
         .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_null())
         .filter(Column::Id.eq(id))
-        .one(data.conn())
+        .lock_exclusive()
+        .one(&txn)
         .await
         .map_api_err()?
         .ok_or(ApiError::NotFound)?;
 
-    post.clone().delete(data.conn()).await.map_api_err()?;
+    check_if_match(&if_match, &PostOutput::from(post.clone()))?;
+
+    let mut post = post.into_active_model();
+    post.deleted_at = Set(Some(Utc::now()));
+
+    let post = post.update(&txn).await.map_api_err()?;
+    txn.commit().await.map_api_err()?;
 
     Ok(HttpResponse::Ok().json(PostOutput::from(post)))
 }
+
+#[post("/{id}/restore")]
+pub async fn restore_post(
+    data: web::Data<AppState>,
+    path_id: web::Path<i32>,
+    api_key: WriteApiKey,
+) -> Result<PostOutput, ApiError> {
+    let id = path_id.into_inner();
+
+    let post: Model = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::DeletedAt.is_not_null())
+        .filter(Column::Id.eq(id))
+        .one(data.conn())
+        .await
+        .map_api_err()?
+        .ok_or(ApiError::NotFound)?;
+
+    // Slugs are unique across namespaces
+    let slug_taken = Entity::find()
+        .filter(Column::DeletedAt.is_null())
+        .filter(Column::Slug.eq(post.slug.as_str()))
+        .count(data.conn())
+        .await
+        .map_api_err()?
+        > 0;
+
+    if slug_taken {
+        return Err(ApiError::RestoreConflict(String::from("slug")));
+    }
+
+    let mut post = post.into_active_model();
+    post.deleted_at = Set(None);
+
+    Ok(post.update(data.conn()).await.map_api_err()?.into())
+}
@@ -1,5 +1,5 @@
 use crate::services::quote::routes::{
-    create_quote, delete_quote, get_quote, list_quotes, update_quote,
+    create_quote, delete_quote, get_quote, list_quotes, patch_quote, update_quote,
 };
 use actix_web::{web::scope, Scope};
 
@@ -12,5 +12,6 @@ pub fn quote_service() -> Scope {
         .service(get_quote)
         .service(create_quote)
         .service(update_quote)
+        .service(patch_quote)
         .service(delete_quote)
 }
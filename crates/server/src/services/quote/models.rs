@@ -1,6 +1,8 @@
+use crate::utils::{http_cache::json_response_with_etag, serde_json_patch::Patch};
 use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
 use chrono::{DateTime, Utc};
 use entity::quote;
+use getset::Getters;
 use sea_orm::ActiveValue::Set;
 use serde::{Deserialize, Serialize};
 
@@ -21,6 +23,16 @@ impl QuoteInput {
     }
 }
 
+#[derive(Deserialize, Clone, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct QuotePatchInput {
+    #[serde(default)]
+    author: Patch<String>,
+    #[serde(default)]
+    message: Patch<String>,
+}
+
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct QuoteOutput {
@@ -49,6 +61,6 @@ impl Responder for QuoteOutput {
     type Body = BoxBody;
 
     fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
-        HttpResponse::Ok().json(self)
+        json_response_with_etag(&self)
     }
 }
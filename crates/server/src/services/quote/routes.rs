@@ -2,11 +2,16 @@ use crate::{
     errors::{utils::MapApiError, ApiError},
     middlewares::api_key::{ApiKey, WriteApiKey},
     server::AppState,
-    services::quote::models::{QuoteInput, QuoteOutput},
+    services::quote::models::{QuoteInput, QuoteOutput, QuotePatchInput},
+    utils::{http_cache::check_if_match, serde_json_patch::Patch::Value},
+};
+use actix_web::{
+    delete, get, http::header::IfMatch, patch, post, put, web, Error as ActixError, HttpResponse,
 };
-use actix_web::{delete, get, post, put, web, Error as ActixError, HttpResponse};
 use entity::quote::{Column, Entity, Model};
-use sea_orm::{prelude::*, ActiveValue::Set, TryIntoModel};
+use sea_orm::{
+    prelude::*, ActiveValue::Set, IntoActiveModel, QuerySelect, TransactionTrait, TryIntoModel,
+};
 
 #[get("")]
 pub async fn list_quotes(
@@ -32,7 +37,7 @@ pub async fn get_quote(
     data: web::Data<AppState>,
     api_key: ApiKey,
     path_id: web::Path<i32>,
-) -> Result<HttpResponse, ActixError> {
+) -> Result<QuoteOutput, ApiError> {
     let id = path_id.into_inner();
 
     let quote: Model = Entity::find()
@@ -43,7 +48,7 @@ pub async fn get_quote(
         .map_api_err()?
         .ok_or(ApiError::NotFound)?;
 
-    Ok(HttpResponse::Ok().json(QuoteOutput::from(quote)))
+    Ok(quote.into())
 }
 
 #[post("")]
@@ -69,28 +74,84 @@ pub async fn update_quote(
     path_id: web::Path<i32>,
     body: web::Json<QuoteInput>,
     api_key: WriteApiKey,
+    if_match: Option<web::Header<IfMatch>>,
 ) -> Result<QuoteOutput, ApiError> {
     let id = path_id.into_inner();
 
+    // The quote stays locked until it is written, so that no other write can
+    // slip in after its precondition was checked
+    let txn = data.conn().begin().await.map_api_err()?;
+
     // Page must exists to be replaced
-    Entity::find()
+    let current: Model = Entity::find()
         .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
         .filter(Column::Id.eq(id))
-        .one(data.conn())
+        .lock_exclusive()
+        .one(&txn)
         .await
         .map_api_err()?
         .ok_or(ApiError::NotFound)?;
 
+    check_if_match(&if_match, &QuoteOutput::from(current))?;
+
     let mut model = body.active_model();
     model.namespace = Set(api_key.namespace().into());
     model.id = Set(id);
 
-    Ok(model
-        .save(data.conn())
+    let quote: Model = model.save(&txn).await.map_api_err()?.try_into_model()?;
+    txn.commit().await.map_api_err()?;
+
+    Ok(quote.into())
+}
+
+#[patch("/{id}")]
+pub async fn patch_quote(
+    data: web::Data<AppState>,
+    path_id: web::Path<i32>,
+    body: web::Json<QuotePatchInput>,
+    api_key: WriteApiKey,
+    if_match: Option<web::Header<IfMatch>>,
+) -> Result<QuoteOutput, ApiError> {
+    let id = path_id.into_inner();
+
+    if body.author().is_null() {
+        return Err(ApiError::PatchNotNullable(String::from("author")));
+    }
+
+    if body.message().is_null() {
+        return Err(ApiError::PatchNotNullable(String::from("message")));
+    }
+
+    if body.author().is_missing() && body.message().is_missing() {
+        return Err(ApiError::PatchAtLeastOneField);
+    }
+
+    let txn = data.conn().begin().await.map_api_err()?;
+    let current: Model = Entity::find()
+        .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
+        .filter(Column::Id.eq(id))
+        .lock_exclusive()
+        .one(&txn)
         .await
         .map_api_err()?
-        .try_into_model()?
-        .into())
+        .ok_or(ApiError::NotFound)?;
+
+    check_if_match(&if_match, &QuoteOutput::from(current.clone()))?;
+
+    let mut quote = current.into_active_model();
+
+    if let Value(author) = body.author() {
+        quote.author = Set(author.clone());
+    }
+
+    if let Value(message) = body.message() {
+        quote.message = Set(message.clone());
+    }
+
+    let quote: Model = quote.save(&txn).await.map_api_err()?.try_into_model()?;
+    txn.commit().await.map_api_err()?;
+
+    Ok(quote.into())
 }
 
 #[delete("/{id}")]
@@ -98,18 +159,24 @@ pub async fn delete_quote(
     data: web::Data<AppState>,
     path_id: web::Path<i32>,
     api_key: WriteApiKey,
+    if_match: Option<web::Header<IfMatch>>,
 ) -> Result<HttpResponse, ActixError> {
     let id = path_id.into_inner();
 
+    let txn = data.conn().begin().await.map_api_err()?;
     let quote: Model = Entity::find()
         .filter(Column::Namespace.eq(api_key.namespace().to_owned()))
         .filter(Column::Id.eq(id))
-        .one(data.conn())
+        .lock_exclusive()
+        .one(&txn)
         .await
         .map_api_err()?
         .ok_or(ApiError::NotFound)?;
 
-    quote.clone().delete(data.conn()).await.map_api_err()?;
+    check_if_match(&if_match, &QuoteOutput::from(quote.clone()))?;
+
+    quote.clone().delete(&txn).await.map_api_err()?;
+    txn.commit().await.map_api_err()?;
 
     Ok(HttpResponse::Ok().json(QuoteOutput::from(quote)))
 }
@@ -0,0 +1,9 @@
+use crate::services::trash::routes::list_trash;
+use actix_web::{web::scope, Scope};
+
+mod models;
+mod routes;
+
+pub fn trash_service() -> Scope {
+    scope("/trash").service(list_trash)
+}
@@ -0,0 +1,38 @@
+use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use derive_more::Constructor;
+use getset::Getters;
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum TrashItemKind {
+    Page,
+    Blok,
+    Post,
+    Image,
+    File,
+}
+
+#[derive(Serialize, Clone, Constructor, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct TrashItemOutput {
+    kind: TrashItemKind,
+    id: i32,
+    /// Human readable name of the resource (path, title, storage key...)
+    label: String,
+    deleted_at: DateTime<Utc>,
+    /// Date after which the resource is permanently removed
+    purge_at: DateTime<Utc>,
+}
+
+pub struct TrashOutput(pub Vec<TrashItemOutput>);
+
+impl Responder for TrashOutput {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self.0)
+    }
+}
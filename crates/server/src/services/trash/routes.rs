@@ -0,0 +1,114 @@
+use crate::{
+    errors::{utils::MapApiError, ApiError},
+    middlewares::api_key::WriteApiKey,
+    server::AppState,
+    services::{
+        image::services::main_images,
+        trash::models::{TrashItemKind, TrashItemOutput, TrashOutput},
+    },
+};
+use actix_web::{get, web};
+use chrono::{DateTime, Duration, Utc};
+use entity::{blok, file, image, page, post};
+use sea_orm::prelude::*;
+
+#[get("")]
+pub async fn list_trash(
+    data: web::Data<AppState>,
+    api_key: WriteApiKey,
+) -> Result<TrashOutput, ApiError> {
+    let namespace = api_key.namespace().to_owned();
+    let retention = Duration::days((*data.settings().jobs().trash().retention_days()).into());
+    let item = |kind: TrashItemKind, id: i32, label: String, deleted_at: DateTime<Utc>| {
+        TrashItemOutput::new(kind, id, label, deleted_at, deleted_at + retention)
+    };
+
+    let mut items: Vec<TrashItemOutput> = Vec::new();
+
+    let pages = page::Entity::find()
+        .filter(page::Column::Namespace.eq(namespace.clone()))
+        .filter(page::Column::DeletedAt.is_not_null())
+        .all(data.conn())
+        .await
+        .map_api_err()?;
+
+    for page in pages {
+        if let Some(deleted_at) = page.deleted_at {
+            items.push(item(TrashItemKind::Page, page.id, page.path, deleted_at));
+        }
+    }
+
+    let bloks = blok::Entity::find()
+        .find_also_related(page::Entity)
+        .filter(page::Column::Namespace.eq(namespace.clone()))
+        .filter(blok::Column::DeletedAt.is_not_null())
+        .all(data.conn())
+        .await
+        .map_api_err()?;
+
+    for (blok, _) in bloks {
+        if let Some(deleted_at) = blok.deleted_at {
+            items.push(item(
+                TrashItemKind::Blok,
+                blok.id,
+                blok.component_id,
+                deleted_at,
+            ));
+        }
+    }
+
+    let posts = post::Entity::find()
+        .filter(post::Column::Namespace.eq(namespace.clone()))
+        .filter(post::Column::DeletedAt.is_not_null())
+        .all(data.conn())
+        .await
+        .map_api_err()?;
+
+    for post in posts {
+        if let Some(deleted_at) = post.deleted_at {
+            items.push(item(TrashItemKind::Post, post.id, post.title, deleted_at));
+        }
+    }
+
+    // Lazy images are trashed and restored along with their image
+    let images = image::Entity::find()
+        .filter(image::Column::Namespace.eq(namespace.clone()))
+        .filter(image::Column::DeletedAt.is_not_null())
+        .filter(main_images())
+        .all(data.conn())
+        .await
+        .map_api_err()?;
+
+    for image in images {
+        if let Some(deleted_at) = image.deleted_at {
+            items.push(item(
+                TrashItemKind::Image,
+                image.id,
+                image.storage_key,
+                deleted_at,
+            ));
+        }
+    }
+
+    let files = file::Entity::find()
+        .filter(file::Column::Namespace.eq(namespace))
+        .filter(file::Column::DeletedAt.is_not_null())
+        .all(data.conn())
+        .await
+        .map_api_err()?;
+
+    for file in files {
+        if let Some(deleted_at) = file.deleted_at {
+            items.push(item(
+                TrashItemKind::File,
+                file.id,
+                file.storage_key,
+                deleted_at,
+            ));
+        }
+    }
+
+    items.sort_by(|a, b| b.deleted_at().cmp(a.deleted_at()));
+
+    Ok(TrashOutput(items))
+}
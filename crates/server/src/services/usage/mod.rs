@@ -0,0 +1,10 @@
+use crate::services::usage::routes::get_usage;
+use actix_web::{web::scope, Scope};
+
+pub mod models;
+mod routes;
+pub mod services;
+
+pub fn usage_service() -> Scope {
+    scope("/usage").service(get_usage)
+}
@@ -0,0 +1,74 @@
+use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
+use getset::Getters;
+use serde::Serialize;
+
+/// Bytes and objects stored for a resource type
+#[derive(Serialize, Clone, Copy, Debug, Default, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct ResourceUsage {
+    pub bytes: i64,
+    pub objects: i64,
+}
+
+impl From<(i64, i64)> for ResourceUsage {
+    fn from((bytes, objects): (i64, i64)) -> Self {
+        Self { bytes, objects }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct UsageByType {
+    pub files: ResourceUsage,
+    /// Previous contents kept for files to be restored
+    pub file_versions: ResourceUsage,
+    /// Files waiting to be uploaded, counted in the quota but not in the
+    /// total as their objects may not be stored yet
+    pub pending_files: ResourceUsage,
+    /// Images along with their originals and lazy images
+    pub images: ResourceUsage,
+    pub image_variants: ResourceUsage,
+    pub image_transforms: ResourceUsage,
+}
+
+impl UsageByType {
+    /// Usage of the stored objects, pending files excluded
+    pub fn stored(&self) -> ResourceUsage {
+        [
+            self.files,
+            self.file_versions,
+            self.images,
+            self.image_variants,
+            self.image_transforms,
+        ]
+        .into_iter()
+        .fold(ResourceUsage::default(), |total, usage| ResourceUsage {
+            bytes: total.bytes + usage.bytes,
+            objects: total.objects + usage.objects,
+        })
+    }
+}
+
+/// Storage used by a namespace, resources in the trash included as their
+/// objects are kept until they are purged
+#[derive(Serialize, Clone, Debug, Getters)]
+#[serde(rename_all = "camelCase")]
+#[getset(get = "pub")]
+pub struct UsageOutput {
+    pub namespace: String,
+    pub bytes: i64,
+    pub objects: i64,
+    /// Most bytes the namespace can store, unlimited when unset
+    pub quota: Option<u64>,
+    pub types: UsageByType,
+}
+
+impl Responder for UsageOutput {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
@@ -0,0 +1,25 @@
+use crate::{
+    errors::ApiError,
+    middlewares::api_key::ApiKey,
+    server::AppState,
+    services::usage::{models::UsageOutput, services::namespace_usage},
+};
+use actix_web::{get, web};
+
+#[get("")]
+pub async fn get_usage(
+    data: web::Data<AppState>,
+    api_key: ApiKey,
+) -> Result<UsageOutput, ApiError> {
+    let namespace = api_key.namespace().as_str();
+    let types = namespace_usage(data.conn(), namespace).await?;
+    let stored = types.stored();
+
+    Ok(UsageOutput {
+        namespace: namespace.to_string(),
+        bytes: stored.bytes,
+        objects: stored.objects,
+        quota: data.settings().storage().quota().max_size_for(namespace),
+        types,
+    })
+}
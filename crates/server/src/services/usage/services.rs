@@ -0,0 +1,144 @@
+use crate::{
+    config::QuotaSettings,
+    errors::{utils::MapApiError, ApiError},
+    services::usage::models::{ResourceUsage, UsageByType},
+};
+use entity::{file, file::FileStatus, file_version, image, image_transform, image_variant};
+use sea_orm::{prelude::*, sea_query::Expr, ConnectionTrait, JoinType, QuerySelect, RelationTrait};
+
+async fn file_usage<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+    status: FileStatus,
+) -> Result<ResourceUsage, ApiError> {
+    file::Entity::find()
+        .select_only()
+        // Sums of bigints are numeric in Postgres
+        .column_as(
+            Expr::cust("coalesce(sum(files.content_length), 0)::bigint"),
+            "bytes",
+        )
+        .column_as(Expr::cust("count(*)"), "objects")
+        .filter(file::Column::Namespace.eq(namespace.to_owned()))
+        .filter(file::Column::Status.eq(status))
+        .into_tuple::<(i64, i64)>()
+        .one(conn)
+        .await
+        .map_api_err()
+        .map(|usage| usage.map(ResourceUsage::from).unwrap_or_default())
+}
+
+/// Bytes and objects stored by the resources of a namespace, per type. Sizes
+/// unknown for resources stored before they were recorded are not counted.
+pub async fn namespace_usage<C: ConnectionTrait>(
+    conn: &C,
+    namespace: &str,
+) -> Result<UsageByType, ApiError> {
+    let files = file_usage(conn, namespace, FileStatus::Uploaded).await?;
+    let pending_files = file_usage(conn, namespace, FileStatus::Pending).await?;
+
+    let file_versions = file_version::Entity::find()
+        .select_only()
+        .column_as(
+            Expr::cust("coalesce(sum(file_versions.content_length), 0)::bigint"),
+            "bytes",
+        )
+        .column_as(Expr::cust("count(*)"), "objects")
+        .join(JoinType::InnerJoin, file_version::Relation::File.def())
+        .filter(file::Column::Namespace.eq(namespace.to_owned()))
+        .into_tuple::<(i64, i64)>()
+        .one(conn)
+        .await
+        .map_api_err()?;
+
+    // Images are stored in each of their formats, along with their original
+    // once their renditions are generated
+    let images = image::Entity::find()
+        .select_only()
+        .column_as(
+            Expr::cust(
+                "coalesce(sum(coalesce(images.size, 0) + coalesce(images.original_size, 0)), \
+                 0)::bigint",
+            ),
+            "bytes",
+        )
+        .column_as(
+            Expr::cust(
+                "coalesce(sum(greatest(cardinality(images.formats), 1) + case when \
+                 images.original_storage_key <> images.storage_key then 1 else 0 end), 0)::bigint",
+            ),
+            "objects",
+        )
+        .filter(image::Column::Namespace.eq(namespace.to_owned()))
+        .into_tuple::<(i64, i64)>()
+        .one(conn)
+        .await
+        .map_api_err()?;
+
+    let image_variants = image_variant::Entity::find()
+        .select_only()
+        .column_as(
+            Expr::cust("coalesce(sum(image_variants.size), 0)::bigint"),
+            "bytes",
+        )
+        .column_as(Expr::cust("count(*)"), "objects")
+        .join(JoinType::InnerJoin, image_variant::Relation::Image.def())
+        .filter(image::Column::Namespace.eq(namespace.to_owned()))
+        .into_tuple::<(i64, i64)>()
+        .one(conn)
+        .await
+        .map_api_err()?;
+
+    let image_transforms = image_transform::Entity::find()
+        .select_only()
+        .column_as(
+            Expr::cust("coalesce(sum(image_transforms.size), 0)::bigint"),
+            "bytes",
+        )
+        .column_as(Expr::cust("count(*)"), "objects")
+        .join(JoinType::InnerJoin, image_transform::Relation::Image.def())
+        .filter(image::Column::Namespace.eq(namespace.to_owned()))
+        .into_tuple::<(i64, i64)>()
+        .one(conn)
+        .await
+        .map_api_err()?;
+
+    Ok(UsageByType {
+        files,
+        file_versions: file_versions.map(ResourceUsage::from).unwrap_or_default(),
+        pending_files,
+        images: images.map(ResourceUsage::from).unwrap_or_default(),
+        image_variants: image_variants.map(ResourceUsage::from).unwrap_or_default(),
+        image_transforms: image_transforms
+            .map(ResourceUsage::from)
+            .unwrap_or_default(),
+    })
+}
+
+/// Bytes counted against the quota of a namespace. Pending files count as
+/// used, their objects may be uploaded at any time.
+pub async fn quota_usage<C: ConnectionTrait>(conn: &C, namespace: &str) -> Result<u64, ApiError> {
+    let usage = namespace_usage(conn, namespace).await?;
+
+    Ok((usage.stored().bytes + usage.pending_files.bytes).max(0) as u64)
+}
+
+/// Fails when storing `size` more bytes would exceed the quota of the
+/// namespace
+pub async fn check_quota<C: ConnectionTrait>(
+    conn: &C,
+    settings: &QuotaSettings,
+    namespace: &str,
+    size: u64,
+) -> Result<(), ApiError> {
+    let Some(quota) = settings.max_size_for(namespace) else {
+        return Ok(());
+    };
+
+    let used = quota_usage(conn, namespace).await?;
+    if used + size > quota {
+        return Err(ApiError::QuotaExceeded(quota, used, size));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,626 @@
+use crate::{
+    config::Settings,
+    storage::{
+        hex, unhex, ListedObject, ObjectInfo, ObjectOptions, ObjectPage, Storage, StorageError,
+        UploadConditions,
+    },
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncWriteExt},
+};
+use url::Url;
+use uuid::Uuid;
+
+/// Number of objects listed per page
+const LIST_PAGE_SIZE: usize = 1000;
+/// Smallest part size, as on S3 every part but the last must be at least 5 MiB
+const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Stores objects on the local filesystem, under `storage.local.root`. The
+/// objects are served by the API under `/storage`, private objects and uploads
+/// requiring a URL signed with `storage.local.secret`.
+///
+/// Objects are stored at `<root>/<bucket>/<key>` along with a JSON sidecar at
+/// `<root>/.meta/<bucket>/<key>.json` holding their content type, visibility,
+/// SHA-256 and metadata. Parts of multipart uploads are kept under
+/// `<root>/.uploads/<upload id>` until the upload is completed.
+#[derive(Clone, Debug)]
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+    secret: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectMeta {
+    pub content_type: Option<String>,
+    pub public: bool,
+    pub sha256: String,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct MultipartUpload {
+    bucket: String,
+    key: String,
+    content_type: Option<String>,
+    public: bool,
+}
+
+/// Operation a signed URL allows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignedMethod {
+    Get,
+    Put,
+}
+
+impl SignedMethod {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SignedMethod::Get => "GET",
+            SignedMethod::Put => "PUT",
+        }
+    }
+}
+
+fn io_error(e: std::io::Error) -> StorageError {
+    match e.kind() {
+        ErrorKind::NotFound => StorageError::NotFound,
+        _ => StorageError::backend(e),
+    }
+}
+
+/// Buckets and key segments are path components, they cannot climb out of
+/// the storage root nor reach the hidden directories it keeps
+fn is_valid_segment(segment: &str) -> bool {
+    !segment.is_empty() && segment != "." && segment != ".." && !segment.contains(['\\', '\0'])
+}
+
+fn is_valid_bucket(bucket: &str) -> bool {
+    is_valid_segment(bucket) && !bucket.starts_with('.') && !bucket.contains('/')
+}
+
+fn is_valid_key(key: &str) -> bool {
+    key.split('/').all(is_valid_segment)
+}
+
+fn new_hmac(secret: &str) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size")
+}
+
+/// Writes the file at `path` from a temporary file, so that it is never read
+/// partially written
+async fn write_atomic(root: &Path, path: &Path, contents: &[u8]) -> Result<(), StorageError> {
+    let tmp_path = root.join(".tmp").join(Uuid::new_v4().to_string());
+    fs::create_dir_all(root.join(".tmp"))
+        .await
+        .map_err(io_error)?;
+    fs::write(&tmp_path, contents).await.map_err(io_error)?;
+    move_file(&tmp_path, path).await
+}
+
+async fn move_file(from: &Path, to: &Path) -> Result<(), StorageError> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).await.map_err(io_error)?;
+    }
+    fs::rename(from, to).await.map_err(io_error)
+}
+
+/// Keys of the objects found under `dir`, prefixed with `prefix`
+fn walk_keys(dir: &Path, prefix: &str, keys: &mut Vec<(String, PathBuf)>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let key = match prefix.is_empty() {
+            true => name,
+            false => format!("{prefix}/{name}"),
+        };
+        match entry.file_type()?.is_dir() {
+            true => walk_keys(&entry.path(), &key, keys)?,
+            false => keys.push((key, entry.path())),
+        }
+    }
+
+    Ok(())
+}
+
+/// Concatenates the parts of a multipart upload into `path`, checking them
+/// against their ETag. Returns the hex encoded SHA-256 of the result.
+async fn assemble_parts(
+    upload_dir: &Path,
+    parts: &[(u32, String)],
+    path: &Path,
+) -> Result<String, StorageError> {
+    let mut object = fs::File::create(path).await.map_err(io_error)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut previous_part = 0;
+
+    for (index, (part_number, etag)) in parts.iter().enumerate() {
+        if *part_number <= previous_part {
+            return Err(StorageError::InvalidParts(
+                "parts must be listed in ascending order".to_string(),
+            ));
+        }
+        previous_part = *part_number;
+
+        let part_path = upload_dir.join(part_number.to_string());
+        let mut part = fs::File::open(&part_path).await.map_err(|_| {
+            StorageError::InvalidParts(format!("part {part_number} was not uploaded"))
+        })?;
+
+        let mut part_hasher = Sha256::new();
+        let mut part_size = 0;
+        loop {
+            let read = part.read(&mut buffer).await.map_err(io_error)?;
+            if read == 0 {
+                break;
+            }
+            part_size += read as u64;
+            part_hasher.update(&buffer[..read]);
+            hasher.update(&buffer[..read]);
+            object.write_all(&buffer[..read]).await.map_err(io_error)?;
+        }
+
+        if hex(&part_hasher.finalize()) != etag.trim_matches('"') {
+            return Err(StorageError::InvalidParts(format!(
+                "part {part_number} does not match its ETag"
+            )));
+        }
+        if part_size < MIN_PART_SIZE && index + 1 < parts.len() {
+            return Err(StorageError::InvalidParts(format!(
+                "part {part_number} is smaller than the 5 MiB minimum"
+            )));
+        }
+    }
+    object.flush().await.map_err(io_error)?;
+
+    Ok(hex(&hasher.finalize()))
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>, base_url: &str, secret: impl Into<String>) -> Self {
+        Self {
+            root: root.into(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            secret: secret.into(),
+        }
+    }
+
+    pub fn from_settings(settings: &Settings) -> Self {
+        let local = settings
+            .storage()
+            .local()
+            .as_ref()
+            .expect("The local storage driver requires the storage.local settings");
+
+        Self::new(
+            local.root(),
+            &settings.storage_base_url(),
+            local.secret().clone(),
+        )
+    }
+
+    pub fn object_path(&self, bucket: &str, key: &str) -> Result<PathBuf, StorageError> {
+        if !is_valid_bucket(bucket) || !is_valid_key(key) {
+            return Err(StorageError::NotFound);
+        }
+
+        Ok(self.root.join(bucket).join(key))
+    }
+
+    fn meta_path(&self, bucket: &str, key: &str) -> Result<PathBuf, StorageError> {
+        if !is_valid_bucket(bucket) || !is_valid_key(key) {
+            return Err(StorageError::NotFound);
+        }
+
+        Ok(self
+            .root
+            .join(".meta")
+            .join(bucket)
+            .join(format!("{key}.json")))
+    }
+
+    fn upload_dir(&self, upload_id: &str) -> Result<PathBuf, StorageError> {
+        Uuid::parse_str(upload_id).map_err(|_| StorageError::NotFound)?;
+
+        Ok(self.root.join(".uploads").join(upload_id))
+    }
+
+    pub async fn object_meta(&self, bucket: &str, key: &str) -> Result<ObjectMeta, StorageError> {
+        let meta = fs::read(self.meta_path(bucket, key)?)
+            .await
+            .map_err(io_error)?;
+
+        serde_json::from_slice(&meta).map_err(StorageError::backend)
+    }
+
+    async fn write_meta(
+        &self,
+        bucket: &str,
+        key: &str,
+        meta: &ObjectMeta,
+    ) -> Result<(), StorageError> {
+        let meta = serde_json::to_vec(meta).map_err(StorageError::backend)?;
+        write_atomic(&self.root, &self.meta_path(bucket, key)?, &meta).await
+    }
+
+    async fn read_upload(&self, upload_id: &str) -> Result<MultipartUpload, StorageError> {
+        let upload = fs::read(self.upload_dir(upload_id)?.join("upload.json"))
+            .await
+            .map_err(io_error)?;
+
+        serde_json::from_slice(&upload).map_err(StorageError::backend)
+    }
+
+    fn signature(
+        &self,
+        method: SignedMethod,
+        bucket: &str,
+        key: &str,
+        query: &str,
+    ) -> Hmac<Sha256> {
+        let mut mac = new_hmac(&self.secret);
+        mac.update(format!("{}\n{bucket}/{key}\n{query}", method.as_str()).as_bytes());
+        mac
+    }
+
+    /// URL of the object, with the given query parameters and an expiry,
+    /// signed for the given method
+    fn signed_url(
+        &self,
+        method: SignedMethod,
+        bucket: &str,
+        key: &str,
+        params: &[(&str, String)],
+        url_ttl: u64,
+    ) -> Result<String, StorageError> {
+        let mut url = self.object_url(bucket, key)?;
+        let expires = Utc::now().timestamp() + url_ttl as i64;
+
+        url.query_pairs_mut()
+            .extend_pairs(params)
+            .append_pair("expires", &expires.to_string());
+        let signature = self.signature(method, bucket, key, url.query().unwrap_or_default());
+        url.query_pairs_mut()
+            .append_pair("signature", &hex(&signature.finalize().into_bytes()));
+
+        Ok(url.to_string())
+    }
+
+    fn object_url(&self, bucket: &str, key: &str) -> Result<Url, StorageError> {
+        let mut url = Url::parse(&self.base_url).map_err(StorageError::backend)?;
+        url.path_segments_mut()
+            .map_err(|_| StorageError::Backend("Invalid storage base URL".to_string()))?
+            .push(bucket)
+            .extend(key.split('/'));
+
+        Ok(url)
+    }
+
+    /// Checks the signature ending the query string of a request, returns the
+    /// signed query parameters
+    pub fn verify_signed_query(
+        &self,
+        method: SignedMethod,
+        bucket: &str,
+        key: &str,
+        query: &str,
+    ) -> Option<HashMap<String, String>> {
+        let (signed, signature) = query.rsplit_once("&signature=")?;
+        self.signature(method, bucket, key, signed)
+            .verify_slice(&unhex(signature)?)
+            .ok()?;
+
+        let params: HashMap<String, String> = url::form_urlencoded::parse(signed.as_bytes())
+            .into_owned()
+            .collect();
+        let expires: i64 = params.get("expires")?.parse().ok()?;
+
+        (expires >= Utc::now().timestamp()).then_some(params)
+    }
+
+    /// Moves an uploaded body, written to `tmp_path`, to the object
+    pub async fn store_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        tmp_path: &Path,
+        meta: ObjectMeta,
+    ) -> Result<(), StorageError> {
+        move_file(tmp_path, &self.object_path(bucket, key)?).await?;
+        self.write_meta(bucket, key, &meta).await
+    }
+
+    /// Path a body being received is written to until it is complete
+    pub async fn tmp_path(&self) -> Result<PathBuf, StorageError> {
+        fs::create_dir_all(self.root.join(".tmp"))
+            .await
+            .map_err(io_error)?;
+
+        Ok(self.root.join(".tmp").join(Uuid::new_v4().to_string()))
+    }
+
+    /// Moves an uploaded part, written to `tmp_path`, to its multipart upload
+    pub async fn store_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+        tmp_path: &Path,
+    ) -> Result<(), StorageError> {
+        let upload = self.read_upload(upload_id).await?;
+        if upload.bucket != bucket || upload.key != key {
+            return Err(StorageError::NotFound);
+        }
+
+        move_file(
+            tmp_path,
+            &self.upload_dir(upload_id)?.join(part_number.to_string()),
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Vec<u8>,
+        options: ObjectOptions,
+    ) -> Result<(), StorageError> {
+        let meta = ObjectMeta {
+            content_type: options.content_type,
+            public: options.public,
+            sha256: hex(&Sha256::digest(&body)),
+            metadata: options.metadata.into_iter().collect(),
+        };
+
+        write_atomic(&self.root, &self.object_path(bucket, key)?, &body).await?;
+        self.write_meta(bucket, key, &meta).await
+    }
+
+    async fn get_object(&self, bucket: &str, key: &str) -> Result<Vec<u8>, StorageError> {
+        fs::read(self.object_path(bucket, key)?)
+            .await
+            .map_err(io_error)
+    }
+
+    async fn head_object(&self, bucket: &str, key: &str) -> Result<ObjectInfo, StorageError> {
+        let file = fs::metadata(self.object_path(bucket, key)?)
+            .await
+            .map_err(io_error)?;
+        let meta = self.object_meta(bucket, key).await?;
+
+        Ok(ObjectInfo {
+            content_length: file.len() as i64,
+            content_type: meta.content_type,
+            sha256: Some(meta.sha256),
+        })
+    }
+
+    async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), StorageError> {
+        for path in [self.object_path(bucket, key)?, self.meta_path(bucket, key)?] {
+            match fs::remove_file(path).await {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(StorageError::backend(e)),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        continuation: Option<String>,
+    ) -> Result<ObjectPage, StorageError> {
+        if !is_valid_bucket(bucket) {
+            return Err(StorageError::NotFound);
+        }
+
+        let dir = self.root.join(bucket);
+        let mut keys = tokio::task::spawn_blocking(move || {
+            let mut keys = Vec::new();
+            match walk_keys(&dir, "", &mut keys) {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+                _ => Ok(keys),
+            }
+        })
+        .await
+        .map_err(StorageError::backend)?
+        .map_err(StorageError::backend)?;
+        keys.sort();
+
+        let mut remaining = keys
+            .into_iter()
+            .filter(|(key, _)| continuation.as_ref().is_none_or(|after| key > after))
+            .peekable();
+
+        let mut objects = Vec::new();
+        for (key, path) in remaining.by_ref() {
+            let last_modified = fs::metadata(&path)
+                .await
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .map(DateTime::<Utc>::from);
+            objects.push(ListedObject { key, last_modified });
+
+            if objects.len() == LIST_PAGE_SIZE {
+                break;
+            }
+        }
+
+        Ok(ObjectPage {
+            continuation: remaining
+                .peek()
+                .and_then(|_| objects.last())
+                .map(|object| object.key.clone()),
+            objects,
+        })
+    }
+
+    async fn set_object_visibility(
+        &self,
+        bucket: &str,
+        key: &str,
+        public: bool,
+    ) -> Result<(), StorageError> {
+        let mut meta = self.object_meta(bucket, key).await?;
+        meta.public = public;
+        self.write_meta(bucket, key, &meta).await
+    }
+
+    /// The conditions are part of the signed URL and checked on upload
+    async fn presign_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        conditions: &UploadConditions,
+        url_ttl: u64,
+    ) -> Result<String, StorageError> {
+        let mut params = vec![
+            ("contentLength", conditions.content_length.to_string()),
+            ("public", conditions.public.to_string()),
+        ];
+        if let Some(content_type) = &conditions.content_type {
+            params.push(("contentType", content_type.clone()));
+        }
+        if let Some(sha256) = &conditions.sha256 {
+            params.push(("sha256", sha256.to_lowercase()));
+        }
+
+        self.signed_url(SignedMethod::Put, bucket, key, &params, url_ttl)
+    }
+
+    async fn presign_download(
+        &self,
+        bucket: &str,
+        key: &str,
+        url_ttl: u64,
+    ) -> Result<String, StorageError> {
+        self.signed_url(SignedMethod::Get, bucket, key, &[], url_ttl)
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        options: ObjectOptions,
+    ) -> Result<String, StorageError> {
+        self.object_path(bucket, key)?;
+
+        let upload_id = Uuid::new_v4().to_string();
+        let upload = serde_json::to_vec(&MultipartUpload {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            content_type: options.content_type,
+            public: options.public,
+        })
+        .map_err(StorageError::backend)?;
+
+        let upload_dir = self.upload_dir(&upload_id)?;
+        fs::create_dir_all(&upload_dir).await.map_err(io_error)?;
+        fs::write(upload_dir.join("upload.json"), upload)
+            .await
+            .map_err(io_error)?;
+
+        Ok(upload_id)
+    }
+
+    async fn presign_upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+        url_ttl: u64,
+    ) -> Result<String, StorageError> {
+        self.signed_url(
+            SignedMethod::Put,
+            bucket,
+            key,
+            &[
+                ("uploadId", upload_id.to_string()),
+                ("partNumber", part_number.to_string()),
+            ],
+            url_ttl,
+        )
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<(u32, String)>,
+    ) -> Result<(), StorageError> {
+        let upload = self.read_upload(upload_id).await?;
+        if upload.bucket != bucket || upload.key != key {
+            return Err(StorageError::NotFound);
+        }
+
+        let upload_dir = self.upload_dir(upload_id)?;
+        let tmp_path = self.tmp_path().await?;
+        let sha256 = match assemble_parts(&upload_dir, &parts, &tmp_path).await {
+            Ok(sha256) => sha256,
+            Err(e) => {
+                fs::remove_file(&tmp_path).await.ok();
+                return Err(e);
+            }
+        };
+
+        self.store_upload(
+            bucket,
+            key,
+            &tmp_path,
+            ObjectMeta {
+                content_type: upload.content_type,
+                public: upload.public,
+                sha256,
+                metadata: HashMap::new(),
+            },
+        )
+        .await?;
+
+        fs::remove_dir_all(upload_dir).await.map_err(io_error)
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<(), StorageError> {
+        let upload = self.read_upload(upload_id).await?;
+        if upload.bucket != bucket || upload.key != key {
+            return Err(StorageError::NotFound);
+        }
+
+        fs::remove_dir_all(self.upload_dir(upload_id)?)
+            .await
+            .map_err(io_error)
+    }
+
+    /// Computed when the object is written
+    async fn object_sha256(&self, bucket: &str, key: &str) -> Result<String, StorageError> {
+        Ok(self.object_meta(bucket, key).await?.sha256)
+    }
+}
@@ -0,0 +1,217 @@
+pub mod local;
+pub mod routes;
+pub mod s3;
+
+use crate::{
+    config::{Settings, StorageDriver},
+    errors::ApiError,
+    storage::{local::LocalStorage, s3::S3Storage},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tracing::error;
+
+pub type StorageExt = Arc<dyn Storage>;
+
+/// Builds the storage backend selected by `storage.driver`
+pub fn from_settings(settings: &Settings) -> StorageExt {
+    match settings.storage().driver() {
+        StorageDriver::S3 => Arc::new(S3Storage::from_settings(settings)),
+        StorageDriver::Local => Arc::new(LocalStorage::from_settings(settings)),
+    }
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decodes a hex string, `None` when it is not one
+pub fn unhex(hex: &str) -> Option<Vec<u8>> {
+    hex.len()
+        .is_multiple_of(2)
+        .then(|| {
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+                .collect()
+        })
+        .flatten()
+}
+
+#[derive(Debug, Clone)]
+pub enum StorageError {
+    NotFound,
+    /// Why the parts of a multipart upload cannot be assembled
+    InvalidParts(String),
+    Backend(String),
+}
+
+impl StorageError {
+    pub fn backend(e: impl std::fmt::Debug) -> Self {
+        StorageError::Backend(format!("{:?}", e))
+    }
+
+    /// Logs the error along with the given message, for errors which do not
+    /// need to be told apart
+    pub fn into_api_error(self, message: &str) -> ApiError {
+        match self {
+            StorageError::NotFound => ApiError::NotFound,
+            StorageError::InvalidParts(reason) => {
+                ApiError::InvalidField("parts".to_string(), reason)
+            }
+            StorageError::Backend(e) => {
+                error!(error_message = e.as_str(), "{}", message);
+                ApiError::InternalServerError
+            }
+        }
+    }
+}
+
+/// How an object is stored, metadata being kept as is along with it
+#[derive(Debug, Clone, Default)]
+pub struct ObjectOptions {
+    pub content_type: Option<String>,
+    /// Public objects can be read without credentials from their public URL
+    pub public: bool,
+    pub metadata: Vec<(String, String)>,
+}
+
+impl ObjectOptions {
+    pub fn new(content_type: impl Into<String>, public: bool) -> Self {
+        Self {
+            content_type: Some(content_type.into()),
+            public,
+            metadata: Vec::new(),
+        }
+    }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.push((key.into(), value.into()));
+        self
+    }
+}
+
+/// Conditions the object uploaded to a presigned URL must meet
+#[derive(Debug, Clone, Default)]
+pub struct UploadConditions {
+    pub content_length: u64,
+    pub content_type: Option<String>,
+    /// Hex encoded SHA-256 the uploaded content must match
+    pub sha256: Option<String>,
+    pub public: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectInfo {
+    pub content_length: i64,
+    pub content_type: Option<String>,
+    /// Hex encoded SHA-256, when the backend computed it
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListedObject {
+    pub key: String,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Objects of a bucket, `continuation` being set when more objects remain
+#[derive(Debug, Clone)]
+pub struct ObjectPage {
+    pub objects: Vec<ListedObject>,
+    pub continuation: Option<String>,
+}
+
+/// Object store the images and files are kept in. Objects are grouped in
+/// buckets, named after `storage.s3.buckets`.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Vec<u8>,
+        options: ObjectOptions,
+    ) -> Result<(), StorageError>;
+
+    async fn get_object(&self, bucket: &str, key: &str) -> Result<Vec<u8>, StorageError>;
+
+    async fn head_object(&self, bucket: &str, key: &str) -> Result<ObjectInfo, StorageError>;
+
+    /// Hex encoded SHA-256 of an object, for objects whose info do not
+    /// carry it
+    async fn object_sha256(&self, bucket: &str, key: &str) -> Result<String, StorageError> {
+        Ok(hex(&Sha256::digest(self.get_object(bucket, key).await?)))
+    }
+
+    /// Succeeds when the object does not exist
+    async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), StorageError>;
+
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        continuation: Option<String>,
+    ) -> Result<ObjectPage, StorageError>;
+
+    async fn set_object_visibility(
+        &self,
+        bucket: &str,
+        key: &str,
+        public: bool,
+    ) -> Result<(), StorageError>;
+
+    /// URL the client uploads an object to with a single PUT
+    async fn presign_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        conditions: &UploadConditions,
+        url_ttl: u64,
+    ) -> Result<String, StorageError>;
+
+    /// URL an object can be downloaded from, whatever its visibility
+    async fn presign_download(
+        &self,
+        bucket: &str,
+        key: &str,
+        url_ttl: u64,
+    ) -> Result<String, StorageError>;
+
+    /// Starts a multipart upload, returns its ID
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        options: ObjectOptions,
+    ) -> Result<String, StorageError>;
+
+    /// URL the client uploads a part to, the ETag it answers with must be
+    /// sent back to complete the upload
+    async fn presign_upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+        url_ttl: u64,
+    ) -> Result<String, StorageError>;
+
+    /// Assembles the given parts, as `(part number, ETag)`, into the object
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<(u32, String)>,
+    ) -> Result<(), StorageError>;
+
+    /// Discards a multipart upload along with its uploaded parts
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<(), StorageError>;
+}
@@ -0,0 +1,295 @@
+use crate::{
+    errors::ApiError,
+    storage::{
+        hex,
+        local::{LocalStorage, ObjectMeta, SignedMethod},
+        StorageError,
+    },
+};
+use actix_web::{
+    http::{
+        header::{ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE, ETAG, RANGE},
+        StatusCode,
+    },
+    web, HttpRequest, HttpResponse, Scope,
+};
+use futures::StreamExt;
+use percent_encoding::percent_decode_str;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, io::SeekFrom, path::Path};
+use tokio::{
+    fs,
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+};
+use tokio_util::io::ReaderStream;
+
+/// Serves the objects of the local storage driver, under the URLs it presigns
+pub fn local_storage_service(storage: LocalStorage) -> Scope {
+    web::scope("/storage")
+        .app_data(web::Data::new(storage))
+        .route("/{path:.+}", web::get().to(get_object))
+        .route("/{path:.+}", web::put().to(put_object))
+}
+
+fn storage_error(e: StorageError) -> ApiError {
+    e.into_api_error("Cannot access local storage")
+}
+
+/// Bucket and key of the object a request targets, from its raw path
+fn object_location(req: &HttpRequest) -> Result<(String, String), ApiError> {
+    let path = req
+        .uri()
+        .path()
+        .strip_prefix("/storage/")
+        .ok_or(ApiError::NotFound)?;
+
+    let segments = path
+        .split('/')
+        .map(|segment| {
+            percent_decode_str(segment)
+                .decode_utf8()
+                .ok()
+                .map(|segment| segment.into_owned())
+                // An encoded slash would make the key differ from the signed one
+                .filter(|segment| !segment.contains('/'))
+                .ok_or(ApiError::NotFound)
+        })
+        .collect::<Result<Vec<String>, ApiError>>()?;
+
+    match segments.split_first() {
+        Some((bucket, key)) if !key.is_empty() => Ok((bucket.clone(), key.join("/"))),
+        _ => Err(ApiError::NotFound),
+    }
+}
+
+/// Requested byte range, as `(start, end)` inclusive. Only single ranges are
+/// served, other requests get the whole object.
+fn parse_range(header: &str, length: u64) -> Result<Option<(u64, u64)>, ()> {
+    let Some(range) = header.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    if range.contains(',') {
+        return Ok(None);
+    }
+    let Some((start, end)) = range.split_once('-') else {
+        return Ok(None);
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return Err(()),
+            Ok(suffix) => (length.saturating_sub(suffix), length.saturating_sub(1)),
+            Err(_) => return Ok(None),
+        },
+        (start, end) => match (start.parse::<u64>(), end) {
+            (Ok(start), "") => (start, length.saturating_sub(1)),
+            (Ok(start), end) => match end.parse::<u64>() {
+                Ok(end) if end >= start => (start, end.min(length.saturating_sub(1))),
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        },
+    };
+
+    match start < length {
+        true => Ok(Some((start, end))),
+        false => Err(()),
+    }
+}
+
+/// Public objects are served to anyone, private ones with a signed URL
+async fn get_object(
+    req: HttpRequest,
+    storage: web::Data<LocalStorage>,
+) -> Result<HttpResponse, ApiError> {
+    let (bucket, key) = object_location(&req)?;
+    let meta = storage
+        .object_meta(&bucket, &key)
+        .await
+        .map_err(storage_error)?;
+
+    if !meta.public
+        && storage
+            .verify_signed_query(SignedMethod::Get, &bucket, &key, req.query_string())
+            .is_none()
+    {
+        return Err(ApiError::InvalidSignature);
+    }
+
+    let mut file = fs::File::open(storage.object_path(&bucket, &key).map_err(storage_error)?)
+        .await
+        .map_err(|e| storage_error(StorageError::backend(e)))?;
+    let length = file
+        .metadata()
+        .await
+        .map_err(|e| storage_error(StorageError::backend(e)))?
+        .len();
+
+    let range = match req
+        .headers()
+        .get(RANGE)
+        .and_then(|range| range.to_str().ok())
+    {
+        Some(range) => match parse_range(range, length) {
+            Ok(range) => range,
+            Err(()) => {
+                return Ok(HttpResponse::build(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .insert_header((CONTENT_RANGE, format!("bytes */{length}")))
+                    .finish())
+            }
+        },
+        None => None,
+    };
+
+    let mut response = match range {
+        Some(_) => HttpResponse::PartialContent(),
+        None => HttpResponse::Ok(),
+    };
+    response
+        .insert_header((ACCEPT_RANGES, "bytes"))
+        .insert_header((ETAG, format!("\"{}\"", meta.sha256)));
+    if let Some(content_type) = &meta.content_type {
+        response.insert_header((CONTENT_TYPE, content_type.as_str()));
+    }
+
+    let (start, end) = range.unwrap_or((0, length.saturating_sub(1)));
+    let served_length = match length {
+        0 => 0,
+        _ => end - start + 1,
+    };
+    if range.is_some() {
+        response.insert_header((CONTENT_RANGE, format!("bytes {start}-{end}/{length}")));
+    }
+
+    file.seek(SeekFrom::Start(start))
+        .await
+        .map_err(|e| storage_error(StorageError::backend(e)))?;
+
+    Ok(response
+        .no_chunking(served_length)
+        .streaming(ReaderStream::new(file.take(served_length))))
+}
+
+/// Writes the request body to `path`, returns its size and hex encoded
+/// SHA-256
+async fn receive_body(path: &Path, mut payload: web::Payload) -> Result<(u64, String), ApiError> {
+    let mut file = fs::File::create(path)
+        .await
+        .map_err(|e| storage_error(StorageError::backend(e)))?;
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|_| ApiError::InternalServerError)?;
+        size += chunk.len() as u64;
+        hasher.update(&chunk);
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| storage_error(StorageError::backend(e)))?;
+    }
+    file.flush()
+        .await
+        .map_err(|e| storage_error(StorageError::backend(e)))?;
+
+    Ok((size, hex(&hasher.finalize())))
+}
+
+/// Checks the received body against the conditions of its signed URL
+fn check_upload(
+    req: &HttpRequest,
+    params: &HashMap<String, String>,
+    (size, sha256): (u64, &str),
+) -> Result<(), ApiError> {
+    if let Some(content_length) = params.get("contentLength") {
+        if content_length != &size.to_string() {
+            return Err(ApiError::FileUploadMismatch(format!(
+                "its size is {size} bytes but {content_length} bytes were declared"
+            )));
+        }
+    }
+
+    if let Some(content_type) = params.get("contentType") {
+        let sent = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|sent| sent.to_str().ok())
+            .unwrap_or_default();
+        if sent != content_type {
+            return Err(ApiError::FileUploadMismatch(format!(
+                "its content type is \"{sent}\" but \"{content_type}\" was declared"
+            )));
+        }
+    }
+
+    if let Some(declared) = params.get("sha256") {
+        if declared != sha256 {
+            return Err(ApiError::FileUploadMismatch(format!(
+                "its SHA-256 is {sha256} but {declared} was declared"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Receives the object or part a signed upload URL was presigned for
+async fn put_object(
+    req: HttpRequest,
+    storage: web::Data<LocalStorage>,
+    payload: web::Payload,
+) -> Result<HttpResponse, ApiError> {
+    let (bucket, key) = object_location(&req)?;
+    let params = storage
+        .verify_signed_query(SignedMethod::Put, &bucket, &key, req.query_string())
+        .ok_or(ApiError::InvalidSignature)?;
+
+    let tmp_path = storage.tmp_path().await.map_err(storage_error)?;
+    let stored = async {
+        let (size, sha256) = receive_body(&tmp_path, payload).await?;
+
+        if let (Some(upload_id), Some(part_number)) =
+            (params.get("uploadId"), params.get("partNumber"))
+        {
+            let part_number = part_number.parse().map_err(|_| ApiError::NotFound)?;
+            storage
+                .store_part(&bucket, &key, upload_id, part_number, &tmp_path)
+                .await
+                .map_err(storage_error)?;
+            return Ok(sha256);
+        }
+
+        check_upload(&req, &params, (size, &sha256))?;
+        storage
+            .store_upload(
+                &bucket,
+                &key,
+                &tmp_path,
+                ObjectMeta {
+                    content_type: params.get("contentType").cloned().or_else(|| {
+                        req.headers()
+                            .get(CONTENT_TYPE)
+                            .and_then(|sent| sent.to_str().ok())
+                            .map(ToString::to_string)
+                    }),
+                    public: params.get("public").map(String::as_str) == Some("true"),
+                    sha256: sha256.clone(),
+                    metadata: HashMap::new(),
+                },
+            )
+            .await
+            .map_err(storage_error)?;
+
+        Ok(sha256)
+    }
+    .await;
+
+    match stored {
+        Ok(sha256) => Ok(HttpResponse::Ok()
+            .insert_header((ETAG, format!("\"{sha256}\"")))
+            .finish()),
+        Err(e) => {
+            fs::remove_file(&tmp_path).await.ok();
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use crate::{
+    config::Settings,
+    storage::{
+        hex, unhex, ListedObject, ObjectInfo, ObjectOptions, ObjectPage, Storage, StorageError,
+        UploadConditions,
+    },
+    utils::b64,
+};
+use async_trait::async_trait;
+use aws_sdk_s3::{
+    model::{ChecksumMode, CompletedMultipartUpload, CompletedPart, ObjectCannedAcl},
+    presigning::config::PresigningConfig,
+    types::{ByteStream, SdkError},
+    Client,
+};
+use aws_smithy_http::body::SdkBody;
+use chrono::{TimeZone, Utc};
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tracing::warn;
+
+pub struct S3Storage {
+    client: Client,
+}
+
+fn presigning_config(url_ttl: u64) -> Result<PresigningConfig, StorageError> {
+    PresigningConfig::builder()
+        .expires_in(Duration::from_secs(url_ttl))
+        .build()
+        .map_err(StorageError::backend)
+}
+
+/// Private objects get no ACL grant, they can only be read with credentials
+/// or presigned URLs
+fn object_acl(public: bool) -> ObjectCannedAcl {
+    match public {
+        true => ObjectCannedAcl::PublicRead,
+        false => ObjectCannedAcl::Private,
+    }
+}
+
+impl S3Storage {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self::new(Client::from_conf(settings.clone().into()))
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Vec<u8>,
+        options: ObjectOptions,
+    ) -> Result<(), StorageError> {
+        let mut request = self
+            .client
+            .put_object()
+            .bucket(bucket)
+            .key(key)
+            .set_content_type(options.content_type)
+            .body(ByteStream::new(SdkBody::from(body)));
+
+        // Objects are private unless granted otherwise
+        if options.public {
+            request = request.acl(ObjectCannedAcl::PublicRead);
+        }
+
+        for (name, value) in options.metadata {
+            request = request.metadata(name, value);
+        }
+
+        request.send().await.map_err(StorageError::backend)?;
+
+        Ok(())
+    }
+
+    async fn get_object(&self, bucket: &str, key: &str) -> Result<Vec<u8>, StorageError> {
+        Ok(self
+            .client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| match e.into_service_error() {
+                e if e.is_no_such_key() => StorageError::NotFound,
+                e => StorageError::backend(e),
+            })?
+            .body
+            .collect()
+            .await
+            .map_err(StorageError::backend)?
+            .into_bytes()
+            .to_vec())
+    }
+
+    async fn head_object(&self, bucket: &str, key: &str) -> Result<ObjectInfo, StorageError> {
+        let object = self
+            .client
+            .head_object()
+            .bucket(bucket)
+            .key(key)
+            .checksum_mode(ChecksumMode::Enabled)
+            .send()
+            .await
+            .map_err(|e| match e.into_service_error() {
+                e if e.is_not_found() => StorageError::NotFound,
+                e => StorageError::backend(e),
+            })?;
+
+        Ok(ObjectInfo {
+            content_length: object.content_length(),
+            content_type: object.content_type().map(ToString::to_string),
+            sha256: object
+                .checksum_sha256()
+                .map(|checksum| hex(&b64::decode(checksum).unwrap_or_default())),
+        })
+    }
+
+    /// Streams the object instead of loading it in memory
+    async fn object_sha256(&self, bucket: &str, key: &str) -> Result<String, StorageError> {
+        let mut body = self
+            .client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(StorageError::backend)?
+            .body;
+
+        let mut hasher = Sha256::new();
+        while let Some(chunk) = body.next().await {
+            hasher.update(chunk.map_err(StorageError::backend)?);
+        }
+
+        Ok(hex(&hasher.finalize()))
+    }
+
+    async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), StorageError> {
+        self.client
+            .delete_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(StorageError::backend)?;
+
+        Ok(())
+    }
+
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        continuation: Option<String>,
+    ) -> Result<ObjectPage, StorageError> {
+        let output = self
+            .client
+            .list_objects_v2()
+            .bucket(bucket)
+            .set_continuation_token(continuation)
+            .send()
+            .await
+            .map_err(StorageError::backend)?;
+
+        Ok(ObjectPage {
+            objects: output
+                .contents()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|object| {
+                    Some(ListedObject {
+                        key: object.key()?.to_string(),
+                        last_modified: object.last_modified().and_then(|last_modified| {
+                            Utc.timestamp_opt(last_modified.secs(), 0).single()
+                        }),
+                    })
+                })
+                .collect(),
+            continuation: match output.is_truncated() {
+                true => output.next_continuation_token().map(ToString::to_string),
+                false => None,
+            },
+        })
+    }
+
+    async fn set_object_visibility(
+        &self,
+        bucket: &str,
+        key: &str,
+        public: bool,
+    ) -> Result<(), StorageError> {
+        self.client
+            .put_object_acl()
+            .bucket(bucket)
+            .key(key)
+            .acl(object_acl(public))
+            .send()
+            .await
+            .map_err(StorageError::backend)?;
+
+        Ok(())
+    }
+
+    /// When a SHA-256 is given, the client must send it in the
+    /// `x-amz-checksum-sha256` header and S3 rejects other contents
+    async fn presign_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        conditions: &UploadConditions,
+        url_ttl: u64,
+    ) -> Result<String, StorageError> {
+        Ok(self
+            .client
+            .put_object()
+            .content_length(conditions.content_length as i64)
+            .bucket(bucket)
+            .key(key)
+            .acl(object_acl(conditions.public))
+            .set_content_type(conditions.content_type.clone())
+            .set_checksum_sha256(
+                conditions
+                    .sha256
+                    .as_deref()
+                    .and_then(unhex)
+                    .map(b64::encode),
+            )
+            .presigned(presigning_config(url_ttl)?)
+            .await
+            .map_err(StorageError::backend)?
+            .uri()
+            .to_string())
+    }
+
+    async fn presign_download(
+        &self,
+        bucket: &str,
+        key: &str,
+        url_ttl: u64,
+    ) -> Result<String, StorageError> {
+        Ok(self
+            .client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .presigned(presigning_config(url_ttl)?)
+            .await
+            .map_err(StorageError::backend)?
+            .uri()
+            .to_string())
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        options: ObjectOptions,
+    ) -> Result<String, StorageError> {
+        self.client
+            .create_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .acl(object_acl(options.public))
+            .set_content_type(options.content_type)
+            .send()
+            .await
+            .map_err(StorageError::backend)?
+            .upload_id()
+            .map(ToString::to_string)
+            .ok_or_else(|| StorageError::Backend("Multipart upload has no ID".to_string()))
+    }
+
+    async fn presign_upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+        url_ttl: u64,
+    ) -> Result<String, StorageError> {
+        Ok(self
+            .client
+            .upload_part()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .part_number(part_number as i32)
+            .presigned(presigning_config(url_ttl)?)
+            .await
+            .map_err(StorageError::backend)?
+            .uri()
+            .to_string())
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<(u32, String)>,
+    ) -> Result<(), StorageError> {
+        let completed = self
+            .client
+            .complete_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(
+                        parts
+                            .into_iter()
+                            .map(|(part_number, etag)| {
+                                CompletedPart::builder()
+                                    .part_number(part_number as i32)
+                                    .e_tag(etag)
+                                    .build()
+                            })
+                            .collect(),
+                    ))
+                    .build(),
+            )
+            .send()
+            .await;
+
+        match completed {
+            Ok(_) => Ok(()),
+            // Some S3 compatible stores answer with another document than S3
+            // does, the assembled object is checked afterwards anyway
+            Err(SdkError::ServiceError(context)) if context.raw().http().status().is_success() => {
+                warn!(
+                    error_message = format!("{:?}", context.err()).as_str(),
+                    s3_key = key,
+                    "Cannot read multipart upload completion"
+                );
+                Ok(())
+            }
+            Err(e) => {
+                let e = e.into_service_error();
+                Err(match e.code() {
+                    Some("NoSuchUpload") => StorageError::NotFound,
+                    Some("InvalidPart" | "InvalidPartOrder" | "EntityTooSmall") => {
+                        StorageError::InvalidParts(e.message().unwrap_or_default().to_string())
+                    }
+                    _ => StorageError::backend(e),
+                })
+            }
+        }
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<(), StorageError> {
+        self.client
+            .abort_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .upload_id(upload_id)
+            .send()
+            .await
+            .map_err(StorageError::backend)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{errors::ApiError, utils::b64};
+use actix_web::{
+    body::BoxBody,
+    http::{
+        header::{
+            ETag, EntityTag, IfMatch, IfModifiedSince, IfNoneMatch, LastModified, CACHE_CONTROL,
+        },
+        Method,
+    },
+    web, HttpMessage, HttpRequest, HttpResponse, Responder, ResponseError,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::SystemTime;
+use tracing::error;
+
+/// Computes a strong entity tag from the given representation bytes
+pub fn strong_etag(bytes: &[u8]) -> EntityTag {
+    let digest = Sha256::digest(bytes);
+    EntityTag::new_strong(b64::encode(&digest[..16]).trim_end_matches('=').to_string())
+}
+
+/// Computes the strong entity tag of the JSON representation of the given value
+pub fn json_etag<T: Serialize>(value: &T) -> Result<EntityTag, ApiError> {
+    let bytes = serde_json::to_vec(value).map_err(|e| {
+        error!(
+            error_message = format!("{:?}", e).as_str(),
+            "An error occured while serializing response"
+        );
+        ApiError::InternalServerError
+    })?;
+
+    Ok(strong_etag(&bytes))
+}
+
+/// Serializes the given value as a JSON response carrying its strong `ETag`, so
+/// that clients can send it back in `If-Match` on their next write
+pub fn json_response_with_etag<T: Serialize>(value: &T) -> HttpResponse {
+    match json_etag(value) {
+        Ok(etag) => HttpResponse::Ok().insert_header(ETag(etag)).json(value),
+        Err(e) => e.error_response(),
+    }
+}
+
+/// Ensures the `If-Match` precondition (if any) matches the current
+/// representation of the resource, otherwise fails with the current version so
+/// the client can refetch and retry
+pub fn check_if_match<T: Serialize>(
+    if_match: &Option<web::Header<IfMatch>>,
+    current: &T,
+) -> Result<(), ApiError> {
+    let Some(web::Header(if_match)) = if_match else {
+        return Ok(());
+    };
+
+    let current_etag = json_etag(current)?;
+
+    match if_match {
+        IfMatch::Any => Ok(()),
+        // A missing header is parsed as an empty list of tags
+        IfMatch::Items(tags) if tags.is_empty() => Ok(()),
+        IfMatch::Items(tags) if tags.iter().any(|tag| tag.strong_eq(&current_etag)) => Ok(()),
+        IfMatch::Items(_) => Err(ApiError::PreconditionFailed(current_etag.to_string())),
+    }
+}
+
+/// A JSON response served with `ETag`, `Last-Modified` and `Cache-Control`
+/// headers, answering `304 Not Modified` when the request preconditions
+/// (`If-None-Match`, `If-Modified-Since`) show that the client already holds
+/// the current representation.
+pub struct Cached<T> {
+    body: T,
+    last_modified: Option<DateTime<Utc>>,
+    cache_control: String,
+}
+
+impl<T: Serialize> Cached<T> {
+    pub fn new(body: T, last_modified: Option<DateTime<Utc>>, cache_control: &str) -> Self {
+        Self {
+            body,
+            last_modified,
+            cache_control: cache_control.to_string(),
+        }
+    }
+}
+
+fn is_not_modified(
+    req: &HttpRequest,
+    etag: &EntityTag,
+    last_modified: Option<DateTime<Utc>>,
+) -> bool {
+    if req.method() != Method::GET && req.method() != Method::HEAD {
+        return false;
+    }
+
+    // If-None-Match takes precedence over If-Modified-Since (RFC 7232 section
+    // 6)
+    if let Some(if_none_match) = req.get_header::<IfNoneMatch>() {
+        return match if_none_match {
+            IfNoneMatch::Any => true,
+            IfNoneMatch::Items(tags) => tags.iter().any(|tag| tag.weak_eq(etag)),
+        };
+    }
+
+    match (req.get_header::<IfModifiedSince>(), last_modified) {
+        (Some(IfModifiedSince(since)), Some(last_modified)) => {
+            // HTTP dates have a one second precision
+            last_modified.timestamp() <= DateTime::<Utc>::from(SystemTime::from(since)).timestamp()
+        }
+        _ => false,
+    }
+}
+
+impl<T: Serialize> Responder for Cached<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let body = match serde_json::to_vec(&self.body) {
+            Ok(body) => body,
+            Err(e) => {
+                error!(
+                    error_message = format!("{:?}", e).as_str(),
+                    "An error occured while serializing response"
+                );
+                return ApiError::InternalServerError.error_response();
+            }
+        };
+
+        let etag = strong_etag(&body);
+        let not_modified = is_not_modified(req, &etag, self.last_modified);
+
+        let mut response = if not_modified {
+            HttpResponse::NotModified()
+        } else {
+            HttpResponse::Ok()
+        };
+
+        response
+            .insert_header(ETag(etag))
+            .insert_header((CACHE_CONTROL, self.cache_control));
+
+        if let Some(last_modified) = self.last_modified {
+            response.insert_header(LastModified(SystemTime::from(last_modified).into()));
+        }
+
+        if not_modified {
+            return response.finish();
+        }
+
+        response.content_type(mime::APPLICATION_JSON).body(body)
+    }
+}
@@ -1,2 +1,3 @@
 pub mod b64;
+pub mod http_cache;
 pub mod serde_json_patch;
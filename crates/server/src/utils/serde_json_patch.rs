@@ -1,7 +1,8 @@
 use serde::{Deserialize, Deserializer};
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
 pub enum Patch<T> {
+    #[default]
     Missing,
     Null,
     Value(T),
@@ -17,12 +18,6 @@ impl<T> Patch<T> {
     }
 }
 
-impl<T> Default for Patch<T> {
-    fn default() -> Self {
-        Patch::Missing
-    }
-}
-
 impl<T> From<Option<T>> for Patch<T> {
     fn from(opt: Option<T>) -> Patch<T> {
         match opt {
@@ -3,6 +3,7 @@ use crate::{
     test_app::TestApp,
 };
 use reqwest::StatusCode;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
 use serde_json::json;
 use test_context::test_context;
 
@@ -85,3 +86,112 @@ async fn delete_blok_from_other_namespace_should_be_denied(ctx: &mut TestApp) {
 
     assert_eq!(StatusCode::NOT_FOUND, response.status());
 }
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn delete_blok_with_read_only_key_should_be_denied(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page = create_page(
+        ctx,
+        &json!({
+            "path": "/path",
+            "title": "My Title",
+            "description": "My Description"
+        }),
+    )
+    .await;
+
+    let page_id = page
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+
+    let blok = create_blok(
+        ctx,
+        &json!({
+            "pageId": page_id,
+            "componentId": "Hero",
+            "props": {}
+        }),
+    )
+    .await;
+
+    ctx.create_api_key("namespace", true).await;
+
+    let response = ctx
+        .delete(format!("/blok/{}", blok.get("id").expect("Expected ID")))
+        .await;
+
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn restore_blok_should_offset_bloks_which_took_its_priority(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page = create_page(
+        ctx,
+        &json!({
+            "path": "/path",
+            "title": "My Title",
+            "description": "My Description"
+        }),
+    )
+    .await;
+    let page_id = page.get("id").expect("Expected ID");
+
+    let mut ids = Vec::new();
+    for (component_id, priority) in [("First", 0), ("Second", 1)] {
+        let blok = create_blok(
+            ctx,
+            &json!({
+                "pageId": page_id,
+                "componentId": component_id,
+                "props": {},
+                "priority": priority
+            }),
+        )
+        .await;
+        ids.push(
+            blok.get("id")
+                .and_then(|v| v.as_i64())
+                .expect("Expected ID"),
+        );
+    }
+
+    let response = ctx.delete(format!("/blok/{}", ids[0])).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    // The trashed blok does not hold its priority anymore
+    let blok = create_blok(
+        ctx,
+        &json!({ "pageId": page_id, "componentId": "Third", "props": {}, "priority": 0 }),
+    )
+    .await;
+    ids.push(
+        blok.get("id")
+            .and_then(|v| v.as_i64())
+            .expect("Expected ID"),
+    );
+
+    let response = ctx
+        .post(format!("/blok/{}/restore", ids[0]), json!({}))
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let bloks = entity::blok::Entity::find()
+        .filter(entity::blok::Column::Id.is_in(ids.iter().map(|id| *id as i32)))
+        .order_by_asc(entity::blok::Column::Priority)
+        .all(ctx.database_connection())
+        .await
+        .expect("Failed to fetch bloks");
+    assert_eq!(
+        vec![("First", 0), ("Third", 1), ("Second", 2)],
+        bloks
+            .iter()
+            .map(|blok| (blok.component_id.as_str(), blok.priority))
+            .collect::<Vec<_>>()
+    );
+}
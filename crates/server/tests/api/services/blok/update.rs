@@ -2,7 +2,7 @@ use crate::{
     services::{blok::create::create_blok, page::create::create_page},
     test_app::TestApp,
 };
-use reqwest::StatusCode;
+use reqwest::{Method, StatusCode};
 use serde_json::{json, Value};
 use std::collections::HashSet;
 use test_context::test_context;
@@ -156,3 +156,67 @@ async fn update_blok_with_page_from_different_namespace_shoulod_be_denied(ctx: &
 
     assert_eq!(Some(&Value::String("REFNF".to_string())), json.get("code"));
 }
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn concurrent_updates_with_same_if_match_should_let_one_through(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page = create_page(
+        ctx,
+        &json!({
+          "path": "/about/me",
+          "title": "A propos !",
+          "description": "Qui suis-je ? Telle est la question !"
+        }),
+    )
+    .await;
+    let page_id = page
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+
+    let blok = create_blok(
+        ctx,
+        &json!({
+          "pageId": page_id,
+          "componentId": "Hero",
+          "props": {}
+        }),
+    )
+    .await;
+    let id = blok
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let etag = ctx
+        .get(format!("/blok/{}", id))
+        .await
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string)
+        .expect("Expected ETag header");
+    let headers = [("If-Match", etag.as_str())];
+
+    let update = |size: i64| {
+        ctx.req_with_headers(
+            Method::PUT,
+            format!("/blok/{}", id),
+            Some(json!({
+              "pageId": page_id,
+              "componentId": "Hero",
+              "props": { "size": size }
+            })),
+            &headers,
+        )
+    };
+    let (first, second) = tokio::join!(update(1), update(2));
+
+    let mut statuses = vec![first.status(), second.status()];
+    statuses.sort();
+    assert_eq!(
+        vec![StatusCode::OK, StatusCode::PRECONDITION_FAILED],
+        statuses
+    );
+}
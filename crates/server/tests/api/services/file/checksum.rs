@@ -0,0 +1,185 @@
+use crate::test_app::TestApp;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::{
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    StatusCode,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use test_context::test_context;
+
+const CONTENT: &str = "Signed contract";
+
+fn sha256_hex(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+async fn create_file(ctx: &TestApp, sha256: &str) -> reqwest::Response {
+    create_file_with_visibility(ctx, sha256, "public").await
+}
+
+async fn create_file_with_visibility(
+    ctx: &TestApp,
+    sha256: &str,
+    visibility: &str,
+) -> reqwest::Response {
+    ctx.post(
+        "/file",
+        json!({
+            "file": {
+                "contentType": "text/plain",
+                "contentLength": CONTENT.len(),
+                "fileName": "contract.txt",
+                "sha256": sha256
+            },
+            "tags": [],
+            "metadata": {},
+            "visibility": visibility
+        }),
+    )
+    .await
+}
+
+async fn json_body(response: reqwest::Response) -> Value {
+    response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body")
+}
+
+async fn upload(file: &Value, content: &'static str) -> reqwest::Response {
+    let upload_url = file
+        .get("uploadUrl")
+        .and_then(|v| v.as_str())
+        .expect("Expected an upload URL");
+
+    reqwest::Client::new()
+        .put(upload_url)
+        .header(CONTENT_TYPE, "text/plain")
+        .header(CONTENT_LENGTH, content.len())
+        .header("x-amz-acl", "public-read")
+        .header(
+            "x-amz-checksum-sha256",
+            STANDARD.encode(Sha256::digest(content.as_bytes())),
+        )
+        .body(content)
+        .send()
+        .await
+        .expect("Failed to upload file")
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn file_with_checksum_should_be_stored_once(ctx: &mut TestApp) {
+    ctx.create_api_key("test_file_checksum", false).await;
+    let sha256 = sha256_hex(CONTENT);
+
+    let response = create_file(ctx, &sha256).await;
+    assert_eq!(StatusCode::OK, response.status());
+    let file = json_body(response).await;
+    assert_eq!(Some(&json!(false)), file.get("duplicate"));
+
+    assert_eq!(StatusCode::OK, upload(&file, CONTENT).await.status());
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let response = ctx.post(format!("/file/{id}/confirm"), json!({})).await;
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(
+        Some(&json!(sha256)),
+        json_body(response).await.get("sha256")
+    );
+
+    // Declared checksums are case insensitive
+    let response = create_file(ctx, &sha256.to_uppercase()).await;
+    assert_eq!(StatusCode::OK, response.status());
+    let duplicate = json_body(response).await;
+    assert_eq!(Some(&json!(true)), duplicate.get("duplicate"));
+    assert_eq!(Some(&json!(id)), duplicate.get("id"));
+    assert_eq!(Some(&Value::Null), duplicate.get("uploadUrl"));
+    assert_eq!(Some(&json!("uploaded")), duplicate.get("status"));
+
+    // Duplicates are only looked up in the namespace
+    ctx.create_api_key("test_file_checksum_other", false).await;
+    let response = create_file(ctx, &sha256).await;
+    assert_eq!(
+        Some(&json!(false)),
+        json_body(response).await.get("duplicate")
+    );
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn private_file_should_not_be_deduplicated_with_public_one(ctx: &mut TestApp) {
+    ctx.create_api_key("test_file_checksum", false).await;
+    let sha256 = sha256_hex(CONTENT);
+
+    let file = json_body(create_file(ctx, &sha256).await).await;
+    assert_eq!(StatusCode::OK, upload(&file, CONTENT).await.status());
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let response = ctx.post(format!("/file/{id}/confirm"), json!({})).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response = create_file_with_visibility(ctx, &sha256, "private").await;
+    assert_eq!(StatusCode::OK, response.status());
+    let private = json_body(response).await;
+    assert_eq!(Some(&json!(false)), private.get("duplicate"));
+    assert_ne!(Some(&json!(id)), private.get("id"));
+    assert_eq!(Some(&json!("private")), private.get("visibility"));
+    assert!(private.get("uploadUrl").and_then(|v| v.as_str()).is_some());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn upload_with_other_content_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("test_file_checksum", false).await;
+
+    let response = create_file(ctx, &sha256_hex(CONTENT)).await;
+    let file = json_body(response).await;
+
+    // Same size, other content. S3 rejects the upload, the confirmation fails
+    // with stores which do not check the checksum header.
+    let response = upload(&file, "Forged contract").await;
+    if response.status().is_success() {
+        let id = file
+            .get("id")
+            .and_then(|v| v.as_i64())
+            .expect("Expected ID");
+        let response = ctx.post(format!("/file/{id}/confirm"), json!({})).await;
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, response.status());
+        assert_eq!(Some(&json!("FLUPM")), json_body(response).await.get("code"));
+    }
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn invalid_checksum_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("test_file_checksum", false).await;
+
+    let response = create_file(ctx, "not-a-sha256").await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response = ctx
+        .post(
+            "/file/multipart",
+            json!({
+                "file": {
+                    "contentType": "text/plain",
+                    "contentLength": CONTENT.len(),
+                    "fileName": "contract.txt",
+                    "sha256": sha256_hex(CONTENT)
+                },
+                "tags": [],
+                "metadata": {}
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+}
@@ -0,0 +1,237 @@
+use crate::test_app::TestApp;
+use aws_sdk_s3::types::ByteStream;
+use chrono::{Duration, Utc};
+use entity::file::FileVisibility;
+use reqwest::{
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    StatusCode,
+};
+use serde_json::{json, Value};
+use server::{
+    jobs::files::remove_expired_uploads,
+    services::files::{
+        models::{FileInput, FilePayload},
+        repository::FilesRepository,
+    },
+};
+use std::collections::HashMap;
+use test_context::test_context;
+
+const CONTENT: &str = "Hello world";
+
+async fn create_pending_file(ctx: &TestApp) -> Value {
+    let response = ctx
+        .post(
+            "/file",
+            json!({
+                "file": {
+                    "contentType": "text/plain",
+                    "contentLength": CONTENT.len(),
+                    "fileName": "file.txt"
+                },
+                "tags": [],
+                "metadata": {}
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body")
+}
+
+async fn list_file_count(ctx: &TestApp) -> usize {
+    ctx.get("/file")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body")
+        .as_array()
+        .expect("Expected json list response")
+        .len()
+}
+
+async fn put_object(ctx: &TestApp, key: &str, body: &'static str) {
+    ctx.s3_client()
+        .put_object()
+        .bucket(ctx.settings().storage().s3().buckets().file())
+        .key(key)
+        .content_type("text/plain")
+        .body(ByteStream::from_static(body.as_bytes()))
+        .send()
+        .await
+        .expect("Failed to upload object");
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn confirm_uploaded_file_should_list_it(ctx: &mut TestApp) {
+    ctx.create_api_key("test_confirm_file", false).await;
+
+    let file = create_pending_file(ctx).await;
+    assert_eq!(Some(&json!("pending")), file.get("status"));
+    assert!(file
+        .get("uploadExpiresAt")
+        .and_then(|v| v.as_str())
+        .is_some());
+
+    // Pending files are not listed
+    assert_eq!(0, list_file_count(ctx).await);
+
+    let upload_url = file
+        .get("uploadUrl")
+        .and_then(|v| v.as_str())
+        .expect("Expected an upload URL");
+    let response = reqwest::Client::new()
+        .put(upload_url)
+        .header(CONTENT_TYPE, "text/plain")
+        .header(CONTENT_LENGTH, CONTENT.len())
+        .header("x-amz-acl", "public-read")
+        .body(CONTENT)
+        .send()
+        .await
+        .expect("Failed to upload file");
+    assert_eq!(StatusCode::OK, response.status());
+
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let response = ctx.post(format!("/file/{id}/confirm"), json!({})).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body");
+    assert_eq!(Some(&json!("uploaded")), body.get("status"));
+    assert_eq!(Some(&json!("text/plain")), body.get("contentType"));
+    assert_eq!(Some(&json!(CONTENT.len())), body.get("contentLength"));
+
+    assert_eq!(1, list_file_count(ctx).await);
+
+    // Confirming again has no effect
+    let response = ctx.post(format!("/file/{id}/confirm"), json!({})).await;
+    assert_eq!(StatusCode::OK, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn confirm_file_not_uploaded_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("test_confirm_file", false).await;
+
+    let file = create_pending_file(ctx).await;
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+
+    let response = ctx.post(format!("/file/{id}/confirm"), json!({})).await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body");
+    assert_eq!(Some(&json!("FLNUP")), body.get("code"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn confirm_file_with_other_size_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("test_confirm_file", false).await;
+
+    let file = create_pending_file(ctx).await;
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let key = file
+        .get("key")
+        .and_then(|v| v.as_str())
+        .expect("Expected key");
+
+    put_object(ctx, key, "Hello").await;
+
+    let response = ctx.post(format!("/file/{id}/confirm"), json!({})).await;
+    assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, response.status());
+
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body");
+    assert_eq!(Some(&json!("FLUPM")), body.get("code"));
+    assert_eq!(0, list_file_count(ctx).await);
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn expired_pending_files_should_be_removed(ctx: &mut TestApp) {
+    ctx.create_api_key("test_confirm_file", false).await;
+
+    let pending = create_pending_file(ctx).await;
+    let pending_key = pending
+        .get("key")
+        .and_then(|v| v.as_str())
+        .expect("Expected key");
+    // Uploaded without being confirmed
+    put_object(ctx, pending_key, CONTENT).await;
+
+    let uploaded = ctx
+        .database_connection()
+        .create_file(
+            "test_confirm_file",
+            FileInput {
+                file: FilePayload {
+                    file_name: "file.txt".to_string(),
+                    content_type: Some("text/plain".to_string()),
+                    content_length: CONTENT.len() as u64,
+                    sha256: None,
+                },
+                metadata: HashMap::new(),
+                tags: Vec::new(),
+                visibility: FileVisibility::Public,
+            },
+            180,
+        )
+        .await
+        .expect("Failed to create test file");
+    ctx.database_connection()
+        .confirm_file_upload(uploaded)
+        .await
+        .expect("Failed to confirm test file");
+
+    // Upload URLs are still valid
+    let removed = remove_expired_uploads(
+        ctx.database_connection(),
+        ctx.storage().as_ref(),
+        ctx.settings().storage().s3().buckets(),
+        Utc::now(),
+    )
+    .await
+    .expect("Failed to remove expired uploads");
+    assert_eq!(0, removed);
+
+    let removed = remove_expired_uploads(
+        ctx.database_connection(),
+        ctx.storage().as_ref(),
+        ctx.settings().storage().s3().buckets(),
+        Utc::now() + Duration::hours(1),
+    )
+    .await
+    .expect("Failed to remove expired uploads");
+    assert_eq!(1, removed);
+
+    assert_eq!(1, list_file_count(ctx).await);
+
+    let object = ctx
+        .s3_client()
+        .head_object()
+        .bucket(ctx.settings().storage().s3().buckets().file())
+        .key(pending_key)
+        .send()
+        .await;
+    assert!(object.is_err());
+}
@@ -1,4 +1,5 @@
 use crate::test_app::TestApp;
+use entity::file::FileVisibility;
 use reqwest::StatusCode;
 use server::services::files::{
     models::{FileInput, FilePayload},
@@ -21,10 +22,13 @@ async fn delete_file_should_work(ctx: &mut TestApp) {
                     file_name: "test_file.txt".to_string(),
                     content_type: Some("text/plain".to_string()),
                     content_length: 100000,
+                    sha256: None,
                 },
                 metadata: HashMap::from([("update".to_string(), "no".to_string())]),
                 tags: vec!["events".to_string()],
+                visibility: FileVisibility::Public,
             },
+            180,
         )
         .await
         .expect("Failed to create test file");
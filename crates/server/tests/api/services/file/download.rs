@@ -0,0 +1,184 @@
+use crate::test_app::TestApp;
+use reqwest::{
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    StatusCode,
+};
+use serde_json::{json, Value};
+use test_context::test_context;
+
+const CONTENT: &str = "Invoice #42";
+const ALL_USERS: &str = "http://acs.amazonaws.com/groups/global/AllUsers";
+
+async fn create_file(ctx: &TestApp, visibility: &str) -> Value {
+    let response = ctx
+        .post(
+            "/file",
+            json!({
+                "file": {
+                    "contentType": "text/plain",
+                    "contentLength": CONTENT.len(),
+                    "fileName": "invoice.txt"
+                },
+                "tags": [],
+                "metadata": {},
+                "visibility": visibility
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body")
+}
+
+async fn upload_file(ctx: &TestApp, file: &Value, acl: &str) -> i64 {
+    let upload_url = file
+        .get("uploadUrl")
+        .and_then(|v| v.as_str())
+        .expect("Expected an upload URL");
+    let response = reqwest::Client::new()
+        .put(upload_url)
+        .header(CONTENT_TYPE, "text/plain")
+        .header(CONTENT_LENGTH, CONTENT.len())
+        .header("x-amz-acl", acl)
+        .body(CONTENT)
+        .send()
+        .await
+        .expect("Failed to upload file");
+    assert_eq!(StatusCode::OK, response.status());
+
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let response = ctx.post(format!("/file/{id}/confirm"), json!({})).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    id
+}
+
+async fn is_publicly_readable(ctx: &TestApp, key: &str) -> bool {
+    ctx.s3_client()
+        .get_object_acl()
+        .bucket(ctx.settings().storage().s3().buckets().file())
+        .key(key)
+        .send()
+        .await
+        .expect("Failed to get object ACL")
+        .grants()
+        .unwrap_or_default()
+        .iter()
+        .any(|grant| {
+            grant.grantee().and_then(|grantee| grantee.uri()) == Some(ALL_USERS)
+                && grant.permission().map(|p| p.as_str()) == Some("READ")
+        })
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn private_file_should_be_downloaded_from_presigned_url(ctx: &mut TestApp) {
+    ctx.create_api_key("test_download_file", false).await;
+
+    let file = create_file(ctx, "private").await;
+    assert_eq!(Some(&json!("private")), file.get("visibility"));
+    assert_eq!(Some(&Value::Null), file.get("publicUrl"));
+    let key = file
+        .get("key")
+        .and_then(|v| v.as_str())
+        .expect("Expected key");
+
+    let id = upload_file(ctx, &file, "private").await;
+    assert!(!is_publicly_readable(ctx, key).await);
+
+    // Read-only keys can download files
+    ctx.create_api_key("test_download_file", true).await;
+    let response = ctx.get(format!("/file/{id}/download")).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body");
+    assert!(body.get("expiresAt").and_then(|v| v.as_str()).is_some());
+    let url = body
+        .get("url")
+        .and_then(|v| v.as_str())
+        .expect("Expected a download URL");
+
+    let response = reqwest::get(url).await.expect("Failed to download file");
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(
+        CONTENT,
+        response.text().await.expect("Failed to read download")
+    );
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn download_pending_file_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("test_download_file", false).await;
+
+    let file = create_file(ctx, "private").await;
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+
+    let response = ctx.get(format!("/file/{id}/download")).await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body");
+    assert_eq!(Some(&json!("FLNUP")), body.get("code"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn download_file_from_other_namespace_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("test_download_file", false).await;
+    let file = create_file(ctx, "private").await;
+    let id = upload_file(ctx, &file, "private").await;
+
+    ctx.create_api_key("test_download_other", true).await;
+    let response = ctx.get(format!("/file/{id}/download")).await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn update_visibility_should_update_object_acl(ctx: &mut TestApp) {
+    ctx.create_api_key("test_download_file", false).await;
+
+    let file = create_file(ctx, "public").await;
+    let key = file
+        .get("key")
+        .and_then(|v| v.as_str())
+        .expect("Expected key");
+    assert!(file.get("publicUrl").and_then(|v| v.as_str()).is_some());
+
+    let id = upload_file(ctx, &file, "public-read").await;
+    assert!(is_publicly_readable(ctx, key).await);
+
+    let response = ctx
+        .put(format!("/file/{id}"), json!({ "visibility": "private" }))
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body");
+    assert_eq!(Some(&json!("private")), body.get("visibility"));
+    assert_eq!(Some(&Value::Null), body.get("publicUrl"));
+    assert!(!is_publicly_readable(ctx, key).await);
+
+    let response = ctx
+        .put(format!("/file/{id}"), json!({ "visibility": "public" }))
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    assert!(is_publicly_readable(ctx, key).await);
+}
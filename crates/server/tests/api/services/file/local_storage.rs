@@ -0,0 +1,281 @@
+use crate::test_app::{spawn_app_with_storage, TestApp};
+use async_trait::async_trait;
+use reqwest::{
+    header::{CONTENT_RANGE, CONTENT_TYPE, ETAG, RANGE},
+    StatusCode,
+};
+use serde_json::{json, Value};
+use server::config::StorageDriver;
+use test_context::{test_context, AsyncTestContext};
+
+const CONTENT: &str = "Invoice #42";
+
+/// Application storing its objects on the local filesystem
+struct LocalStorageApp(TestApp);
+
+#[async_trait]
+impl AsyncTestContext for LocalStorageApp {
+    async fn setup() -> Self {
+        Self(spawn_app_with_storage(StorageDriver::Local).await)
+    }
+
+    async fn teardown(self) {
+        self.0.terminate().await
+    }
+}
+
+async fn parse_body(response: reqwest::Response) -> Value {
+    response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body")
+}
+
+fn body_str<'a>(body: &'a Value, field: &str) -> &'a str {
+    body.get(field)
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Expected {field}"))
+}
+
+async fn create_file(ctx: &TestApp, visibility: &str) -> Value {
+    let response = ctx
+        .post(
+            "/file",
+            json!({
+                "file": {
+                    "contentType": "text/plain",
+                    "contentLength": CONTENT.len(),
+                    "fileName": "invoice.txt"
+                },
+                "tags": [],
+                "metadata": {},
+                "visibility": visibility
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    parse_body(response).await
+}
+
+async fn upload(url: &str, content: &'static str) -> reqwest::Response {
+    reqwest::Client::new()
+        .put(url)
+        .header(CONTENT_TYPE, "text/plain")
+        .body(content)
+        .send()
+        .await
+        .expect("Failed to upload file")
+}
+
+async fn get(url: &str, range: Option<&str>) -> reqwest::Response {
+    let mut request = reqwest::Client::new().get(url);
+    if let Some(range) = range {
+        request = request.header(RANGE, range);
+    }
+
+    request.send().await.expect("Failed to download file")
+}
+
+/// Uploads the file to its upload URL and confirms it
+async fn upload_file(ctx: &TestApp, file: &Value) -> Value {
+    let response = upload(body_str(file, "uploadUrl"), CONTENT).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let response = ctx.post(format!("/file/{id}/confirm"), json!({})).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    parse_body(response).await
+}
+
+#[test_context(LocalStorageApp)]
+#[tokio::test]
+async fn local_file_should_be_served_with_ranges(ctx: &mut LocalStorageApp) {
+    let ctx = &mut ctx.0;
+    ctx.create_api_key("test_local_storage", false).await;
+
+    let file = create_file(ctx, "public").await;
+    let file = upload_file(ctx, &file).await;
+    let public_url = body_str(&file, "publicUrl");
+    assert!(public_url.starts_with(&format!("http://127.0.0.1:{}/storage/", ctx.port())));
+
+    let response = get(public_url, None).await;
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(
+        Some("text/plain"),
+        response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+    );
+    assert_eq!(CONTENT, response.text().await.expect("Expected body"));
+
+    let response = get(public_url, Some("bytes=0-6")).await;
+    assert_eq!(StatusCode::PARTIAL_CONTENT, response.status());
+    assert_eq!(
+        Some("bytes 0-6/11"),
+        response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+    );
+    assert_eq!("Invoice", response.text().await.expect("Expected body"));
+
+    let response = get(public_url, Some("bytes=-2")).await;
+    assert_eq!(StatusCode::PARTIAL_CONTENT, response.status());
+    assert_eq!("42", response.text().await.expect("Expected body"));
+
+    let response = get(public_url, Some("bytes=20-")).await;
+    assert_eq!(StatusCode::RANGE_NOT_SATISFIABLE, response.status());
+    assert_eq!(
+        Some("bytes */11"),
+        response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+    );
+}
+
+#[test_context(LocalStorageApp)]
+#[tokio::test]
+async fn private_local_file_should_require_signed_url(ctx: &mut LocalStorageApp) {
+    let ctx = &mut ctx.0;
+    ctx.create_api_key("test_local_storage", false).await;
+
+    let file = create_file(ctx, "private").await;
+    let file = upload_file(ctx, &file).await;
+    assert_eq!(Some(&Value::Null), file.get("publicUrl"));
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+
+    let download = parse_body(ctx.get(format!("/file/{id}/download")).await).await;
+    let url = body_str(&download, "url");
+
+    let response = get(url, Some("bytes=8-")).await;
+    assert_eq!(StatusCode::PARTIAL_CONTENT, response.status());
+    assert_eq!("#42", response.text().await.expect("Expected body"));
+
+    let (unsigned, _) = url.split_once('?').expect("Expected a signed URL");
+    let response = get(unsigned, None).await;
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+
+    let tampered = url.replace("expires=", "expires=1");
+    let response = get(&tampered, None).await;
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+}
+
+#[test_context(LocalStorageApp)]
+#[tokio::test]
+async fn local_upload_should_match_signed_conditions(ctx: &mut LocalStorageApp) {
+    let ctx = &mut ctx.0;
+    ctx.create_api_key("test_local_storage", false).await;
+
+    let file = create_file(ctx, "public").await;
+    let upload_url = body_str(&file, "uploadUrl");
+
+    let response = upload(upload_url, "Invoice #4242").await;
+    assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, response.status());
+    assert_eq!(
+        Some(&json!("FLUPM")),
+        parse_body(response).await.get("code")
+    );
+
+    let tampered = upload_url.replace("contentLength=11", "contentLength=13");
+    let response = upload(&tampered, "Invoice #4242").await;
+    assert_eq!(StatusCode::FORBIDDEN, response.status());
+    assert_eq!(
+        Some(&json!("SGINV")),
+        parse_body(response).await.get("code")
+    );
+
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let response = ctx.post(format!("/file/{id}/confirm"), json!({})).await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+}
+
+#[test_context(LocalStorageApp)]
+#[tokio::test]
+async fn local_multipart_upload_should_assemble_parts(ctx: &mut LocalStorageApp) {
+    let ctx = &mut ctx.0;
+    ctx.create_api_key("test_local_storage", false).await;
+
+    let response = ctx
+        .post(
+            "/file/multipart",
+            json!({
+                "file": {
+                    "contentType": "text/plain",
+                    "contentLength": CONTENT.len(),
+                    "fileName": "invoice.txt"
+                },
+                "tags": [],
+                "metadata": {}
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    let file = parse_body(response).await;
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+
+    let parts = parse_body(
+        ctx.post(
+            format!("/file/{id}/multipart/parts"),
+            json!({ "partNumbers": [1] }),
+        )
+        .await,
+    )
+    .await;
+    let part_url = parts
+        .get("parts")
+        .and_then(|v| v.get(0))
+        .map(|part| body_str(part, "uploadUrl"))
+        .expect("Expected a part URL");
+
+    let response = reqwest::Client::new()
+        .put(part_url)
+        .body(CONTENT)
+        .send()
+        .await
+        .expect("Failed to upload part");
+    assert_eq!(StatusCode::OK, response.status());
+    let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .expect("Expected part ETag")
+        .to_string();
+
+    let response = ctx
+        .post(
+            format!("/file/{id}/multipart/complete"),
+            json!({ "parts": [{ "partNumber": 1, "etag": "\"0000\"" }] }),
+        )
+        .await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response = ctx
+        .post(
+            format!("/file/{id}/multipart/complete"),
+            json!({ "parts": [{ "partNumber": 1, "etag": etag }] }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    let file = parse_body(response).await;
+    assert_eq!(Some(&json!("uploaded")), file.get("status"));
+
+    let response = get(body_str(&file, "publicUrl"), None).await;
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(CONTENT, response.text().await.expect("Expected body"));
+}
@@ -1,4 +1,10 @@
+mod checksum;
+mod confirm;
 mod create;
 mod delete;
+mod download;
+mod local_storage;
+mod multipart;
 mod read;
 mod update;
+mod versions;
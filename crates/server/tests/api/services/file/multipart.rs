@@ -0,0 +1,229 @@
+use crate::test_app::TestApp;
+use reqwest::{header::ETAG, StatusCode};
+use sea_orm::EntityTrait;
+use serde_json::{json, Value};
+use test_context::test_context;
+
+/// Smallest part size, files above it are uploaded in several parts
+const PART_SIZE: usize = 8 * 1024 * 1024;
+
+async fn create_multipart_file(ctx: &TestApp, content_length: usize) -> reqwest::Response {
+    ctx.post(
+        "/file/multipart",
+        json!({
+            "file": {
+                "contentType": "video/mp4",
+                "contentLength": content_length,
+                "fileName": "video.mp4"
+            },
+            "tags": ["videos"],
+            "metadata": {}
+        }),
+    )
+    .await
+}
+
+async fn parse_body(response: reqwest::Response) -> Value {
+    response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body")
+}
+
+fn body_id(body: &Value) -> i64 {
+    body.get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID")
+}
+
+async fn upload_part(url: &str, body: Vec<u8>) -> String {
+    let response = reqwest::Client::new()
+        .put(url)
+        .body(body)
+        .send()
+        .await
+        .expect("Failed to upload part");
+    assert_eq!(StatusCode::OK, response.status());
+
+    response
+        .headers()
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .expect("Expected part ETag")
+        .to_string()
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn multipart_upload_should_assemble_parts(ctx: &mut TestApp) {
+    ctx.create_api_key("test_multipart_file", false).await;
+
+    let content_length = PART_SIZE + 11;
+    let response = create_multipart_file(ctx, content_length).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let file = parse_body(response).await;
+    let id = body_id(&file);
+    assert_eq!(Some(&json!("pending")), file.get("status"));
+    assert_eq!(Some(&json!(PART_SIZE)), file.get("partSize"));
+    assert_eq!(Some(&json!(2)), file.get("partCount"));
+
+    let response = ctx
+        .post(
+            format!("/file/{id}/multipart/parts"),
+            json!({ "partNumbers": [1, 2] }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let parts = parse_body(response).await;
+    assert!(parts
+        .get("uploadExpiresAt")
+        .and_then(|v| v.as_str())
+        .is_some());
+    let urls: Vec<&str> = parts
+        .get("parts")
+        .and_then(|v| v.as_array())
+        .expect("Expected parts")
+        .iter()
+        .filter_map(|part| part.get("uploadUrl").and_then(|v| v.as_str()))
+        .collect();
+    assert_eq!(2, urls.len());
+
+    let first_etag = upload_part(urls[0], vec![0; PART_SIZE]).await;
+    let second_etag = upload_part(urls[1], vec![1; 11]).await;
+
+    let response = ctx
+        .post(
+            format!("/file/{id}/multipart/complete"),
+            json!({
+                "parts": [
+                    { "partNumber": 2, "etag": second_etag },
+                    { "partNumber": 1, "etag": first_etag }
+                ]
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let body = parse_body(response).await;
+    assert_eq!(Some(&json!("uploaded")), body.get("status"));
+    assert_eq!(Some(&json!(content_length)), body.get("contentLength"));
+
+    let listed = parse_body(ctx.get("/file").await).await;
+    assert_eq!(1, listed.as_array().expect("Expected a list").len());
+
+    let object = ctx
+        .s3_client()
+        .head_object()
+        .bucket(ctx.settings().storage().s3().buckets().file())
+        .key(
+            body.get("key")
+                .and_then(|v| v.as_str())
+                .expect("Expected key"),
+        )
+        .send()
+        .await
+        .expect("Expected object to be uploaded");
+    assert_eq!(content_length as i64, object.content_length());
+    assert_eq!(Some("video/mp4"), object.content_type());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn complete_multipart_upload_with_invalid_part_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("test_multipart_file", false).await;
+
+    let file = parse_body(create_multipart_file(ctx, 11).await).await;
+    let id = body_id(&file);
+
+    let response = ctx
+        .post(
+            format!("/file/{id}/multipart/complete"),
+            json!({ "parts": [{ "partNumber": 1, "etag": "\"unknown\"" }] }),
+        )
+        .await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    assert_eq!(
+        Some(&json!("FLINV")),
+        parse_body(response).await.get("code")
+    );
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn presign_unknown_parts_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("test_multipart_file", false).await;
+
+    let file = parse_body(create_multipart_file(ctx, 11).await).await;
+    assert_eq!(Some(&json!(1)), file.get("partCount"));
+    let id = body_id(&file);
+
+    for part_number in [0, 2] {
+        let response = ctx
+            .post(
+                format!("/file/{id}/multipart/parts"),
+                json!({ "partNumbers": [part_number] }),
+            )
+            .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+        assert_eq!(
+            Some(&json!("FLINV")),
+            parse_body(response).await.get("code")
+        );
+    }
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn abort_multipart_upload_should_remove_file(ctx: &mut TestApp) {
+    ctx.create_api_key("test_multipart_file", false).await;
+
+    let file = parse_body(create_multipart_file(ctx, 11).await).await;
+    let id = body_id(&file);
+
+    let response = ctx.delete(format!("/file/{id}/multipart")).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let model = entity::file::Entity::find_by_id(id as i32)
+        .one(ctx.database_connection())
+        .await
+        .expect("Failed to find file");
+    assert!(model.is_none());
+
+    let response = ctx
+        .post(
+            format!("/file/{id}/multipart/parts"),
+            json!({ "partNumbers": [1] }),
+        )
+        .await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn namespace_max_size_should_apply_to_uploads(ctx: &mut TestApp) {
+    ctx.create_api_key("small_files", false).await;
+
+    let response = create_multipart_file(ctx, 2000).await;
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+    assert_eq!(
+        Some(&json!("FTBIG")),
+        parse_body(response).await.get("code")
+    );
+
+    let response = ctx
+        .post(
+            "/file",
+            json!({
+                "file": { "contentLength": 2000, "fileName": "file.pdf" },
+                "tags": [],
+                "metadata": {}
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+
+    let response = create_multipart_file(ctx, 1000).await;
+    assert_eq!(StatusCode::OK, response.status());
+}
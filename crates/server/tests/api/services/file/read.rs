@@ -1,7 +1,8 @@
-use std::collections::HashMap;
-
+use chrono::{Duration, SecondsFormat, Utc};
+use entity::file::FileVisibility;
 use reqwest::StatusCode;
 use sea_orm::ConnectionTrait;
+use serde_json::{json, Value};
 use test_context::test_context;
 
 use server::services::files::{
@@ -16,20 +17,50 @@ async fn create_test_file<T: ConnectionTrait>(
     namespace: &str,
     tags: Vec<&str>,
 ) -> entity::file::Model {
-    db.create_file(
+    create_described_file(
+        db,
         namespace,
-        FileInput {
-            file: FilePayload {
-                content_type: Some("text/plain".to_string()),
-                content_length: 10000000,
-                file_name: "file.txt".to_string(),
-            },
-            tags: tags.iter().map(|str| str.to_string()).collect(),
-            metadata: HashMap::from([("some".to_string(), "metadata".to_string())]),
-        },
+        "file.txt",
+        "text/plain",
+        tags,
+        vec![("some", "metadata")],
     )
     .await
-    .expect("Failed to create file")
+}
+
+async fn create_described_file<T: ConnectionTrait>(
+    db: &T,
+    namespace: &str,
+    file_name: &str,
+    content_type: &str,
+    tags: Vec<&str>,
+    metadata: Vec<(&str, &str)>,
+) -> entity::file::Model {
+    let file = db
+        .create_file(
+            namespace,
+            FileInput {
+                file: FilePayload {
+                    content_type: Some(content_type.to_string()),
+                    content_length: 10000000,
+                    file_name: file_name.to_string(),
+                    sha256: None,
+                },
+                tags: tags.iter().map(|str| str.to_string()).collect(),
+                metadata: metadata
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect(),
+                visibility: FileVisibility::Public,
+            },
+            180,
+        )
+        .await
+        .expect("Failed to create file");
+
+    db.confirm_file_upload(file)
+        .await
+        .expect("Failed to confirm file")
 }
 
 #[test_context(TestApp)]
@@ -57,3 +88,216 @@ async fn list_file_should_work(ctx: &mut TestApp) {
 
     assert_eq!(3, body.len());
 }
+
+async fn list_file_names(ctx: &TestApp, query: &str) -> Vec<String> {
+    let response = ctx.get(format!("/file?{query}")).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let mut names: Vec<String> = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body")
+        .as_array()
+        .expect("Expected json list response")
+        .iter()
+        .map(|file| {
+            let key = file
+                .get("key")
+                .and_then(|v| v.as_str())
+                .expect("Expected key");
+            key.split_once('_')
+                .expect("Expected prefixed key")
+                .1
+                .to_string()
+        })
+        .collect();
+    names.sort();
+
+    names
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn list_file_with_tags_should_match_any_or_all(ctx: &mut TestApp) {
+    ctx.create_api_key("test_file", true).await;
+    let db = ctx.database_connection();
+
+    create_described_file(
+        db,
+        "test_file",
+        "a.txt",
+        "text/plain",
+        vec!["events", "2023"],
+        vec![],
+    )
+    .await;
+    create_described_file(
+        db,
+        "test_file",
+        "b.txt",
+        "text/plain",
+        vec!["events"],
+        vec![],
+    )
+    .await;
+    create_described_file(db, "test_file", "c.txt", "text/plain", vec!["2023"], vec![]).await;
+    create_described_file(
+        db,
+        "test_file",
+        "d.txt",
+        "text/plain",
+        vec!["other"],
+        vec![],
+    )
+    .await;
+
+    assert_eq!(
+        vec!["a.txt", "b.txt", "c.txt"],
+        list_file_names(ctx, "tags=events,2023").await
+    );
+    assert_eq!(
+        vec!["a.txt"],
+        list_file_names(ctx, "tags=events,2023&tagMatch=all").await
+    );
+    // The single tag filter is still supported
+    assert_eq!(
+        vec!["a.txt", "b.txt"],
+        list_file_names(ctx, "tag=events").await
+    );
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn list_file_with_metadata_should_filter(ctx: &mut TestApp) {
+    ctx.create_api_key("test_file", true).await;
+    let db = ctx.database_connection();
+
+    create_described_file(
+        db,
+        "test_file",
+        "a.txt",
+        "text/plain",
+        vec![],
+        vec![("author", "jane"), ("draft", "yes")],
+    )
+    .await;
+    create_described_file(
+        db,
+        "test_file",
+        "b.txt",
+        "text/plain",
+        vec![],
+        vec![("author", "jane")],
+    )
+    .await;
+    create_described_file(
+        db,
+        "test_file",
+        "c.txt",
+        "text/plain",
+        vec![],
+        vec![("author", "john")],
+    )
+    .await;
+
+    assert_eq!(
+        vec!["a.txt", "b.txt"],
+        list_file_names(ctx, "metadata=author:jane").await
+    );
+    assert_eq!(
+        vec!["a.txt"],
+        list_file_names(ctx, "metadata=author:jane,draft").await
+    );
+    assert!(list_file_names(ctx, "metadata=author:jack")
+        .await
+        .is_empty());
+
+    let response = ctx.get("/file?metadata=:jane").await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn list_file_with_content_type_dates_and_search_should_filter(ctx: &mut TestApp) {
+    ctx.create_api_key("test_file", true).await;
+    let db = ctx.database_connection();
+
+    create_described_file(
+        db,
+        "test_file",
+        "invoice_2023.pdf",
+        "application/pdf",
+        vec![],
+        vec![],
+    )
+    .await;
+    create_described_file(
+        db,
+        "test_file",
+        "Invoice_logo.png",
+        "image/png",
+        vec![],
+        vec![],
+    )
+    .await;
+    create_described_file(db, "test_file", "photo.jpg", "image/jpeg", vec![], vec![]).await;
+
+    assert_eq!(
+        vec!["Invoice_logo.png", "photo.jpg"],
+        list_file_names(ctx, "contentType=image/*").await
+    );
+    assert_eq!(
+        vec!["invoice_2023.pdf"],
+        list_file_names(ctx, "contentType=application/pdf").await
+    );
+    assert_eq!(
+        vec!["Invoice_logo.png", "invoice_2023.pdf"],
+        list_file_names(ctx, "search=INVOICE").await
+    );
+    // Wildcards are searched literally
+    assert_eq!(
+        vec!["invoice_2023.pdf"],
+        list_file_names(ctx, "search=e_2").await
+    );
+
+    let in_an_hour = (Utc::now() + Duration::hours(1)).to_rfc3339_opts(SecondsFormat::Secs, true);
+    assert_eq!(
+        3,
+        list_file_names(ctx, &format!("createdBefore={in_an_hour}"))
+            .await
+            .len()
+    );
+    assert!(list_file_names(
+        ctx,
+        &format!("createdAfter={in_an_hour}&contentType=image/*")
+    )
+    .await
+    .is_empty());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn list_file_tags_should_count_files(ctx: &mut TestApp) {
+    ctx.create_api_key("test_file", true).await;
+
+    create_test_file(
+        ctx.database_connection(),
+        "test_file",
+        vec!["events", "2023"],
+    )
+    .await;
+    create_test_file(ctx.database_connection(), "test_file", vec!["events"]).await;
+    create_test_file(ctx.database_connection(), "other_namespace", vec!["events"]).await;
+
+    let response = ctx.get("/file/tags").await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body");
+    assert_eq!(
+        json!([{ "tag": "events", "count": 2 }, { "tag": "2023", "count": 1 }]),
+        body
+    );
+}
@@ -1,4 +1,5 @@
 use crate::test_app::TestApp;
+use entity::file::FileVisibility;
 use insta::assert_json_snapshot;
 use reqwest::StatusCode;
 use serde_json::json;
@@ -23,10 +24,13 @@ async fn update_file_metadata_should_work(ctx: &mut TestApp) {
                     file_name: "test_file.txt".to_string(),
                     content_type: Some("text/plain".to_string()),
                     content_length: 100000,
+                    sha256: None,
                 },
                 metadata: HashMap::from([("update".to_string(), "no".to_string())]),
                 tags: vec!["events".to_string()],
+                visibility: FileVisibility::Public,
             },
+            180,
         )
         .await
         .expect("Failed to create test file");
@@ -56,6 +60,7 @@ async fn update_file_metadata_should_work(ctx: &mut TestApp) {
     assert!(body.get("updatedAt").is_some());
     assert!(body.get("publicUrl").is_some());
     assert!(body.get("key").is_some());
+    assert!(body.get("uploadExpiresAt").is_some());
     assert_eq!(Some(&serde_json::Value::Null), body.get("uploadUrl"));
 
     let mut body = (*body.as_object().expect("Body is not a JSON object")).clone();
@@ -66,6 +71,7 @@ async fn update_file_metadata_should_work(ctx: &mut TestApp) {
     body.remove("updatedAt");
     body.remove("publicUrl");
     body.remove("key");
+    body.remove("uploadExpiresAt");
 
     assert_json_snapshot!(body);
 }
@@ -84,10 +90,13 @@ async fn update_file_should_work(ctx: &mut TestApp) {
                     file_name: "test_file.txt".to_string(),
                     content_type: Some("text/plain".to_string()),
                     content_length: 100000,
+                    sha256: None,
                 },
                 metadata: HashMap::from([("update".to_string(), "no".to_string())]),
                 tags: vec!["events".to_string()],
+                visibility: FileVisibility::Public,
             },
+            180,
         )
         .await
         .expect("Failed to create test file");
@@ -119,6 +128,7 @@ async fn update_file_should_work(ctx: &mut TestApp) {
     assert!(body.get("updatedAt").is_some());
     assert!(body.get("publicUrl").is_some());
     assert!(body.get("key").is_some());
+    assert!(body.get("uploadExpiresAt").is_some());
     assert!(body.get("uploadUrl").is_some());
 
     let mut body = (*body.as_object().expect("Body is not a JSON object")).clone();
@@ -129,6 +139,7 @@ async fn update_file_should_work(ctx: &mut TestApp) {
     body.remove("updatedAt");
     body.remove("publicUrl");
     body.remove("key");
+    body.remove("uploadExpiresAt");
     body.remove("uploadUrl");
 
     assert_json_snapshot!(body);
@@ -0,0 +1,235 @@
+use crate::test_app::TestApp;
+use chrono::{Duration, Utc};
+use reqwest::{
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+    StatusCode,
+};
+use serde_json::{json, Value};
+use server::jobs::files::remove_expired_uploads;
+use test_context::test_context;
+
+fn file_payload(content: &str) -> Value {
+    json!({
+        "contentType": "text/plain",
+        "contentLength": content.len(),
+        "fileName": "report.txt"
+    })
+}
+
+fn get_str<'a>(body: &'a Value, key: &str) -> &'a str {
+    body.get(key)
+        .and_then(|v| v.as_str())
+        .unwrap_or_else(|| panic!("Expected {key}"))
+}
+
+async fn json_body(response: reqwest::Response) -> Value {
+    response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body")
+}
+
+/// Uploads `content` to the upload URL of `file` and confirms it
+async fn upload_and_confirm(ctx: &TestApp, file: &Value, content: &'static str) -> Value {
+    let response = reqwest::Client::new()
+        .put(get_str(file, "uploadUrl"))
+        .header(CONTENT_TYPE, "text/plain")
+        .header(CONTENT_LENGTH, content.len())
+        .header("x-amz-acl", "public-read")
+        .body(content)
+        .send()
+        .await
+        .expect("Failed to upload file");
+    assert_eq!(StatusCode::OK, response.status());
+
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let response = ctx.post(format!("/file/{id}/confirm"), json!({})).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    json_body(response).await
+}
+
+async fn create_uploaded_file(ctx: &TestApp, content: &'static str) -> Value {
+    let response = ctx
+        .post(
+            "/file",
+            json!({ "file": file_payload(content), "tags": [], "metadata": {} }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    upload_and_confirm(ctx, &json_body(response).await, content).await
+}
+
+async fn replace_content(ctx: &TestApp, id: i64, content: &str) -> Value {
+    let response = ctx
+        .put(
+            format!("/file/{id}"),
+            json!({ "file": file_payload(content) }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    json_body(response).await
+}
+
+async fn list_versions(ctx: &TestApp, id: i64) -> Vec<Value> {
+    let response = ctx.get(format!("/file/{id}/versions")).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    json_body(response)
+        .await
+        .as_array()
+        .expect("Expected json list response")
+        .to_owned()
+}
+
+async fn object_exists(ctx: &TestApp, key: &str) -> bool {
+    ctx.s3_client()
+        .head_object()
+        .bucket(ctx.settings().storage().s3().buckets().file())
+        .key(key)
+        .send()
+        .await
+        .is_ok()
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn replaced_content_should_be_kept_as_version(ctx: &mut TestApp) {
+    ctx.create_api_key("test_file_versions", false).await;
+
+    let file = create_uploaded_file(ctx, "first").await;
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let first_key = get_str(&file, "key").to_string();
+    assert!(list_versions(ctx, id).await.is_empty());
+
+    let replaced = replace_content(ctx, id, "second").await;
+    upload_and_confirm(ctx, &replaced, "second").await;
+
+    let versions = list_versions(ctx, id).await;
+    assert_eq!(1, versions.len());
+    assert_eq!(first_key, get_str(&versions[0], "key"));
+    assert_eq!(
+        Some(&json!("first".len())),
+        versions[0].get("contentLength")
+    );
+    assert!(versions[0]
+        .get("publicUrl")
+        .and_then(|v| v.as_str())
+        .is_some());
+    assert!(object_exists(ctx, &first_key).await);
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn oldest_versions_should_be_deleted(ctx: &mut TestApp) {
+    ctx.create_api_key("test_file_versions", false).await;
+
+    // The test app keeps 2 versions
+    let file = create_uploaded_file(ctx, "v1").await;
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let first_key = get_str(&file, "key").to_string();
+
+    for content in ["v2", "v3", "v4"] {
+        let replaced = replace_content(ctx, id, content).await;
+        upload_and_confirm(ctx, &replaced, content).await;
+    }
+
+    let versions = list_versions(ctx, id).await;
+    assert_eq!(2, versions.len());
+    assert!(versions
+        .iter()
+        .all(|version| get_str(version, "key") != first_key));
+    assert!(!object_exists(ctx, &first_key).await);
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn restore_version_should_swap_content(ctx: &mut TestApp) {
+    ctx.create_api_key("test_file_versions", false).await;
+
+    let file = create_uploaded_file(ctx, "first").await;
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let first_key = get_str(&file, "key").to_string();
+
+    let replaced = replace_content(ctx, id, "second").await;
+    let second_key = get_str(&replaced, "key").to_string();
+    upload_and_confirm(ctx, &replaced, "second").await;
+
+    let version_id = list_versions(ctx, id).await[0]
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected version ID");
+    let response = ctx
+        .post(
+            format!("/file/{id}/versions/{version_id}/restore"),
+            json!({}),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let body = json_body(response).await;
+    assert_eq!(first_key, get_str(&body, "key"));
+    assert_eq!(Some(&json!("uploaded")), body.get("status"));
+    assert_eq!(Some(&json!("first".len())), body.get("contentLength"));
+
+    let versions = list_versions(ctx, id).await;
+    assert_eq!(1, versions.len());
+    assert_eq!(second_key, get_str(&versions[0], "key"));
+
+    // Restored versions are no longer versions
+    let response = ctx
+        .post(
+            format!("/file/{id}/versions/{version_id}/restore"),
+            json!({}),
+        )
+        .await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn expired_replacement_should_restore_previous_content(ctx: &mut TestApp) {
+    ctx.create_api_key("test_file_versions", false).await;
+
+    let file = create_uploaded_file(ctx, "first").await;
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let first_key = get_str(&file, "key").to_string();
+
+    // Never uploaded
+    let replaced = replace_content(ctx, id, "second").await;
+    assert_eq!(Some(&json!("pending")), replaced.get("status"));
+
+    let expired = remove_expired_uploads(
+        ctx.database_connection(),
+        ctx.storage().as_ref(),
+        ctx.settings().storage().s3().buckets(),
+        Utc::now() + Duration::hours(1),
+    )
+    .await
+    .expect("Failed to remove expired uploads");
+    assert_eq!(1, expired);
+
+    let files = json_body(ctx.get("/file").await).await;
+    let files = files.as_array().expect("Expected json list response");
+    assert_eq!(1, files.len());
+    assert_eq!(first_key, get_str(&files[0], "key"));
+    assert_eq!(Some(&json!("uploaded")), files[0].get("status"));
+    assert!(list_versions(ctx, id).await.is_empty());
+}
@@ -0,0 +1,318 @@
+use crate::{services::image::create::create_image, test_app::TestApp, utils::wipe_bucket};
+use async_trait::async_trait;
+use aws_sdk_s3::types::ByteStream;
+use chrono::{Duration, Utc};
+use entity::image;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait, IntoActiveModel, PaginatorTrait};
+use serde_json::Value;
+use server::{
+    jobs::objects::{delete_objects, reconcile_image_objects, retry_object_deletions},
+    storage::{ObjectInfo, ObjectOptions, ObjectPage, Storage, StorageError, UploadConditions},
+};
+use test_context::test_context;
+use uuid::Uuid;
+
+/// Lists a single object per page, as large buckets spread the objects of an
+/// image over several pages
+struct SingleObjectPages<'a>(&'a dyn Storage);
+
+#[async_trait]
+impl Storage for SingleObjectPages<'_> {
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        body: Vec<u8>,
+        options: ObjectOptions,
+    ) -> Result<(), StorageError> {
+        self.0.put_object(bucket, key, body, options).await
+    }
+
+    async fn get_object(&self, bucket: &str, key: &str) -> Result<Vec<u8>, StorageError> {
+        self.0.get_object(bucket, key).await
+    }
+
+    async fn head_object(&self, bucket: &str, key: &str) -> Result<ObjectInfo, StorageError> {
+        self.0.head_object(bucket, key).await
+    }
+
+    async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), StorageError> {
+        self.0.delete_object(bucket, key).await
+    }
+
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        continuation: Option<String>,
+    ) -> Result<ObjectPage, StorageError> {
+        let mut objects = self.0.list_objects(bucket, None).await?.objects;
+        objects.sort_by(|a, b| a.key.cmp(&b.key));
+        let mut remaining = objects.into_iter().filter(|object| {
+            continuation
+                .as_ref()
+                .is_none_or(|after| &object.key > after)
+        });
+
+        let objects: Vec<_> = remaining.next().into_iter().collect();
+        Ok(ObjectPage {
+            continuation: remaining
+                .next()
+                .and_then(|_| objects.first())
+                .map(|object| object.key.clone()),
+            objects,
+        })
+    }
+
+    async fn set_object_visibility(
+        &self,
+        bucket: &str,
+        key: &str,
+        public: bool,
+    ) -> Result<(), StorageError> {
+        self.0.set_object_visibility(bucket, key, public).await
+    }
+
+    async fn presign_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        conditions: &UploadConditions,
+        url_ttl: u64,
+    ) -> Result<String, StorageError> {
+        self.0
+            .presign_upload(bucket, key, conditions, url_ttl)
+            .await
+    }
+
+    async fn presign_download(
+        &self,
+        bucket: &str,
+        key: &str,
+        url_ttl: u64,
+    ) -> Result<String, StorageError> {
+        self.0.presign_download(bucket, key, url_ttl).await
+    }
+
+    async fn create_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        options: ObjectOptions,
+    ) -> Result<String, StorageError> {
+        self.0.create_multipart_upload(bucket, key, options).await
+    }
+
+    async fn presign_upload_part(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        part_number: u32,
+        url_ttl: u64,
+    ) -> Result<String, StorageError> {
+        self.0
+            .presign_upload_part(bucket, key, upload_id, part_number, url_ttl)
+            .await
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        parts: Vec<(u32, String)>,
+    ) -> Result<(), StorageError> {
+        self.0
+            .complete_multipart_upload(bucket, key, upload_id, parts)
+            .await
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<(), StorageError> {
+        self.0.abort_multipart_upload(bucket, key, upload_id).await
+    }
+}
+
+async fn list_image_keys(ctx: &TestApp) -> Vec<String> {
+    ctx.s3_client()
+        .list_objects_v2()
+        .bucket(ctx.settings().storage().s3().buckets().image())
+        .send()
+        .await
+        .expect("Failed to list objects")
+        .contents()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|object| object.key().map(ToString::to_string))
+        .collect()
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn reconcile_image_objects_should_delete_orphaned_objects(ctx: &mut TestApp) {
+    ctx.create_api_key("tests", false).await;
+
+    create_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "reconcile_image.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        None,
+    )
+    .await;
+
+    let bucket = ctx.settings().storage().s3().buckets().image();
+
+    ctx.s3_client()
+        .put_object()
+        .bucket(bucket)
+        .key("orphan.jpeg")
+        .body(ByteStream::from_static(b"orphan"))
+        .send()
+        .await
+        .expect("Failed to upload orphaned object");
+
+    // Recently written objects are left alone
+    let orphans = reconcile_image_objects(
+        ctx.database_connection(),
+        ctx.storage().as_ref(),
+        ctx.settings().storage().s3().buckets(),
+        Utc::now() - Duration::hours(1),
+    )
+    .await
+    .expect("Failed to reconcile image objects");
+    assert_eq!(0, orphans);
+
+    let orphans = reconcile_image_objects(
+        ctx.database_connection(),
+        ctx.storage().as_ref(),
+        ctx.settings().storage().s3().buckets(),
+        Utc::now() + Duration::seconds(5),
+    )
+    .await
+    .expect("Failed to reconcile image objects");
+    assert_eq!(1, orphans);
+
+    let keys = list_image_keys(ctx).await;
+
+    // The original, the image and its lazy image are kept
+    assert_eq!(3, keys.len());
+    assert!(!keys.contains(&"orphan.jpeg".to_string()));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn reconcile_image_objects_should_keep_originals_listed_apart(ctx: &mut TestApp) {
+    ctx.create_api_key("tests", false).await;
+
+    let id = create_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "reconcile_original.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        None,
+    )
+    .await
+    .json::<Value>()
+    .await
+    .expect("Cannot parse image output")
+    .get("id")
+    .and_then(|v| v.as_i64())
+    .expect("Expected image to have an id");
+
+    // Replaced originals do not share the prefix of the renditions
+    let bucket = ctx.settings().storage().s3().buckets().image();
+    let original_key = format!("{}__replaced.jpg", Uuid::new_v4());
+    ctx.s3_client()
+        .put_object()
+        .bucket(bucket)
+        .key(&original_key)
+        .body(ByteStream::from_static(b"original"))
+        .send()
+        .await
+        .expect("Failed to upload original");
+    let image = image::Entity::find_by_id(id as i32)
+        .one(ctx.database_connection())
+        .await
+        .expect("Failed to fetch image")
+        .expect("Image should exist");
+    let mut image = image.into_active_model();
+    image.original_storage_key = Set(Some(original_key.clone()));
+    image
+        .update(ctx.database_connection())
+        .await
+        .expect("Failed to update image");
+
+    let orphans = reconcile_image_objects(
+        ctx.database_connection(),
+        &SingleObjectPages(ctx.storage().as_ref()),
+        ctx.settings().storage().s3().buckets(),
+        Utc::now() + Duration::seconds(5),
+    )
+    .await
+    .expect("Failed to reconcile image objects");
+
+    // Only the previous original is orphaned
+    assert_eq!(1, orphans);
+    assert!(list_image_keys(ctx).await.contains(&original_key));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn failed_object_deletions_should_be_queued(ctx: &mut TestApp) {
+    let missing_bucket = format!("missing{}", Uuid::new_v4().to_string().replace('-', ""));
+
+    delete_objects(
+        ctx.database_connection(),
+        ctx.storage().as_ref(),
+        &missing_bucket,
+        vec!["missing.jpeg".to_string()],
+    )
+    .await
+    .expect("Failed to delete objects");
+
+    let queued = entity::object_deletion::Entity::find()
+        .all(ctx.database_connection())
+        .await
+        .expect("Failed to fetch queued deletions");
+    assert_eq!(1, queued.len());
+    assert_eq!(0, queued[0].attempts);
+    assert!(queued[0].last_error.is_some());
+
+    let deleted = retry_object_deletions(ctx.database_connection(), ctx.storage().as_ref())
+        .await
+        .expect("Failed to retry object deletions");
+    assert_eq!(0, deleted);
+
+    let queued = entity::object_deletion::Entity::find()
+        .one(ctx.database_connection())
+        .await
+        .expect("Failed to fetch queued deletions")
+        .expect("Deletion should still be queued");
+    assert_eq!(1, queued.attempts);
+
+    // Once the bucket exists, the deletion succeeds
+    ctx.s3_client()
+        .create_bucket()
+        .bucket(&missing_bucket)
+        .send()
+        .await
+        .expect("Failed to create bucket");
+
+    let deleted = retry_object_deletions(ctx.database_connection(), ctx.storage().as_ref())
+        .await
+        .expect("Failed to retry object deletions");
+    assert_eq!(1, deleted);
+
+    let remaining = entity::object_deletion::Entity::find()
+        .count(ctx.database_connection())
+        .await
+        .expect("Failed to count queued deletions");
+    assert_eq!(0, remaining);
+
+    wipe_bucket(ctx.s3_client(), &missing_bucket).await;
+}
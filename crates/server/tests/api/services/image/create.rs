@@ -1,9 +1,15 @@
-use crate::{services, services::image::assert_image_output, test_app::TestApp};
+use crate::{
+    services,
+    services::{image::assert_image_output, image_profile::update::put_image_profile},
+    test_app::TestApp,
+};
 use reqwest::{multipart, Response, StatusCode};
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::time::Duration;
 use test_context::test_context;
 use tokio::fs::File;
 use tokio_util::codec::{BytesCodec, FramedRead};
+use url::Url;
 
 pub async fn create_image(
     app: &TestApp,
@@ -17,6 +23,60 @@ pub async fn create_image(
         None => "".to_string(),
     };
 
+    let response = upload_image(app, file_path, filename, mime, &query).await;
+    wait_for_image(app, response).await
+}
+
+/// Renditions are generated in the background, polls the accepted image until
+/// they are and returns it
+pub async fn wait_for_image(app: &TestApp, response: Response) -> Response {
+    assert_eq!(StatusCode::ACCEPTED, response.status());
+    let id = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse image output")
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected image to have an id");
+
+    wait_for_image_id(app, id).await
+}
+
+pub async fn wait_for_image_id(app: &TestApp, id: i64) -> Response {
+    for _ in 0..120 {
+        let image = app
+            .get(format!("/image/{id}"))
+            .await
+            .json::<Value>()
+            .await
+            .expect("Cannot parse image output");
+
+        if image.get("status").and_then(|v| v.as_str()) != Some("processing") {
+            return app.get(format!("/image/{id}")).await;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    panic!("Image {id} is still processing");
+}
+
+pub async fn upload_image(
+    app: &TestApp,
+    file_path: &str,
+    filename: &'static str,
+    mime: &str,
+    query: &str,
+) -> Response {
+    post_image_file(app, format!("/image{}", query), file_path, filename, mime).await
+}
+
+pub async fn post_image_file(
+    app: &TestApp,
+    uri: String,
+    file_path: &str,
+    filename: &'static str,
+    mime: &str,
+) -> Response {
     let file_handle = File::open(file_path)
         .await
         .unwrap_or_else(|_| panic!("Failed to open image at {}", file_path));
@@ -30,9 +90,7 @@ pub async fn create_image(
             .mime_str(mime)
             .expect("Invalid mime for creating an image"),
     );
-    let response = app.post_multipart(format!("/image{}", query), form).await;
-    assert_eq!(StatusCode::OK, response.status());
-    response
+    app.post_multipart(uri, form).await
 }
 
 #[test_context(TestApp)]
@@ -79,3 +137,729 @@ async fn create_valid_image_should_work(ctx: &mut TestApp) {
             .expect("Failed to remove downloaded file");
     }
 }
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_image_should_be_accepted_then_processed(ctx: &mut TestApp) {
+    ctx.create_api_key("create_image_should_be_accepted_then_processed", false)
+        .await;
+
+    let response = upload_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "create_image_should_be_accepted_then_processed.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        "?alt=Example",
+    )
+    .await;
+    assert_eq!(StatusCode::ACCEPTED, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    let id = json
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+
+    assert_eq!(Some(&json!("processing")), json.get("status"));
+    assert_eq!(Some(&Value::Null), json.get("publicUrl"));
+    assert_eq!(Some(&Value::Null), json.get("lazyImage"));
+
+    // Images being processed are listed
+    let listed = ctx
+        .get("/image")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    assert!(listed
+        .as_array()
+        .expect("Expected an array")
+        .iter()
+        .any(|image| image.get("id") == Some(&json!(id))));
+
+    let json = wait_for_image_id(ctx, id)
+        .await
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    assert_image_output(&json);
+    assert_eq!(Some(&json!(400)), json.get("width"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_image_should_generate_profile_variants(ctx: &mut TestApp) {
+    ctx.create_api_key("create_image_should_generate_profile_variants", false)
+        .await;
+
+    put_image_profile(ctx, "small", &json!({ "width": 320, "height": 320 })).await;
+    put_image_profile(
+        ctx,
+        "medium",
+        &json!({ "width": 800, "height": 800, "fit": "contain" }),
+    )
+    .await;
+
+    let response = create_image(
+        ctx,
+        "tests/fixtures/img/landscape_2048x1360.jpg",
+        "create_image_should_generate_profile_variants.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        Some("Example image"),
+    )
+    .await;
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    assert_image_output(&json);
+
+    let variants = json
+        .get("variants")
+        .and_then(|v| v.as_array())
+        .expect("Expected variants to be an array");
+
+    assert_eq!(
+        vec![Some("small"), Some("medium")],
+        variants
+            .iter()
+            .map(|v| v.get("name").and_then(|v| v.as_str()))
+            .collect::<Vec<_>>()
+    );
+
+    for (variant, (width, height)) in variants.iter().zip([(320, 320), (800, 531)]) {
+        assert_eq!(Some(width), variant.get("width").and_then(|v| v.as_u64()));
+        assert_eq!(Some(height), variant.get("height").and_then(|v| v.as_u64()));
+
+        let url: Url = variant
+            .get("publicUrl")
+            .and_then(|v| v.as_str())
+            .expect("Expect publicUrl to be a string")
+            .parse()
+            .expect("Public URL is not a valid url");
+
+        let path = services::image::download_file(&url).await;
+        let parsed = image::open(&path).expect("Failed to open/parse variant image");
+        assert_eq!(width as u32, parsed.width());
+        assert_eq!(height as u32, parsed.height());
+        tokio::fs::remove_file(&path)
+            .await
+            .expect("Failed to remove downloaded file");
+    }
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_image_should_encode_alternate_formats(ctx: &mut TestApp) {
+    ctx.create_api_key("create_image_should_encode_alternate_formats", false)
+        .await;
+
+    put_image_profile(ctx, "small", &json!({ "width": 100, "height": 100 })).await;
+
+    let response = upload_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "create_image_should_encode_alternate_formats.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        "?alt=Example&formats=webp,avif",
+    )
+    .await;
+    let response = wait_for_image(ctx, response).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    assert_image_output(&json);
+
+    let sources = json
+        .get("sources")
+        .and_then(|v| v.as_array())
+        .expect("Expected sources to be an array");
+
+    assert_eq!(
+        vec![
+            (Some("webp"), Some("image/webp")),
+            (Some("avif"), Some("image/avif")),
+            (Some("jpeg"), Some("image/jpeg"))
+        ],
+        sources
+            .iter()
+            .map(|v| (
+                v.get("format").and_then(|v| v.as_str()),
+                v.get("mimeType").and_then(|v| v.as_str())
+            ))
+            .collect::<Vec<_>>()
+    );
+
+    for source in sources {
+        let url: Url = source
+            .get("publicUrl")
+            .and_then(|v| v.as_str())
+            .expect("Expect publicUrl to be a string")
+            .parse()
+            .expect("Public URL is not a valid url");
+
+        let path = services::image::download_file(&url).await;
+        let bytes = tokio::fs::read(&path)
+            .await
+            .expect("Failed to read downloaded file");
+
+        match source.get("format").and_then(|v| v.as_str()) {
+            Some("avif") => assert_eq!(b"ftypavif", &bytes[4..12]),
+            _ => assert_eq!(
+                (400, 400),
+                image::load_from_memory(&bytes)
+                    .map(|image| (image.width(), image.height()))
+                    .expect("Failed to parse image")
+            ),
+        }
+
+        tokio::fs::remove_file(&path)
+            .await
+            .expect("Failed to remove downloaded file");
+    }
+
+    let variant_formats = json
+        .get("variants")
+        .and_then(|v| v.as_array())
+        .expect("Expected variants to be an array")
+        .iter()
+        .map(|v| v.get("format").and_then(|v| v.as_str()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        vec![Some("avif"), Some("jpeg"), Some("webp")],
+        variant_formats
+    );
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_transparent_image_should_keep_png(ctx: &mut TestApp) {
+    ctx.create_api_key("create_transparent_image_should_keep_png", false)
+        .await;
+
+    tokio::fs::create_dir("tests/.output").await.ok();
+    let file_path = "tests/.output/create_transparent_image_should_keep_png.png";
+    image::RgbaImage::from_fn(200, 100, |x, _| image::Rgba([255, 0, 0, x as u8]))
+        .save(file_path)
+        .expect("Failed to write transparent image");
+
+    let response = create_image(
+        ctx,
+        file_path,
+        "create_transparent_image_should_keep_png.png",
+        mime::IMAGE_PNG.as_ref(),
+        Some("Transparent image"),
+    )
+    .await;
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    let public_url: Url = json
+        .get("publicUrl")
+        .and_then(|v| v.as_str())
+        .expect("Expect publicUrl to be a string")
+        .parse()
+        .expect("Public URL is not a valid url");
+
+    let path = services::image::download_file(&public_url).await;
+    let parsed = image::open(&path).expect("Failed to open/parse root image");
+    assert!(parsed.color().has_alpha());
+    assert_eq!(0, parsed.to_rgba8().get_pixel(0, 0)[3]);
+
+    tokio::fs::remove_file(&path)
+        .await
+        .expect("Failed to remove downloaded file");
+    tokio::fs::remove_file(file_path)
+        .await
+        .expect("Failed to remove generated file");
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_image_with_unknown_format_should_return_flinv_error(ctx: &mut TestApp) {
+    ctx.create_api_key(
+        "create_image_with_unknown_format_should_return_flinv_error",
+        false,
+    )
+    .await;
+
+    let response = upload_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "create_image_with_unknown_format_should_return_flinv_error.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        "?formats=webp,heic",
+    )
+    .await;
+
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    assert_eq!(Some(&Value::String("FLINV".to_string())), json.get("code"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_image_should_preserve_aspect_ratio_by_default(ctx: &mut TestApp) {
+    ctx.create_api_key(
+        "create_image_should_preserve_aspect_ratio_by_default",
+        false,
+    )
+    .await;
+
+    let response = create_image(
+        ctx,
+        "tests/fixtures/img/landscape_2048x1360.jpg",
+        "create_image_should_preserve_aspect_ratio_by_default.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        Some("Example image"),
+    )
+    .await;
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    assert_eq!(Some(&json!(1626)), json.get("width"));
+    assert_eq!(Some(&json!(1080)), json.get("height"));
+    assert_eq!(Some(&json!(2048)), json.get("originalWidth"));
+    assert_eq!(Some(&json!(1360)), json.get("originalHeight"));
+    assert_eq!(Some(&Value::Null), json.get("focalPoint"));
+    assert_eq!(
+        Some(&json!(54)),
+        json.get("lazyImage").and_then(|v| v.get("width"))
+    );
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_image_with_fill_should_stretch_to_box(ctx: &mut TestApp) {
+    ctx.create_api_key("create_image_with_fill_should_stretch_to_box", false)
+        .await;
+
+    let response = upload_image(
+        ctx,
+        "tests/fixtures/img/landscape_2048x1360.jpg",
+        "create_image_with_fill_should_stretch_to_box.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        "?alt=Example&fit=fill",
+    )
+    .await;
+    let response = wait_for_image(ctx, response).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    assert_eq!(Some(&json!(1920)), json.get("width"));
+    assert_eq!(Some(&json!(1080)), json.get("height"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_image_should_crop_around_focal_point(ctx: &mut TestApp) {
+    ctx.create_api_key("create_image_should_crop_around_focal_point", false)
+        .await;
+
+    put_image_profile(ctx, "square", &json!({ "width": 50, "height": 50 })).await;
+
+    // Red on the left half, blue on the right one
+    tokio::fs::create_dir("tests/.output").await.ok();
+    let file_path = "tests/.output/create_image_should_crop_around_focal_point.png";
+    image::RgbImage::from_fn(400, 100, |x, _| match x < 200 {
+        true => image::Rgb([255, 0, 0]),
+        false => image::Rgb([0, 0, 255]),
+    })
+    .save(file_path)
+    .expect("Failed to write image");
+
+    for (focal_x, expected_red) in [(0.0, true), (1.0, false)] {
+        let response = upload_image(
+            ctx,
+            file_path,
+            "create_image_should_crop_around_focal_point.png",
+            mime::IMAGE_PNG.as_ref(),
+            &format!("?alt=Example&fit=cover&focalX={focal_x}&focalY=0.5"),
+        )
+        .await;
+        let response = wait_for_image(ctx, response).await;
+        assert_eq!(StatusCode::OK, response.status());
+
+        let json = response
+            .json::<Value>()
+            .await
+            .expect("Cannot parse json body");
+
+        assert_eq!(
+            Some(&json!({ "x": focal_x, "y": 0.5 })),
+            json.get("focalPoint")
+        );
+
+        let url: Url = json
+            .get("variants")
+            .and_then(|v| v.as_array())
+            .and_then(|variants| variants.first())
+            .and_then(|variant| variant.get("publicUrl"))
+            .and_then(|v| v.as_str())
+            .expect("Expect the variant publicUrl to be a string")
+            .parse()
+            .expect("Public URL is not a valid url");
+
+        let path = services::image::download_file(&url).await;
+        let parsed = image::open(&path).expect("Failed to open/parse variant image");
+        let pixel = parsed.to_rgb8().get_pixel(25, 25).0;
+        assert_eq!(expected_red, pixel[0] > pixel[2]);
+        tokio::fs::remove_file(&path)
+            .await
+            .expect("Failed to remove downloaded file");
+    }
+
+    tokio::fs::remove_file(file_path)
+        .await
+        .expect("Failed to remove generated file");
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_image_with_invalid_focal_point_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("create_image_with_invalid_focal_point_should_fail", false)
+        .await;
+
+    for (query, code) in [
+        ("?focalX=1.5&focalY=0.5", "FLINV"),
+        ("?focalX=0.5", "FLMIS"),
+    ] {
+        let response = upload_image(
+            ctx,
+            "tests/fixtures/img/gray_400x400.jpg",
+            "create_image_with_invalid_focal_point_should_fail.jpg",
+            mime::IMAGE_JPEG.as_ref(),
+            query,
+        )
+        .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+        let json = response
+            .json::<Value>()
+            .await
+            .expect("Cannot parse json body");
+
+        assert_eq!(Some(&Value::String(code.to_string())), json.get("code"));
+    }
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_image_should_compute_blurhash(ctx: &mut TestApp) {
+    ctx.create_api_key("create_image_should_compute_blurhash", false)
+        .await;
+
+    let response = create_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "create_image_should_compute_blurhash.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        Some("Example image"),
+    )
+    .await;
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    let hash = json
+        .get("blurhash")
+        .and_then(|v| v.as_str())
+        .expect("Expected a blurhash");
+
+    // 4x3 components
+    assert_eq!(28, hash.len());
+
+    let pixels = blurhash::decode(hash, 32, 18, 1.0).expect("Blurhash should be decodable");
+    assert_eq!(32 * 18 * 4, pixels.len());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_image_should_apply_and_strip_exif_orientation(ctx: &mut TestApp) {
+    ctx.create_api_key(
+        "create_image_should_apply_and_strip_exif_orientation",
+        false,
+    )
+    .await;
+
+    // 60x30 image, red on the left two thirds and blue on the right, to be
+    // rotated clockwise. It also holds GPS tags.
+    let response = create_image(
+        ctx,
+        "tests/fixtures/img/exif_rotated_60x30.jpg",
+        "create_image_should_apply_and_strip_exif_orientation.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        Some("Example image"),
+    )
+    .await;
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    assert_eq!(Some(&json!(30)), json.get("originalWidth"));
+    assert_eq!(Some(&json!(60)), json.get("originalHeight"));
+    assert_eq!(Some(&json!("image/jpeg")), json.get("originalMimeType"));
+    assert_eq!(Some(&json!(790)), json.get("originalSize"));
+    assert_eq!(Some(&json!(6)), json.get("orientation"));
+
+    let dominant_color = json
+        .get("dominantColor")
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.strip_prefix('#'))
+        .and_then(|v| u32::from_str_radix(v, 16).ok())
+        .expect("Expected dominantColor to be a hex color");
+    let (red, blue) = (dominant_color >> 16, dominant_color & 0xff);
+    assert!(red > 150 && blue < 80);
+
+    let (image_url, _) = assert_image_output(&json);
+    let file_path = services::image::download_file(&image_url).await;
+
+    let bytes = tokio::fs::read(&file_path)
+        .await
+        .expect("Failed to read generated file");
+    assert!(exif::Reader::new()
+        .read_from_container(&mut std::io::Cursor::new(&bytes))
+        .is_err());
+
+    let image = image::load_from_memory(&bytes)
+        .expect("Failed to parse generated file")
+        .to_rgb8();
+    assert_eq!((30, 60), image.dimensions());
+    // The left of the upload is now on top
+    assert!(image.get_pixel(15, 5)[0] > 150);
+    assert!(image.get_pixel(15, 55)[2] > 150);
+
+    tokio::fs::remove_file(file_path)
+        .await
+        .expect("Failed to remove generated file");
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_image_over_max_size_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("create_image_over_max_size_should_fail", false)
+        .await;
+
+    // Test uploads are limited to 1MB, rejected before decoding
+    tokio::fs::create_dir("tests/.output").await.ok();
+    let file_path = "tests/.output/create_image_over_max_size_should_fail.jpg";
+    tokio::fs::write(file_path, vec![0xff; 1_500_000])
+        .await
+        .expect("Cannot write test file");
+
+    let response = upload_image(
+        ctx,
+        file_path,
+        "create_image_over_max_size_should_fail.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        "",
+    )
+    .await;
+
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    assert_eq!(Some(&Value::String("FTBIG".to_string())), json.get("code"));
+
+    tokio::fs::remove_file(file_path)
+        .await
+        .expect("Failed to remove generated file");
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_image_over_max_dimensions_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("create_image_over_max_dimensions_should_fail", false)
+        .await;
+
+    // Test uploads are limited to 4096x4096
+    tokio::fs::create_dir("tests/.output").await.ok();
+    let file_path = "tests/.output/create_image_over_max_dimensions_should_fail.png";
+    image::GrayImage::new(5000, 10)
+        .save(file_path)
+        .expect("Cannot write test file");
+
+    let response = upload_image(
+        ctx,
+        file_path,
+        "create_image_over_max_dimensions_should_fail.png",
+        mime::IMAGE_PNG.as_ref(),
+        "",
+    )
+    .await;
+
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    assert_eq!(Some(&Value::String("IMDTL".to_string())), json.get("code"));
+
+    tokio::fs::remove_file(file_path)
+        .await
+        .expect("Failed to remove generated file");
+}
+
+/// Main renditions of images stored as uploaded are not JPEG, as expected by
+/// `assert_image_output`
+fn json_url(json: &Value, pointer: &str) -> Url {
+    json.pointer(pointer)
+        .and_then(|v| v.as_str())
+        .expect("Expect public URL to be a string")
+        .parse()
+        .expect("Public URL is not a valid url")
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_svg_image_should_be_sanitized(ctx: &mut TestApp) {
+    ctx.create_api_key("create_svg_image_should_be_sanitized", false)
+        .await;
+
+    tokio::fs::create_dir("tests/.output").await.ok();
+    let file_path = "tests/.output/create_svg_image_should_be_sanitized.svg";
+    tokio::fs::write(
+        file_path,
+        r#"<?xml version="1.0"?>
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="200" height="100" onload="alert(1)">
+  <script>alert(document.cookie)</script>
+  <rect width="200" height="100" fill="red"/>
+  <image xlink:href="https://example.com/tracker.png" width="10" height="10"/>
+  <a href="javascript:alert(2)"><circle cx="50" cy="50" r="10" fill="blue"/></a>
+</svg>"#,
+    )
+    .await
+    .expect("Cannot write test file");
+
+    let response = create_image(
+        ctx,
+        file_path,
+        "create_svg_image_should_be_sanitized.svg",
+        mime::IMAGE_SVG.as_ref(),
+        Some("Vector image"),
+    )
+    .await;
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    assert_eq!(Some(&json!(200)), json.get("originalWidth"));
+    assert_eq!(Some(&json!(100)), json.get("originalHeight"));
+    assert_eq!(Some(&json!("image/svg+xml")), json.get("originalMimeType"));
+    assert_eq!(Some(&json!("svg")), json.pointer("/sources/0/format"));
+
+    let image_url = json_url(&json, "/publicUrl");
+    let lazy_image_url = json_url(&json, "/lazyImage/publicUrl");
+    assert!(image_url.path().ends_with(".svg"));
+
+    let svg_path = services::image::download_file(&image_url).await;
+    let svg = tokio::fs::read_to_string(&svg_path)
+        .await
+        .expect("Failed to read generated file");
+    assert!(svg.contains("<rect"));
+    for forbidden in ["script", "onload", "example.com", "javascript:"] {
+        assert!(!svg.contains(forbidden), "SVG still contains {forbidden}");
+    }
+
+    // Lazy images are rasterized
+    let lazy_path = services::image::download_file(&lazy_image_url).await;
+    let lazy = image::open(&lazy_path).expect("Failed to open/parse lazy image");
+    assert_eq!(2, lazy.width() / lazy.height());
+
+    for path in [svg_path.as_str(), lazy_path.as_str(), file_path] {
+        tokio::fs::remove_file(path)
+            .await
+            .expect("Failed to remove file");
+    }
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_animated_gif_image_should_keep_frames(ctx: &mut TestApp) {
+    ctx.create_api_key("create_animated_gif_image_should_keep_frames", false)
+        .await;
+
+    tokio::fs::create_dir("tests/.output").await.ok();
+    let file_path = "tests/.output/create_animated_gif_image_should_keep_frames.gif";
+    {
+        let file = std::fs::File::create(file_path).expect("Cannot create test file");
+        let mut encoder = image::codecs::gif::GifEncoder::new(file);
+        encoder
+            .encode_frames([255, 0].map(|red| {
+                image::Frame::new(image::RgbaImage::from_pixel(
+                    40,
+                    20,
+                    image::Rgba([red, 0, 255 - red, 255]),
+                ))
+            }))
+            .expect("Cannot encode test file");
+    }
+
+    let response = create_image(
+        ctx,
+        file_path,
+        "create_animated_gif_image_should_keep_frames.gif",
+        mime::IMAGE_GIF.as_ref(),
+        None,
+    )
+    .await;
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    assert_eq!(Some(&json!("image/gif")), json.get("originalMimeType"));
+
+    let image_url = json_url(&json, "/publicUrl");
+    assert!(image_url.path().ends_with(".gif"));
+
+    let gif_path = services::image::download_file(&image_url).await;
+    let decoder = image::codecs::gif::GifDecoder::new(
+        std::fs::File::open(&gif_path).expect("Failed to open generated file"),
+    )
+    .expect("Failed to parse generated file");
+    assert_eq!(2, image::AnimationDecoder::into_frames(decoder).count());
+
+    for path in [gif_path.as_str(), file_path] {
+        tokio::fs::remove_file(path)
+            .await
+            .expect("Failed to remove file");
+    }
+}
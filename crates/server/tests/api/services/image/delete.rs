@@ -31,3 +31,50 @@ async fn delete_image_should_work(ctx: &mut TestApp) {
 
     assert_eq!(StatusCode::OK, response.status());
 }
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn deleted_image_should_be_restorable(ctx: &mut TestApp) {
+    ctx.create_api_key("tests", false).await;
+
+    let response = create_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "restore_image.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        Some("Restore"),
+    )
+    .await;
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Expected response body to be valid JSON");
+
+    let id = json
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+
+    let response = ctx.delete(format!("/image/{}", id)).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let images = ctx
+        .get("/image")
+        .await
+        .json::<Vec<Value>>()
+        .await
+        .expect("Expected response body to be valid JSON");
+    assert!(images.is_empty());
+
+    let response = ctx.post(format!("/image/{}/restore", id), "").await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let images = ctx
+        .get("/image")
+        .await
+        .json::<Vec<Value>>()
+        .await
+        .expect("Expected response body to be valid JSON");
+    assert_eq!(1, images.len());
+}
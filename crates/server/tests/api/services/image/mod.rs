@@ -3,9 +3,13 @@ use serde_json::Value;
 use std::{collections::HashSet, path::Path};
 use url::Url;
 
-mod create;
+mod cleanup;
+pub mod create;
 mod delete;
 mod read;
+mod reprocess;
+mod transform;
+mod update;
 
 pub(crate) fn assert_image_output(json: &Value) -> (Url, Url) {
     let root_image = json
@@ -16,14 +20,44 @@ pub(crate) fn assert_image_output(json: &Value) -> (Url, Url) {
         HashSet::from([
             "id",
             "publicUrl",
+            "status",
+            "width",
+            "height",
+            "originalWidth",
+            "originalHeight",
+            "originalMimeType",
+            "originalSize",
+            "orientation",
+            "dominantColor",
+            "focalPoint",
+            "blurhash",
             "lazyImage",
+            "sources",
+            "variants",
+            "folderId",
             "alt",
+            "tags",
+            "metadata",
             "createdAt",
             "updatedAt"
         ]),
         root_image.keys().map(|v| v.as_str()).collect(),
     );
     assert!(root_image.get("alt").and_then(|v| v.as_str()).is_some());
+    assert_eq!(Some(&Value::from("ready")), root_image.get("status"));
+    assert!(root_image
+        .get("variants")
+        .and_then(|v| v.as_array())
+        .is_some());
+    // The main format is listed last, as the fallback of a <picture>
+    assert_eq!(
+        root_image.get("publicUrl"),
+        root_image
+            .get("sources")
+            .and_then(|v| v.as_array())
+            .and_then(|sources| sources.last())
+            .and_then(|source| source.get("publicUrl"))
+    );
     assert!(root_image.get("id").and_then(|v| v.as_i64()).is_some());
     assert!(root_image
         .get("createdAt")
@@ -43,7 +77,7 @@ pub(crate) fn assert_image_output(json: &Value) -> (Url, Url) {
 
     assert!(root_image_url
         .path_segments()
-        .and_then(|v| v.last())
+        .and_then(|mut v| v.next_back())
         .expect("Public URL should have a path")
         .ends_with(".jpeg"));
 
@@ -54,7 +88,15 @@ pub(crate) fn assert_image_output(json: &Value) -> (Url, Url) {
         .expect("Expected lazyImage to be a json object");
 
     assert_eq!(
-        HashSet::from(["id", "publicUrl", "alt", "createdAt", "updatedAt"]),
+        HashSet::from([
+            "id",
+            "publicUrl",
+            "width",
+            "height",
+            "alt",
+            "createdAt",
+            "updatedAt"
+        ]),
         lazy_image.keys().map(|v| v.as_str()).collect(),
     );
     assert!(lazy_image.get("alt").and_then(|v| v.as_str()).is_some());
@@ -77,7 +119,7 @@ pub(crate) fn assert_image_output(json: &Value) -> (Url, Url) {
 
     assert!(lazy_image_url
         .path_segments()
-        .and_then(|v| v.last())
+        .and_then(|mut v| v.next_back())
         .expect("Public URL should have a path")
         .ends_with(".jpeg"));
 
@@ -89,7 +131,7 @@ pub(crate) async fn download_file(url: &Url) -> String {
 
     let filename = url
         .path_segments()
-        .and_then(|v| v.last())
+        .and_then(|mut v| v.next_back())
         .expect("Expected a filename in url");
     let response = reqwest::get(url.to_string())
         .await
@@ -0,0 +1,135 @@
+use crate::{
+    services::{
+        image::create::{create_image, wait_for_image, wait_for_image_id},
+        image_profile::update::put_image_profile,
+    },
+    test_app::TestApp,
+};
+use chrono::{Duration, Utc};
+use reqwest::StatusCode;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set};
+use serde_json::{json, Value};
+use test_context::test_context;
+
+async fn create_gray_image(ctx: &TestApp) -> i64 {
+    create_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "reprocess_image.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        Some("Reprocess"),
+    )
+    .await
+    .json::<Value>()
+    .await
+    .ok()
+    .and_then(|json| json.get("id").and_then(|v| v.as_i64()))
+    .expect("Expected ID")
+}
+
+fn variant_names(json: &Value) -> Vec<&str> {
+    json.get("variants")
+        .and_then(|v| v.as_array())
+        .expect("Expected variants")
+        .iter()
+        .filter_map(|variant| variant.get("name").and_then(|v| v.as_str()))
+        .collect()
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn reprocess_image_should_generate_new_profile_variants(ctx: &mut TestApp) {
+    ctx.create_api_key("reprocess_image", false).await;
+
+    let id = create_gray_image(ctx).await;
+    put_image_profile(ctx, "small", &json!({ "width": 100, "height": 100 })).await;
+
+    let json = ctx
+        .get(format!("/image/{id}"))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    assert!(variant_names(&json).is_empty());
+
+    let response = ctx.post(format!("/image/{id}/reprocess"), json!({})).await;
+    let json = wait_for_image(ctx, response)
+        .await
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    assert_eq!(Some(&json!("ready")), json.get("status"));
+    assert_eq!(vec!["small"], variant_names(&json));
+    assert_eq!(Some(&json!("Reprocess")), json.get("alt"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn reprocess_image_with_pending_job_should_return_conflict(ctx: &mut TestApp) {
+    ctx.create_api_key("reprocess_image", false).await;
+
+    let id = create_gray_image(ctx).await;
+
+    // Not due yet, so that no worker completes it during the test
+    entity::image_job::ActiveModel {
+        image_id: Set(id as i32),
+        options: Set(json!({
+            "id": "pending",
+            "filename": "reprocess_image.jpg",
+            "fit": "contain",
+            "alternateFormats": []
+        })),
+        run_at: Set(Utc::now() + Duration::hours(1)),
+        ..Default::default()
+    }
+    .insert(ctx.database_connection())
+    .await
+    .expect("Failed to insert image job");
+
+    let response = ctx.post(format!("/image/{id}/reprocess"), json!({})).await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    assert_eq!(Some(&Value::String("IMPRG".to_string())), json.get("code"));
+
+    // Images being processed are skipped
+    let response = ctx.post("/image/reprocess", json!({})).await;
+    assert_eq!(StatusCode::ACCEPTED, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    assert_eq!(Some(&json!(0)), json.get("queued"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn reprocess_images_should_queue_every_image_of_the_namespace(ctx: &mut TestApp) {
+    ctx.create_api_key("reprocess_images", false).await;
+
+    let first = create_gray_image(ctx).await;
+    let second = create_gray_image(ctx).await;
+
+    let response = ctx.post("/image/reprocess", json!({})).await;
+    assert_eq!(StatusCode::ACCEPTED, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    assert_eq!(Some(&json!(2)), json.get("queued"));
+
+    for id in [first, second] {
+        let json = wait_for_image_id(ctx, id)
+            .await
+            .json::<Value>()
+            .await
+            .expect("Cannot parse json body");
+        assert_eq!(Some(&json!("ready")), json.get("status"));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{
+    services::{
+        image::create::create_image, image_transform_size::update::put_image_transform_size,
+    },
+    test_app::TestApp,
+};
+use reqwest::{header::LOCATION, StatusCode};
+use sea_orm::{EntityTrait, PaginatorTrait};
+use serde_json::Value;
+use test_context::test_context;
+
+async fn create_gray_image(ctx: &TestApp) -> i64 {
+    create_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "transform_image.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        Some("Transform"),
+    )
+    .await
+    .json::<Value>()
+    .await
+    .ok()
+    .and_then(|json| json.get("id").and_then(|v| v.as_i64()))
+    .expect("Expected ID")
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn transform_image_should_redirect_to_cached_object(ctx: &mut TestApp) {
+    ctx.create_api_key("tests", false).await;
+    put_image_transform_size(ctx, 200, 100).await;
+
+    let id = create_gray_image(ctx).await;
+    let uri = format!("/image/{id}/transform?width=200&height=100&format=webp");
+
+    let response = ctx.get(&uri).await;
+    assert_eq!(StatusCode::FOUND, response.status());
+    let location = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string)
+        .expect("Expected a location header");
+    assert!(location.ends_with("__t_200x100_cover_q75.webp"));
+
+    let bytes = reqwest::get(&location)
+        .await
+        .expect("Failed to download transformed image")
+        .bytes()
+        .await
+        .expect("Failed to read transformed image");
+    let transformed = image::load_from_memory(&bytes).expect("Failed to parse transformed image");
+    assert_eq!((200, 100), (transformed.width(), transformed.height()));
+
+    // The second request is served from the cache
+    let response = ctx.get(&uri).await;
+    assert_eq!(StatusCode::FOUND, response.status());
+    assert_eq!(
+        Some(location.as_str()),
+        response
+            .headers()
+            .get(LOCATION)
+            .and_then(|v| v.to_str().ok())
+    );
+
+    let cached = entity::image_transform::Entity::find()
+        .count(ctx.database_connection())
+        .await
+        .expect("Failed to count cached transforms");
+    assert_eq!(1, cached);
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn transform_image_to_unknown_size_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("tests", false).await;
+    put_image_transform_size(ctx, 200, 100).await;
+
+    let id = create_gray_image(ctx).await;
+
+    let response = ctx
+        .get(format!("/image/{id}/transform?width=201&height=100"))
+        .await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    assert_eq!(Some(&Value::String("IMSNA".to_string())), json.get("code"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn transform_trashed_image_should_return_not_found(ctx: &mut TestApp) {
+    ctx.create_api_key("tests", false).await;
+    put_image_transform_size(ctx, 200, 100).await;
+
+    let id = create_gray_image(ctx).await;
+    ctx.delete(format!("/image/{id}")).await;
+
+    let response = ctx
+        .get(format!("/image/{id}/transform?width=200&height=100"))
+        .await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn transform_image_should_use_the_original(ctx: &mut TestApp) {
+    ctx.create_api_key("tests", false).await;
+    put_image_transform_size(ctx, 2048, 1360).await;
+
+    let image = create_image(
+        ctx,
+        "tests/fixtures/img/landscape_2048x1360.jpg",
+        "transform_original.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        None,
+    )
+    .await
+    .json::<Value>()
+    .await
+    .expect("Cannot parse image output");
+    let id = image
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    // The main image is downscaled
+    let width = image
+        .get("width")
+        .and_then(|v| v.as_i64())
+        .expect("Expected width");
+    assert!(width < 2048);
+
+    let response = ctx
+        .get(format!(
+            "/image/{id}/transform?width=2048&height=1360&fit=contain"
+        ))
+        .await;
+    assert_eq!(StatusCode::FOUND, response.status());
+    let location = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string)
+        .expect("Expected a location header");
+
+    let bytes = reqwest::get(&location)
+        .await
+        .expect("Failed to download transformed image")
+        .bytes()
+        .await
+        .expect("Failed to read transformed image");
+    let transformed = image::load_from_memory(&bytes).expect("Failed to parse transformed image");
+    assert_eq!((2048, 1360), (transformed.width(), transformed.height()));
+}
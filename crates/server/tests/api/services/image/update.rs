@@ -0,0 +1,196 @@
+use crate::{
+    services::image::create::{create_image, post_image_file, wait_for_image_id},
+    test_app::TestApp,
+};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use test_context::test_context;
+
+async fn create_gray_image(ctx: &TestApp) -> Value {
+    create_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "update_image.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        Some("Typo"),
+    )
+    .await
+    .json::<Value>()
+    .await
+    .expect("Cannot parse json body")
+}
+
+fn image_id(json: &Value) -> i64 {
+    json.get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID")
+}
+
+async fn list_image_ids(ctx: &TestApp, uri: &str) -> Vec<i64> {
+    ctx.get(uri)
+        .await
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|v| v.as_array().cloned())
+        .expect("Expected an array of images")
+        .iter()
+        .map(image_id)
+        .collect()
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn update_image_should_replace_alt_tags_and_metadata(ctx: &mut TestApp) {
+    ctx.create_api_key("update_image", false).await;
+
+    let id = image_id(&create_gray_image(ctx).await);
+
+    let response = ctx
+        .put(
+            format!("/image/{id}"),
+            Some(json!({
+                "alt": "Fixed",
+                "tags": ["hero", "home"],
+                "metadata": { "credits": "Lyonkit" }
+            })),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    assert_eq!(Some(&json!("Fixed")), json.get("alt"));
+    assert_eq!(Some(&json!(["hero", "home"])), json.get("tags"));
+    assert_eq!(Some(&json!({ "credits": "Lyonkit" })), json.get("metadata"));
+    assert_eq!(
+        Some(&json!("Fixed")),
+        json.get("lazyImage").and_then(|v| v.get("alt"))
+    );
+
+    assert_eq!(vec![id], list_image_ids(ctx, "/image?tag=hero").await);
+    assert!(list_image_ids(ctx, "/image?tag=blog").await.is_empty());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn patch_image_should_only_update_given_fields(ctx: &mut TestApp) {
+    ctx.create_api_key("patch_image", false).await;
+
+    let id = image_id(&create_gray_image(ctx).await);
+
+    let response = ctx
+        .patch(format!("/image/{id}"), Some(json!({ "tags": ["hero"] })))
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    assert_eq!(Some(&json!("Typo")), json.get("alt"));
+    assert_eq!(Some(&json!(["hero"])), json.get("tags"));
+
+    let response = ctx
+        .patch(format!("/image/{id}"), Some(json!({ "alt": null })))
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    assert_eq!(Some(&Value::Null), json.get("alt"));
+    assert_eq!(Some(&json!(["hero"])), json.get("tags"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn patch_image_with_invalid_body_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("patch_image", false).await;
+
+    let id = image_id(&create_gray_image(ctx).await);
+
+    for (body, code) in [
+        (json!({}), "PTHOF"),
+        (json!({ "tags": null }), "PTHNN"),
+        (json!({ "metadata": null }), "PTHNN"),
+    ] {
+        let response = ctx.patch(format!("/image/{id}"), Some(body)).await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+        let json = response
+            .json::<Value>()
+            .await
+            .expect("Cannot parse json body");
+        assert_eq!(Some(&Value::String(code.to_string())), json.get("code"));
+    }
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn replace_image_should_keep_id_and_regenerate_renditions(ctx: &mut TestApp) {
+    ctx.create_api_key("replace_image", false).await;
+
+    let created = create_gray_image(ctx).await;
+    let id = image_id(&created);
+    let previous_url = created
+        .get("publicUrl")
+        .and_then(|v| v.as_str())
+        .expect("Expected a public url")
+        .to_string();
+
+    let response = post_image_file(
+        ctx,
+        format!("/image/{id}/replace"),
+        "tests/fixtures/img/landscape_2048x1360.jpg",
+        "replace_image.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+    )
+    .await;
+    assert_eq!(StatusCode::ACCEPTED, response.status());
+
+    // Previous renditions are served until the new ones are generated
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+    assert_eq!(Some(&json!("processing")), json.get("status"));
+    assert_eq!(Some(&json!(previous_url)), json.get("publicUrl"));
+
+    let response = wait_for_image_id(ctx, id).await;
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot parse json body");
+
+    assert_eq!(id, image_id(&json));
+    assert_eq!(Some(&json!("Typo")), json.get("alt"));
+    assert_eq!(Some(&json!(2048)), json.get("originalWidth"));
+    assert_eq!(Some(&json!(1626)), json.get("width"));
+    assert_ne!(Some(&json!(previous_url)), json.get("publicUrl"));
+
+    let previous = reqwest::get(&previous_url)
+        .await
+        .expect("Failed to request previous image");
+    assert_eq!(StatusCode::NOT_FOUND, previous.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn replace_unknown_image_should_return_not_found(ctx: &mut TestApp) {
+    ctx.create_api_key("replace_image", false).await;
+
+    let response = post_image_file(
+        ctx,
+        "/image/1000/replace".to_string(),
+        "tests/fixtures/img/gray_400x400.jpg",
+        "replace_image.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+    )
+    .await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
@@ -0,0 +1,131 @@
+use crate::test_app::TestApp;
+use reqwest::StatusCode;
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use test_context::test_context;
+
+pub async fn put_image_profile(app: &TestApp, name: &str, body: &Value) -> Map<String, Value> {
+    let response = app.put(format!("/image-profile/{name}"), body).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot deserialize body");
+    let json = json.as_object().expect("Expected response to be an object");
+
+    assert_eq!(
+        HashSet::from([
+            "id",
+            "name",
+            "width",
+            "height",
+            "fit",
+            "format",
+            "quality",
+            "createdAt",
+            "updatedAt"
+        ]),
+        json.keys().map(|v| v.as_str()).collect(),
+    );
+    assert_eq!(Some(name), json.get("name").and_then(|v| v.as_str()));
+    assert_eq!(body.get("width"), json.get("width"));
+    assert_eq!(body.get("height"), json.get("height"));
+
+    json.to_owned()
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn put_image_profile_should_create_then_replace(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let created = put_image_profile(ctx, "thumb", &json!({ "width": 320, "height": 180 })).await;
+    assert_eq!(Some("cover"), created.get("fit").and_then(|v| v.as_str()));
+    assert_eq!(Some("jpeg"), created.get("format").and_then(|v| v.as_str()));
+    assert_eq!(Some(75), created.get("quality").and_then(|v| v.as_i64()));
+
+    let replaced = put_image_profile(
+        ctx,
+        "thumb",
+        &json!({ "width": 640, "height": 360, "fit": "contain", "quality": 90 }),
+    )
+    .await;
+    assert_eq!(created.get("id"), replaced.get("id"));
+    assert_eq!(
+        Some("contain"),
+        replaced.get("fit").and_then(|v| v.as_str())
+    );
+
+    let profiles = ctx
+        .get("/image-profile")
+        .await
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|v| v.as_array().cloned())
+        .expect("Expected an array of profiles");
+
+    assert_eq!(1, profiles.len());
+    assert_eq!(Some(&json!(640)), profiles[0].get("width"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn put_invalid_image_profile_should_return_flinv_error(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    for (name, body) in [
+        ("Thumb", json!({ "width": 320, "height": 180 })),
+        ("thumb", json!({ "width": 0, "height": 180 })),
+        (
+            "thumb",
+            json!({ "width": 320, "height": 180, "quality": 101 }),
+        ),
+        (
+            "thumb",
+            json!({ "width": 320, "height": 180, "format": "svg" }),
+        ),
+    ] {
+        let response = ctx.put(format!("/image-profile/{name}"), &body).await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+        let response_body = response
+            .json::<Value>()
+            .await
+            .ok()
+            .and_then(|j| j.as_object().cloned())
+            .expect("Response body is not valid JSON Object");
+
+        assert_eq!(
+            Some(&Value::String("FLINV".to_string())),
+            response_body.get("code")
+        );
+    }
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn deleted_image_profile_should_not_be_listed(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    put_image_profile(ctx, "thumb", &json!({ "width": 320, "height": 180 })).await;
+
+    let response = ctx.delete("/image-profile/thumb").await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response = ctx.delete("/image-profile/thumb").await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+    let profiles = ctx
+        .get("/image-profile")
+        .await
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|v| v.as_array().cloned())
+        .expect("Expected an array of profiles");
+
+    assert!(profiles.is_empty());
+}
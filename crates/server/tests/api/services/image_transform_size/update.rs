@@ -0,0 +1,97 @@
+use crate::test_app::TestApp;
+use reqwest::StatusCode;
+use serde_json::{json, Map, Value};
+use test_context::test_context;
+
+pub async fn put_image_transform_size(
+    app: &TestApp,
+    width: u32,
+    height: u32,
+) -> Map<String, Value> {
+    let response = app
+        .put(
+            format!("/image-transform-size/{width}x{height}"),
+            None as Option<()>,
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot deserialize body");
+    let json = json.as_object().expect("Expected response to be an object");
+
+    assert_eq!(Some(&json!(width)), json.get("width"));
+    assert_eq!(Some(&json!(height)), json.get("height"));
+
+    json.to_owned()
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn put_image_transform_size_should_be_idempotent(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let created = put_image_transform_size(ctx, 640, 360).await;
+    let again = put_image_transform_size(ctx, 640, 360).await;
+    assert_eq!(created.get("id"), again.get("id"));
+
+    put_image_transform_size(ctx, 320, 180).await;
+
+    let sizes = ctx
+        .get("/image-transform-size")
+        .await
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|v| v.as_array().cloned())
+        .expect("Expected an array of sizes");
+
+    assert_eq!(
+        vec![Some(320), Some(640)],
+        sizes
+            .iter()
+            .map(|size| size.get("width").and_then(|v| v.as_i64()))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn put_invalid_image_transform_size_should_return_flinv_error(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let response = ctx
+        .put("/image-transform-size/0x100", None as Option<()>)
+        .await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Cannot deserialize body");
+    assert_eq!(Some(&Value::String("FLINV".to_string())), json.get("code"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn deleted_image_transform_size_should_not_be_listed(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    put_image_transform_size(ctx, 640, 360).await;
+
+    let response = ctx.delete("/image-transform-size/640x360").await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let sizes = ctx
+        .get("/image-transform-size")
+        .await
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|v| v.as_array().cloned())
+        .expect("Expected an array of sizes");
+
+    assert!(sizes.is_empty());
+}
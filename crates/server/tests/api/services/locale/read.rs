@@ -1,4 +1,5 @@
 use crate::{services::locale::LocaleFixtures, test_app::TestApp};
+use reqwest::StatusCode;
 use serde_json::json;
 use test_context::test_context;
 
@@ -30,3 +31,62 @@ async fn get_locales_should_work(ctx: &mut TestApp) {
         res
     );
 }
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn get_locales_should_honor_if_none_match(ctx: &mut TestApp) {
+    let namespace = "test_locales";
+    ctx.create_api_key(namespace, true).await;
+    ctx.database_connection()
+        .create_locale(namespace, "fr", json!({"key1":"value1"}))
+        .await;
+
+    let response = ctx.get("/locale").await;
+    assert_eq!(StatusCode::OK, response.status());
+    let etag = response
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string)
+        .expect("Expected ETag header");
+
+    let response = ctx
+        .get_with_headers("/locale", &[("If-None-Match", &etag)])
+        .await;
+    assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn get_locales_should_keep_etag_with_several_locales(ctx: &mut TestApp) {
+    let namespace = "test_locales";
+    ctx.create_api_key(namespace, true).await;
+    for lang in ["fr", "en", "de", "es"] {
+        ctx.database_connection()
+            .create_locale(namespace, lang, json!({ "key1": lang }))
+            .await;
+    }
+
+    let response = ctx.get("/locale").await;
+    assert_eq!(StatusCode::OK, response.status());
+    assert!(response.headers().get("last-modified").is_none());
+    let etag = response
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string)
+        .expect("Expected ETag header");
+
+    for _ in 0..5 {
+        let response = ctx.get("/locale").await;
+        assert_eq!(
+            Some(etag.as_str()),
+            response.headers().get("etag").and_then(|v| v.to_str().ok())
+        );
+    }
+
+    let response = ctx
+        .get_with_headers("/locale", &[("If-None-Match", &etag)])
+        .await;
+    assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+}
@@ -0,0 +1,124 @@
+use crate::test_app::TestApp;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use test_context::test_context;
+
+pub async fn create_folder(ctx: &TestApp, name: &str, parent_id: Option<i64>) -> i64 {
+    let response = ctx
+        .post(
+            "/media/folders",
+            json!({ "name": name, "parentId": parent_id }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let folder = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize folder");
+    assert_eq!(Some(&json!(name.trim())), folder.get("name"));
+    assert_eq!(Some(&json!(parent_id)), folder.get("parentId"));
+
+    folder
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected folder ID")
+}
+
+async fn error_code(response: reqwest::Response) -> Option<Value> {
+    response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize error")
+        .get("code")
+        .cloned()
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn create_folder_should_work(ctx: &mut TestApp) {
+    ctx.create_api_key("test_media", false).await;
+
+    let photos = create_folder(ctx, " Photos ", None).await;
+    create_folder(ctx, "2023", Some(photos)).await;
+    // Names are unique per parent only
+    create_folder(ctx, "2023", None).await;
+
+    let response = ctx
+        .post("/media/folders", json!({ "name": "Photos" }))
+        .await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    assert_eq!(Some(json!("FDNTK")), error_code(response).await);
+
+    let response = ctx.post("/media/folders", json!({ "name": "a/b" })).await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response = ctx
+        .post("/media/folders", json!({ "name": "Other", "parentId": 0 }))
+        .await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    ctx.create_api_key("other_namespace", true).await;
+    let folders = ctx
+        .get("/media/folders")
+        .await
+        .json::<Vec<Value>>()
+        .await
+        .expect("Failed to deserialize folders");
+    assert!(folders.is_empty());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn folder_should_not_move_into_its_subfolders(ctx: &mut TestApp) {
+    ctx.create_api_key("test_media", false).await;
+
+    let photos = create_folder(ctx, "Photos", None).await;
+    let year = create_folder(ctx, "2023", Some(photos)).await;
+    let month = create_folder(ctx, "June", Some(year)).await;
+
+    for parent_id in [photos, month] {
+        let response = ctx
+            .put(
+                format!("/media/folders/{photos}"),
+                json!({ "name": "Photos", "parentId": parent_id }),
+            )
+            .await;
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    let response = ctx
+        .put(
+            format!("/media/folders/{month}"),
+            json!({ "name": "June 2023", "parentId": photos }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    let folder = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize folder");
+    assert_eq!(Some(&json!("June 2023")), folder.get("name"));
+    assert_eq!(Some(&json!(photos)), folder.get("parentId"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn only_empty_folders_should_be_deleted(ctx: &mut TestApp) {
+    ctx.create_api_key("test_media", false).await;
+
+    let photos = create_folder(ctx, "Photos", None).await;
+    let year = create_folder(ctx, "2023", Some(photos)).await;
+
+    let response = ctx.delete(format!("/media/folders/{photos}")).await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    assert_eq!(Some(json!("FDNEM")), error_code(response).await);
+
+    let response = ctx.delete(format!("/media/folders/{year}")).await;
+    assert_eq!(StatusCode::OK, response.status());
+    let response = ctx.delete(format!("/media/folders/{photos}")).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response = ctx.delete(format!("/media/folders/{photos}")).await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
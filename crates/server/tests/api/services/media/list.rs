@@ -0,0 +1,193 @@
+use crate::{
+    services::{image::create::create_image, media::folders::create_folder},
+    test_app::TestApp,
+};
+use aws_sdk_s3::types::ByteStream;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use test_context::test_context;
+
+const CONTENT: &str = "Hello world";
+
+async fn create_uploaded_file(ctx: &TestApp) -> i64 {
+    let response = ctx
+        .post(
+            "/file",
+            json!({
+                "file": {
+                    "contentType": "text/plain",
+                    "contentLength": CONTENT.len(),
+                    "fileName": "notes.txt"
+                },
+                "tags": ["notes"],
+                "metadata": {}
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    let file = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize file");
+    let id = file
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let key = file
+        .get("key")
+        .and_then(|v| v.as_str())
+        .expect("Expected key");
+
+    ctx.s3_client()
+        .put_object()
+        .bucket(ctx.settings().storage().s3().buckets().file())
+        .key(key)
+        .content_type("text/plain")
+        .body(ByteStream::from_static(CONTENT.as_bytes()))
+        .send()
+        .await
+        .expect("Failed to upload object");
+    let response = ctx.post(format!("/file/{id}/confirm"), json!({})).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    id
+}
+
+async fn list_media<S: AsRef<str>>(ctx: &TestApp, query: S) -> Vec<(String, i64)> {
+    let response = ctx.get(format!("/media{}", query.as_ref())).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    response
+        .json::<Vec<Value>>()
+        .await
+        .expect("Failed to deserialize media")
+        .iter()
+        .map(|item| {
+            (
+                item.get("type")
+                    .and_then(|v| v.as_str())
+                    .expect("Expected type")
+                    .to_string(),
+                item.get("id")
+                    .and_then(|v| v.as_i64())
+                    .expect("Expected ID"),
+            )
+        })
+        .collect()
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn media_should_be_listed_and_moved_to_folders(ctx: &mut TestApp) {
+    ctx.create_api_key("test_media", false).await;
+
+    let file_id = create_uploaded_file(ctx).await;
+    let image = create_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "media.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        None,
+    )
+    .await
+    .json::<Value>()
+    .await
+    .expect("Failed to deserialize image");
+    let image_id = image
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected image ID");
+
+    let image_item = ("image".to_string(), image_id);
+    let file_item = ("file".to_string(), file_id);
+    assert_eq!(
+        vec![image_item.clone(), file_item.clone()],
+        list_media(ctx, "").await
+    );
+    assert_eq!(vec![file_item.clone()], list_media(ctx, "?type=file").await);
+    assert_eq!(vec![file_item.clone()], list_media(ctx, "?tag=notes").await);
+
+    let folder_id = create_folder(ctx, "Photos", None).await;
+    let response = ctx
+        .post(
+            "/media/move",
+            json!({
+                "items": [{ "type": "image", "id": image_id }],
+                "folderId": folder_id
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    let moved = response
+        .json::<Vec<Value>>()
+        .await
+        .expect("Failed to deserialize media");
+    assert_eq!(1, moved.len());
+    assert_eq!(Some(&json!(folder_id)), moved[0].get("folderId"));
+
+    assert_eq!(
+        vec![image_item.clone()],
+        list_media(ctx, format!("?folder={folder_id}")).await
+    );
+    assert_eq!(vec![file_item], list_media(ctx, "?folder=root").await);
+
+    let image = ctx
+        .get(format!("/image/{image_id}"))
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize image");
+    assert_eq!(Some(&json!(folder_id)), image.get("folderId"));
+
+    // Folders with media are not empty
+    let response = ctx.delete(format!("/media/folders/{folder_id}")).await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+
+    let response = ctx
+        .post(
+            "/media/move",
+            json!({ "items": [{ "type": "image", "id": image_id }] }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(
+        vec![image_item],
+        list_media(ctx, "?type=image&folder=root").await
+    );
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn move_unknown_media_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("test_media", false).await;
+
+    let file_id = create_uploaded_file(ctx).await;
+    let folder_id = create_folder(ctx, "Documents", None).await;
+
+    let response = ctx
+        .post(
+            "/media/move",
+            json!({
+                "items": [{ "type": "file", "id": file_id }, { "type": "image", "id": 0 }],
+                "folderId": folder_id
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+    // Nothing is moved
+    assert_eq!(
+        vec![("file".to_string(), file_id)],
+        list_media(ctx, "?folder=root").await
+    );
+
+    let response = ctx
+        .post(
+            "/media/move",
+            json!({ "items": [{ "type": "file", "id": file_id }], "folderId": 0 }),
+        )
+        .await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response = ctx.get("/media?type=video").await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+}
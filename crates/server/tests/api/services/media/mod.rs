@@ -0,0 +1,3 @@
+mod folders;
+mod list;
+mod references;
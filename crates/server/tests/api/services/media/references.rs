@@ -0,0 +1,245 @@
+use crate::{
+    services::{
+        blok::create::create_blok, image::create::create_image, page::create::create_page,
+        post::create::create_post,
+    },
+    test_app::TestApp,
+};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use test_context::test_context;
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn image_references_should_list_bloks_and_posts(ctx: &mut TestApp) {
+    ctx.create_api_key("test_media", false).await;
+
+    let image = create_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "hero.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        None,
+    )
+    .await
+    .json::<Value>()
+    .await
+    .expect("Failed to deserialize image");
+    let image_id = image
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected image ID");
+    let public_url = image
+        .get("publicUrl")
+        .and_then(|v| v.as_str())
+        .expect("Expected public URL");
+
+    let response = ctx.get(format!("/media/image/{image_id}/references")).await;
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(
+        json!([]),
+        response
+            .json::<Value>()
+            .await
+            .expect("Failed to deserialize references")
+    );
+
+    let page = create_page(
+        ctx,
+        &json!({
+          "path": "/about",
+          "title": "About",
+          "description": "About us"
+        }),
+    )
+    .await;
+    let page_id = page
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let blok = create_blok(
+        ctx,
+        &json!({
+            "pageId": page_id,
+            "componentId": "Hero",
+            "props": { "backgroundImage": public_url }
+        }),
+    )
+    .await;
+    create_blok(
+        ctx,
+        &json!({
+            "pageId": page_id,
+            "componentId": "Text",
+            "props": { "text": "Unrelated" }
+        }),
+    )
+    .await;
+    let post = create_post(
+        ctx,
+        &json!({
+          "title": "Hero",
+          "description": "A post with an image",
+          "slug": "hero",
+          "body": { "blocks": [{ "type": "image", "src": public_url }] }
+        }),
+    )
+    .await;
+
+    let response = ctx.get(format!("/media/image/{image_id}/references")).await;
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(
+        json!([
+            { "kind": "blok", "id": blok.get("id"), "label": "Hero", "pageId": page_id },
+            { "kind": "post", "id": post.get("id"), "label": "Hero", "pageId": null }
+        ]),
+        response
+            .json::<Value>()
+            .await
+            .expect("Failed to deserialize references")
+    );
+
+    let response = ctx.get("/media/image/0/references").await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn used_image_should_only_be_deleted_when_forced(ctx: &mut TestApp) {
+    ctx.create_api_key("test_media", false).await;
+
+    let image_id = create_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "hero.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        None,
+    )
+    .await
+    .json::<Value>()
+    .await
+    .expect("Failed to deserialize image")
+    .get("id")
+    .and_then(|v| v.as_i64())
+    .expect("Expected image ID");
+
+    let page = create_page(
+        ctx,
+        &json!({
+          "path": "/home",
+          "title": "Home",
+          "description": "Home page"
+        }),
+    )
+    .await;
+    let page_id = page
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let blok_id = create_blok(
+        ctx,
+        &json!({
+            "pageId": page_id,
+            "componentId": "Gallery",
+            "props": { "items": [{ "imageId": image_id }] }
+        }),
+    )
+    .await
+    .get("id")
+    .and_then(|v| v.as_i64())
+    .expect("Expected ID");
+
+    let response = ctx.delete(format!("/image/{image_id}")).await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+    let error = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize error");
+    assert_eq!(Some(&json!("MDUSE")), error.get("code"));
+
+    // Bloks no longer using it are not references anymore
+    let response = ctx
+        .patch(
+            format!("/blok/{blok_id}"),
+            json!({ "props": { "items": [] } }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    let response = ctx.get(format!("/media/image/{image_id}/references")).await;
+    assert_eq!(
+        json!([]),
+        response
+            .json::<Value>()
+            .await
+            .expect("Failed to deserialize references")
+    );
+
+    let response = ctx
+        .put(
+            format!("/blok/{blok_id}"),
+            json!({
+                "pageId": page_id,
+                "componentId": "Gallery",
+                "props": { "items": [{ "imageId": image_id }] }
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    let response = ctx.delete(format!("/image/{image_id}")).await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+
+    let response = ctx.delete(format!("/image/{image_id}?force=true")).await;
+    assert_eq!(StatusCode::OK, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn file_used_by_trashed_post_should_be_deleted(ctx: &mut TestApp) {
+    ctx.create_api_key("test_media", false).await;
+
+    let response = ctx
+        .post(
+            "/file",
+            json!({
+                "file": {
+                    "contentType": "application/pdf",
+                    "contentLength": 1500,
+                    "fileName": "menu.pdf"
+                },
+                "tags": [],
+                "metadata": {}
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    let file_id = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize file")
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected file ID");
+
+    let post_id = create_post(
+        ctx,
+        &json!({
+          "title": "Menu",
+          "description": "Our menu",
+          "slug": "menu",
+          "body": { "attachment": { "fileId": file_id } }
+        }),
+    )
+    .await
+    .get("id")
+    .and_then(|v| v.as_i64())
+    .expect("Expected post ID");
+
+    let response = ctx.delete(format!("/file/{file_id}")).await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+
+    // Trashed posts do not use media anymore
+    let response = ctx.delete(format!("/post/{post_id}")).await;
+    assert_eq!(StatusCode::OK, response.status());
+    let response = ctx.delete(format!("/file/{file_id}")).await;
+    assert_eq!(StatusCode::OK, response.status());
+}
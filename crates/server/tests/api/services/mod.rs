@@ -1,8 +1,13 @@
 mod blok;
 mod file;
 mod image;
+mod image_profile;
+mod image_transform_size;
 mod locale;
+mod media;
 mod page;
 mod ping;
 mod post;
 mod quote;
+mod trash;
+mod usage;
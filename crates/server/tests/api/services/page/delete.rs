@@ -1,6 +1,6 @@
 use crate::{services::page::create::create_page, test_app::TestApp};
 use reqwest::StatusCode;
-use serde_json::json;
+use serde_json::{json, Value};
 use test_context::test_context;
 
 #[test_context(TestApp)]
@@ -52,3 +52,74 @@ async fn delete_page_from_other_namespace_should_be_denied(ctx: &mut TestApp) {
     let response = ctx.delete(format!("/page/{}", id)).await;
     assert_eq!(StatusCode::NOT_FOUND, response.status());
 }
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn deleted_page_should_be_restorable(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page = create_page(
+        ctx,
+        &json!({
+            "path": "/path",
+            "title": "My Title",
+            "description": "My Description"
+        }),
+    )
+    .await;
+
+    let id = page
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+
+    let response = ctx.delete(format!("/page/{}", id)).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response = ctx.get("/page/wb/path").await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+    let response = ctx.post(format!("/page/{}/restore", id), json!({})).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response = ctx.get("/page/wb/path").await;
+    assert_eq!(StatusCode::OK, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn restore_page_with_reused_path_should_conflict(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page_body = json!({
+        "path": "/path",
+        "title": "My Title",
+        "description": "My Description"
+    });
+
+    let page = create_page(ctx, &page_body).await;
+
+    let id = page
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+
+    let response = ctx.delete(format!("/page/{}", id)).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    // The path of a trashed page can be reused
+    create_page(ctx, &page_body).await;
+
+    let response = ctx.post(format!("/page/{}/restore", id), json!({})).await;
+    assert_eq!(StatusCode::CONFLICT, response.status());
+
+    let response_body = response
+        .json::<Value>()
+        .await
+        .expect("Response body is not valid JSON");
+
+    assert_eq!(
+        Some(&Value::String("RSTCF".to_string())),
+        response_body.get("code")
+    );
+}
@@ -1,4 +1,5 @@
 pub mod create;
 mod delete;
+mod patch;
 mod read;
 mod update;
@@ -0,0 +1,143 @@
+use crate::{services::page::create::create_page, test_app::TestApp};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use test_context::test_context;
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn empty_patch_should_return_pthof_error(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page = create_page(
+        ctx,
+        &json!({
+          "path": "/about/me",
+          "title": "A propos !",
+          "description": "Qui suis-je ? Telle est la question !"
+        }),
+    )
+    .await;
+    let page_id = page.get("id").expect("Expected ID");
+
+    let response = ctx.patch(format!("/page/{page_id}"), &json!({})).await;
+
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response_body = response
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|j| j.as_object().cloned())
+        .expect("Response body is not valid JSON Object");
+
+    assert_eq!(
+        Some(&Value::String("PTHOF".to_string())),
+        response_body.get("code")
+    );
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn null_patch_on_non_nullable_field_should_return_pthnn_error(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page = create_page(
+        ctx,
+        &json!({
+          "path": "/about/me",
+          "title": "A propos !",
+          "description": "Qui suis-je ? Telle est la question !"
+        }),
+    )
+    .await;
+    let page_id = page.get("id").expect("Expected ID");
+
+    let response = ctx
+        .patch(format!("/page/{page_id}"), &json!({ "title": null }))
+        .await;
+
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response_body = response
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|j| j.as_object().cloned())
+        .expect("Response body is not valid JSON Object");
+
+    assert_eq!(
+        Some(&Value::String("PTHNN".to_string())),
+        response_body.get("code")
+    );
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn patch_should_only_update_given_fields(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page = create_page(
+        ctx,
+        &json!({
+          "path": "/about/me",
+          "title": "A propos !",
+          "description": "Qui suis-je ? Telle est la question !"
+        }),
+    )
+    .await;
+    let page_id = page.get("id").expect("Expected ID");
+
+    let response = ctx
+        .patch(
+            format!("/page/{page_id}"),
+            &json!({
+              "title": "About me",
+              "description": null
+            }),
+        )
+        .await;
+
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response_body = response
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|j| j.as_object().cloned())
+        .expect("Response body is not valid JSON Object");
+
+    assert_eq!(page.get("id"), response_body.get("id"));
+    assert_eq!(page.get("path"), response_body.get("path"));
+    assert_eq!(
+        Some(&Value::String("About me".to_string())),
+        response_body.get("title")
+    );
+    assert_eq!(Some(&Value::Null), response_body.get("description"));
+    assert_eq!(page.get("createdAt"), response_body.get("createdAt"));
+    assert_ne!(page.get("updatedAt"), response_body.get("updatedAt"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn patch_page_from_other_namespace_should_be_denied(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page = create_page(
+        ctx,
+        &json!({
+          "path": "/about/me",
+          "title": "A propos !",
+          "description": "Qui suis-je ? Telle est la question !"
+        }),
+    )
+    .await;
+    let page_id = page.get("id").expect("Expected ID");
+
+    ctx.create_api_key("other_namespace", false).await;
+
+    let response = ctx
+        .patch(format!("/page/{page_id}"), &json!({ "title": "About me" }))
+        .await;
+
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
@@ -195,3 +195,60 @@ async fn get_page_with_bloks_read_only_api_key_should_work(ctx: &mut TestApp) {
             .collect::<Vec<&str>>()
     );
 }
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn get_page_with_bloks_should_honor_if_none_match(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+    let page = create_page(
+        ctx,
+        &json!({
+            "path": "/cached-path",
+            "title": "My Path Title",
+            "description": "My Path Description"
+        }),
+    )
+    .await;
+
+    let response = ctx.get("/page/wb/cached-path").await;
+    assert_eq!(StatusCode::OK, response.status());
+    // Removing a blok would not move the latest update forward
+    assert!(response.headers().get("last-modified").is_none());
+    assert_eq!(
+        Some("no-cache"),
+        response
+            .headers()
+            .get("cache-control")
+            .and_then(|v| v.to_str().ok())
+    );
+    let etag = response
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string)
+        .expect("Expected ETag header");
+
+    let response = ctx
+        .get_with_headers("/page/wb/cached-path", &[("If-None-Match", &etag)])
+        .await;
+    assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+
+    create_blok(
+        ctx,
+        &json!({
+            "pageId": page.get("id"),
+            "componentId": "Hero",
+            "props": {}
+        }),
+    )
+    .await;
+
+    let response = ctx
+        .get_with_headers("/page/wb/cached-path", &[("If-None-Match", &etag)])
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    assert_ne!(
+        Some(etag.as_str()),
+        response.headers().get("etag").and_then(|v| v.to_str().ok())
+    );
+}
@@ -1,5 +1,5 @@
 use crate::{services::page::create::create_page, test_app::TestApp};
-use reqwest::StatusCode;
+use reqwest::{Method, StatusCode};
 use serde_json::{json, Value};
 use std::collections::HashSet;
 use test_context::test_context;
@@ -111,3 +111,180 @@ async fn update_page_from_other_namespace_should_be_denied(ctx: &mut TestApp) {
         .await;
     assert_eq!(StatusCode::NOT_FOUND, response.status());
 }
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn update_page_with_outdated_if_match_should_fail(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let response = ctx
+        .post(
+            "/page",
+            json!({
+                "path": "/original-path",
+                "title": "Original Title",
+                "description": "Original Description"
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    let original_etag = response
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string)
+        .expect("Expected ETag header");
+    let id = response
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|v| v.get("id").and_then(|v| v.as_i64()))
+        .expect("Expected ID");
+
+    let response = ctx
+        .req_with_headers(
+            Method::PUT,
+            format!("/page/{}", id),
+            Some(json!({
+                "path": "/first-update",
+                "title": "First update",
+                "description": null
+            })),
+            &[("If-Match", &original_etag)],
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    let current_etag = response
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string)
+        .expect("Expected ETag header");
+    assert_ne!(original_etag, current_etag);
+
+    let response = ctx
+        .req_with_headers(
+            Method::PUT,
+            format!("/page/{}", id),
+            Some(json!({
+                "path": "/concurrent-update",
+                "title": "Concurrent update",
+                "description": null
+            })),
+            &[("If-Match", &original_etag)],
+        )
+        .await;
+    assert_eq!(StatusCode::PRECONDITION_FAILED, response.status());
+    assert_eq!(
+        Some(current_etag.as_str()),
+        response.headers().get("etag").and_then(|v| v.to_str().ok())
+    );
+    let json = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize body");
+    assert_eq!(Some("PRCFL"), json.get("code").and_then(|v| v.as_str()));
+
+    let response = ctx
+        .req_with_headers(
+            Method::DELETE,
+            format!("/page/{}", id),
+            None as Option<()>,
+            &[("If-Match", &original_etag)],
+        )
+        .await;
+    assert_eq!(StatusCode::PRECONDITION_FAILED, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn update_page_with_if_match_from_get_should_work(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page = create_page(
+        ctx,
+        &json!({
+            "path": "/original-path",
+            "title": "Original Title",
+            "description": "Original Description"
+        }),
+    )
+    .await;
+    let id = page
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+
+    let response = ctx.get(format!("/page/{}", id)).await;
+    assert_eq!(StatusCode::OK, response.status());
+    let etag = response
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string)
+        .expect("Expected ETag header");
+    assert_eq!(
+        Some(page),
+        response
+            .json::<Value>()
+            .await
+            .ok()
+            .and_then(|v| v.as_object().cloned())
+    );
+
+    let response = ctx
+        .req_with_headers(
+            Method::PATCH,
+            format!("/page/{}", id),
+            Some(json!({ "title": "Updated Title" })),
+            &[("If-Match", &etag)],
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn concurrent_updates_with_same_if_match_should_let_one_through(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page = create_page(
+        ctx,
+        &json!({
+            "path": "/original-path",
+            "title": "Original Title",
+            "description": "Original Description"
+        }),
+    )
+    .await;
+    let id = page
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .expect("Expected ID");
+    let etag = ctx
+        .get(format!("/page/{}", id))
+        .await
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string)
+        .expect("Expected ETag header");
+    let headers = [("If-Match", etag.as_str())];
+
+    let update = |title: &'static str| {
+        ctx.req_with_headers(
+            Method::PATCH,
+            format!("/page/{}", id),
+            Some(json!({ "title": title })),
+            &headers,
+        )
+    };
+    let (first, second) = tokio::join!(update("First update"), update("Second update"));
+
+    let mut statuses = vec![first.status(), second.status()];
+    statuses.sort();
+    assert_eq!(
+        vec![StatusCode::OK, StatusCode::PRECONDITION_FAILED],
+        statuses
+    );
+}
@@ -54,3 +54,37 @@ async fn delete_post_from_other_namespace_should_be_denied(ctx: &mut TestApp) {
 
     assert_eq!(StatusCode::NOT_FOUND, response.status());
 }
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn deleted_post_should_be_restorable(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let post = create_post(
+        ctx,
+        &json!({
+          "title": "My first article",
+          "description": "Article description",
+          "slug": "first-article",
+          "body": {
+            "...": "..."
+          }
+        }),
+    )
+    .await;
+    let post_id = post.get("id").expect("Expected ID");
+
+    let response = ctx.delete(format!("/post/{post_id}")).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response = ctx.get("/post/s/first-article").await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+
+    let response = ctx
+        .post(format!("/post/{post_id}/restore"), json!({}))
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response = ctx.get("/post/s/first-article").await;
+    assert_eq!(StatusCode::OK, response.status());
+}
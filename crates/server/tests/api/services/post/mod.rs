@@ -1,5 +1,6 @@
-mod create;
+pub mod create;
 mod delete;
+mod patch;
 mod read;
 mod update;
 
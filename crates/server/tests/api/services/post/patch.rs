@@ -0,0 +1,89 @@
+use crate::{services::post::create_post, test_app::TestApp};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use test_context::test_context;
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn empty_patch_should_return_pthof_error(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let post = create_post(
+        ctx,
+        &json!({
+          "title": "My first article",
+          "description": "Article description",
+          "slug": "first-article",
+          "body": {}
+        }),
+    )
+    .await;
+    let post_id = post.get("id").expect("Expected ID");
+
+    let response = ctx.patch(format!("/post/{post_id}"), &json!({})).await;
+
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response_body = response
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|j| j.as_object().cloned())
+        .expect("Response body is not valid JSON Object");
+
+    assert_eq!(
+        Some(&Value::String("PTHOF".to_string())),
+        response_body.get("code")
+    );
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn one_field_patch_should_work(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let post = create_post(
+        ctx,
+        &json!({
+          "title": "My first article",
+          "description": "Article description",
+          "slug": "first-article",
+          "body": {
+            "...": "..."
+          }
+        }),
+    )
+    .await;
+    let post_id = post.get("id").expect("Expected ID");
+
+    let response = ctx
+        .patch(
+            format!("/post/{post_id}"),
+            &json!({
+              "body": {
+                "content": "Updated content"
+              }
+            }),
+        )
+        .await;
+
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response_body = response
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|j| j.as_object().cloned())
+        .expect("Response body is not valid JSON Object");
+
+    assert_eq!(post.get("id"), response_body.get("id"));
+    assert_eq!(post.get("title"), response_body.get("title"));
+    assert_eq!(post.get("description"), response_body.get("description"));
+    assert_eq!(post.get("slug"), response_body.get("slug"));
+    assert_eq!(
+        Some(&json!({ "content": "Updated content" })),
+        response_body.get("body")
+    );
+    assert_eq!(post.get("createdAt"), response_body.get("createdAt"));
+    assert_ne!(post.get("updatedAt"), response_body.get("updatedAt"));
+}
@@ -147,3 +147,96 @@ async fn get_one_post_from_another_namespace_should_be_denied(ctx: &mut TestApp)
 
     assert_eq!(Some("NTFND"), json.get("code").and_then(|v| v.as_str()));
 }
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn get_post_should_honor_if_modified_since(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let post = create_post(
+        ctx,
+        &json!({
+          "title": "My first article",
+          "description": "Article description",
+          "slug": "first-article",
+          "body": {}
+        }),
+    )
+    .await;
+    let uri = format!("/post/{}", post.get("id").expect("Expected ID"));
+
+    let response = ctx.get(&uri).await;
+    assert_eq!(StatusCode::OK, response.status());
+    let last_modified = response
+        .headers()
+        .get("last-modified")
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string)
+        .expect("Expected Last-Modified header");
+
+    let response = ctx
+        .get_with_headers(&uri, &[("If-Modified-Since", &last_modified)])
+        .await;
+    assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+
+    let response = ctx
+        .get_with_headers(
+            &uri,
+            &[("If-Modified-Since", "Thu, 01 Jan 2015 00:00:00 GMT")],
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn list_posts_should_change_when_a_post_is_removed(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let post = create_post(
+        ctx,
+        &json!({
+          "title": "My first article",
+          "description": "Article description",
+          "slug": "first-article",
+          "body": {}
+        }),
+    )
+    .await;
+    create_post(
+        ctx,
+        &json!({
+          "title": "My second article",
+          "description": "Article description",
+          "slug": "second-article",
+          "body": {}
+        }),
+    )
+    .await;
+
+    // Removing a post would not move the latest update of the list forward
+    let response = ctx.get("/post").await;
+    assert_eq!(StatusCode::OK, response.status());
+    assert!(response.headers().get("last-modified").is_none());
+
+    let response = ctx
+        .delete(format!("/post/{}", post.get("id").expect("Expected ID")))
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response = ctx
+        .get_with_headers(
+            "/post",
+            &[("If-Modified-Since", "Fri, 31 Dec 9999 23:59:59 GMT")],
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(
+        1,
+        response
+            .json::<Vec<Value>>()
+            .await
+            .expect("Failed to deserialize posts")
+            .len()
+    );
+}
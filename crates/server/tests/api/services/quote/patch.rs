@@ -0,0 +1,78 @@
+use crate::{services::quote::create::create_quote, test_app::TestApp};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use test_context::test_context;
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn null_patch_on_non_nullable_field_should_return_pthnn_error(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let quote = create_quote(
+        ctx,
+        &json!({
+            "author": "Léo Coletta",
+            "message": "Source blabla"
+        }),
+    )
+    .await;
+    let quote_id = quote.get("id").expect("Expected ID");
+
+    let response = ctx
+        .patch(format!("/quote/{quote_id}"), &json!({ "author": null }))
+        .await;
+
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    let response_body = response
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|j| j.as_object().cloned())
+        .expect("Response body is not valid JSON Object");
+
+    assert_eq!(
+        Some(&Value::String("PTHNN".to_string())),
+        response_body.get("code")
+    );
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn one_field_patch_should_work(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let quote = create_quote(
+        ctx,
+        &json!({
+            "author": "Léo Coletta",
+            "message": "Source blabla"
+        }),
+    )
+    .await;
+    let quote_id = quote.get("id").expect("Expected ID");
+
+    let response = ctx
+        .patch(
+            format!("/quote/{quote_id}"),
+            &json!({ "message": "Updated message" }),
+        )
+        .await;
+
+    assert_eq!(StatusCode::OK, response.status());
+
+    let response_body = response
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|j| j.as_object().cloned())
+        .expect("Response body is not valid JSON Object");
+
+    assert_eq!(quote.get("id"), response_body.get("id"));
+    assert_eq!(quote.get("author"), response_body.get("author"));
+    assert_eq!(
+        Some(&Value::String("Updated message".to_string())),
+        response_body.get("message")
+    );
+    assert_ne!(quote.get("updatedAt"), response_body.get("updatedAt"));
+}
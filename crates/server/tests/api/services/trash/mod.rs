@@ -0,0 +1,2 @@
+mod purge;
+mod read;
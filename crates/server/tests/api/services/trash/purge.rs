@@ -0,0 +1,89 @@
+use crate::{services::page::create::create_page, test_app::TestApp};
+use chrono::{Duration, Utc};
+use entity::file::FileVisibility;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use server::{
+    jobs::trash::purge_trash,
+    services::files::{
+        models::{FileInput, FilePayload},
+        repository::FilesRepository,
+    },
+};
+use std::collections::HashMap;
+use test_context::test_context;
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn purge_trash_should_only_remove_expired_resources(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page = create_page(
+        ctx,
+        &json!({
+            "path": "/path",
+            "title": "My Title",
+            "description": "My Description"
+        }),
+    )
+    .await;
+    let page_id = page.get("id").expect("Expected ID");
+
+    let file = ctx
+        .database_connection()
+        .create_file(
+            "namespace",
+            FileInput {
+                file: FilePayload {
+                    file_name: "test_file.txt".to_string(),
+                    content_type: Some("text/plain".to_string()),
+                    content_length: 100000,
+                    sha256: None,
+                },
+                metadata: HashMap::new(),
+                tags: vec![],
+                visibility: FileVisibility::Public,
+            },
+            180,
+        )
+        .await
+        .expect("Failed to create test file");
+    let file_id = file.id();
+
+    ctx.delete(format!("/page/{page_id}")).await;
+    ctx.delete(format!("/file/{file_id}")).await;
+
+    // Nothing has been trashed for long enough
+    let purged = purge_trash(
+        ctx.database_connection(),
+        ctx.storage().as_ref(),
+        ctx.settings().storage().s3().buckets(),
+        Utc::now() - Duration::days(1),
+    )
+    .await
+    .expect("Failed to purge trash");
+    assert_eq!(0, purged);
+
+    let purged = purge_trash(
+        ctx.database_connection(),
+        ctx.storage().as_ref(),
+        ctx.settings().storage().s3().buckets(),
+        Utc::now(),
+    )
+    .await
+    .expect("Failed to purge trash");
+    assert_eq!(2, purged);
+
+    let items = ctx
+        .get("/trash")
+        .await
+        .json::<Vec<Value>>()
+        .await
+        .expect("Response body is not valid JSON");
+    assert!(items.is_empty());
+
+    let response = ctx
+        .post(format!("/page/{page_id}/restore"), json!({}))
+        .await;
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+}
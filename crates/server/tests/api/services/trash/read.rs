@@ -0,0 +1,122 @@
+use crate::{
+    services::{page::create::create_page, post::create_post},
+    test_app::TestApp,
+};
+use entity::image::ImageStatus;
+use reqwest::StatusCode;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set};
+use serde_json::{json, Value};
+use test_context::test_context;
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn list_trash_should_return_deleted_resources(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    let page = create_page(
+        ctx,
+        &json!({
+            "path": "/path",
+            "title": "My Title",
+            "description": "My Description"
+        }),
+    )
+    .await;
+    let page_id = page.get("id").expect("Expected ID");
+
+    let post = create_post(
+        ctx,
+        &json!({
+          "title": "My first article",
+          "description": "Article description",
+          "slug": "first-article",
+          "body": {}
+        }),
+    )
+    .await;
+    let post_id = post.get("id").expect("Expected ID");
+
+    // Only trashed resources are listed
+    create_page(
+        ctx,
+        &json!({
+            "path": "/other-path",
+            "title": "My Title",
+            "description": "My Description"
+        }),
+    )
+    .await;
+
+    ctx.delete(format!("/page/{page_id}")).await;
+    ctx.delete(format!("/post/{post_id}")).await;
+
+    let response = ctx.get("/trash").await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let items = response
+        .json::<Vec<Value>>()
+        .await
+        .expect("Response body is not valid JSON");
+
+    assert_eq!(2, items.len());
+
+    // Most recently deleted first
+    assert_eq!(Some(&json!("post")), items[0].get("kind"));
+    assert_eq!(Some(post_id), items[0].get("id"));
+    assert_eq!(Some(&json!("My first article")), items[0].get("label"));
+    assert!(items[0].get("deletedAt").is_some());
+    assert!(items[0].get("purgeAt").is_some());
+
+    assert_eq!(Some(&json!("page")), items[1].get("kind"));
+    assert_eq!(Some(page_id), items[1].get("id"));
+    assert_eq!(Some(&json!("/path")), items[1].get("label"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn list_trash_with_read_only_key_should_be_denied(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", true).await;
+
+    let response = ctx.get("/trash").await;
+    assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn list_trash_should_return_images_still_processing(ctx: &mut TestApp) {
+    ctx.create_api_key("namespace", false).await;
+
+    // Images processed for the first time have no lazy image yet, no job is
+    // queued so that it stays processing during the test
+    let image = entity::image::ActiveModel {
+        namespace: Set("namespace".to_string()),
+        storage_key: Set("processing.jpg".to_string()),
+        original_storage_key: Set(Some("processing.jpg".to_string())),
+        status: Set(ImageStatus::Processing),
+        tags: Set(vec![]),
+        metadata: Set(json!({})),
+        formats: Set(vec![]),
+        ..Default::default()
+    }
+    .insert(ctx.database_connection())
+    .await
+    .expect("Failed to insert image");
+
+    let response = ctx.delete(format!("/image/{}", image.id)).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let items = ctx
+        .get("/trash")
+        .await
+        .json::<Vec<Value>>()
+        .await
+        .expect("Response body is not valid JSON");
+    assert_eq!(1, items.len());
+    assert_eq!(Some(&json!("image")), items[0].get("kind"));
+    assert_eq!(Some(&json!(image.id)), items[0].get("id"));
+
+    let response = ctx
+        .post(format!("/image/{}/restore", image.id), json!({}))
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+}
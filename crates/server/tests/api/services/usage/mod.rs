@@ -0,0 +1,2 @@
+mod quota;
+mod read;
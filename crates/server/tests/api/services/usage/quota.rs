@@ -0,0 +1,145 @@
+use crate::{
+    services::image::create::{upload_image, wait_for_image_id},
+    test_app::TestApp,
+};
+use entity::image_job;
+use reqwest::StatusCode;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde_json::{json, Value};
+use test_context::test_context;
+
+async fn create_file(ctx: &TestApp, content_length: u64) -> reqwest::Response {
+    ctx.post(
+        "/file",
+        json!({
+            "file": {
+                "contentType": "application/pdf",
+                "contentLength": content_length,
+                "fileName": "file.pdf"
+            },
+            "tags": [],
+            "metadata": {}
+        }),
+    )
+    .await
+}
+
+async fn assert_quota_exceeded(response: reqwest::Response) {
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, response.status());
+    let body = response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize error");
+    assert_eq!(Some(&json!("QTEXC")), body.get("code"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn uploads_should_not_exceed_namespace_quota(ctx: &mut TestApp) {
+    ctx.create_api_key("small_quota", false).await;
+
+    let response = create_file(ctx, 1500).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    // The pending file counts towards the quota
+    assert_quota_exceeded(create_file(ctx, 1000).await).await;
+    assert_quota_exceeded(
+        ctx.post(
+            "/file/multipart",
+            json!({
+                "file": {
+                    "contentLength": 1000,
+                    "fileName": "file.pdf"
+                },
+                "tags": [],
+                "metadata": {}
+            }),
+        )
+        .await,
+    )
+    .await;
+    assert_quota_exceeded(
+        upload_image(
+            ctx,
+            "tests/fixtures/img/gray_400x400.jpg",
+            "quota.jpg",
+            mime::IMAGE_JPEG.as_ref(),
+            "",
+        )
+        .await,
+    )
+    .await;
+
+    let response = create_file(ctx, 500).await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let usage = ctx
+        .get("/usage")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize usage");
+    assert_eq!(Some(&json!(2000)), usage.get("quota"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn image_renditions_should_not_exceed_namespace_quota(ctx: &mut TestApp) {
+    ctx.create_api_key("small_quota", false).await;
+
+    // The original fits in the quota, not along with its renditions
+    let original_size = std::fs::metadata("tests/fixtures/img/gray_400x400.jpg")
+        .expect("Failed to read image fixture")
+        .len();
+    let response = upload_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "quota.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        "",
+    )
+    .await;
+    assert_eq!(StatusCode::ACCEPTED, response.status());
+    let id = response
+        .json::<Value>()
+        .await
+        .ok()
+        .and_then(|v| v.get("id").and_then(|v| v.as_i64()))
+        .expect("Expected ID");
+
+    let image = wait_for_image_id(ctx, id)
+        .await
+        .json::<Value>()
+        .await
+        .expect("Cannot parse image output");
+    assert_eq!(Some(&json!("failed")), image.get("status"));
+
+    let job = image_job::Entity::find()
+        .filter(image_job::Column::ImageId.eq(id as i32))
+        .one(ctx.database_connection())
+        .await
+        .expect("Failed to find image job")
+        .expect("Expected an image job");
+    // Exceeding the quota is not retried
+    assert_eq!(1, job.attempts);
+    assert!(job.failed_at.is_some());
+    assert!(job
+        .last_error
+        .is_some_and(|error| error.starts_with("QuotaExceeded")));
+
+    // Only the original is kept
+    let usage = ctx
+        .get("/usage")
+        .await
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize usage");
+    assert_eq!(Some(&json!(original_size)), usage.get("bytes"));
+    let objects = ctx
+        .storage()
+        .list_objects(ctx.settings().storage().s3().buckets().image(), None)
+        .await
+        .expect("Failed to list objects")
+        .objects;
+    assert_eq!(1, objects.len());
+}
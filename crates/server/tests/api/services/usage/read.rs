@@ -0,0 +1,123 @@
+use crate::{services::image::create::create_image, test_app::TestApp};
+use entity::image;
+use reqwest::StatusCode;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde_json::{json, Value};
+use test_context::test_context;
+
+async fn get_usage(ctx: &TestApp) -> Value {
+    let response = ctx.get("/usage").await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    response
+        .json::<Value>()
+        .await
+        .expect("Failed to deserialize usage")
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn usage_should_count_files_and_images(ctx: &mut TestApp) {
+    ctx.create_api_key("test_usage", false).await;
+
+    let usage = get_usage(ctx).await;
+    assert_eq!(Some(&json!("test_usage")), usage.get("namespace"));
+    assert_eq!(Some(&json!(0)), usage.get("bytes"));
+    assert_eq!(Some(&Value::Null), usage.get("quota"));
+
+    let response = ctx
+        .post(
+            "/file",
+            json!({
+                "file": {
+                    "contentType": "application/pdf",
+                    "contentLength": 1500,
+                    "fileName": "file.pdf"
+                },
+                "tags": [],
+                "metadata": {}
+            }),
+        )
+        .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    let usage = get_usage(ctx).await;
+    let types = usage.get("types").expect("Expected usage per type");
+    assert_eq!(
+        Some(&json!({ "bytes": 1500, "objects": 1 })),
+        types.get("pendingFiles")
+    );
+    assert_eq!(
+        Some(&json!({ "bytes": 0, "objects": 0 })),
+        types.get("files")
+    );
+    // Pending files are not stored yet
+    assert_eq!(Some(&json!(0)), usage.get("bytes"));
+
+    let response = create_image(
+        ctx,
+        "tests/fixtures/img/gray_400x400.jpg",
+        "usage.jpg",
+        mime::IMAGE_JPEG.as_ref(),
+        None,
+    )
+    .await;
+    assert_eq!(StatusCode::OK, response.status());
+
+    // Sizes of the renditions are recorded as stored
+    let images = image::Entity::find()
+        .filter(image::Column::Namespace.eq("test_usage"))
+        .all(ctx.database_connection())
+        .await
+        .expect("Failed to find images");
+    let mut stored_bytes = 0;
+    for image in &images {
+        let object = ctx
+            .storage()
+            .head_object(
+                ctx.settings().storage().s3().buckets().image(),
+                &image.storage_key,
+            )
+            .await
+            .expect("Expected image object");
+        assert_eq!(Some(object.content_length), image.size);
+        stored_bytes += object.content_length + image.original_size.unwrap_or_default();
+    }
+
+    let usage = get_usage(ctx).await;
+    let types = usage.get("types").expect("Expected usage per type");
+    // The main image, its original and its lazy image
+    assert_eq!(
+        Some(&json!({ "bytes": stored_bytes, "objects": 3 })),
+        types.get("images")
+    );
+    assert_eq!(Some(&json!(stored_bytes)), usage.get("bytes"));
+    assert_eq!(Some(&json!(3)), usage.get("objects"));
+}
+
+#[test_context(TestApp)]
+#[tokio::test]
+async fn usage_should_be_scoped_to_namespace(ctx: &mut TestApp) {
+    ctx.create_api_key("other_namespace", false).await;
+    ctx.post(
+        "/file",
+        json!({
+            "file": {
+                "contentLength": 1500,
+                "fileName": "file.pdf"
+            },
+            "tags": [],
+            "metadata": {}
+        }),
+    )
+    .await;
+
+    ctx.create_api_key("test_usage", true).await;
+    let usage = get_usage(ctx).await;
+    assert_eq!(
+        Some(&json!({ "bytes": 0, "objects": 0 })),
+        usage
+            .get("types")
+            .and_then(|types| types.get("pendingFiles"))
+    );
+}
@@ -12,11 +12,18 @@ use sea_orm::{ActiveModelTrait, ActiveValue, ConnectionTrait, DatabaseConnection
 use serde::Serialize;
 use serde_json::json;
 use server::{
-    config::{LogFormat, S3Buckets, S3Config, S3Credentials, Settings},
+    config::{
+        CacheControlSettings, FileUploadSettings, ImageProcessingSettings, ImageUploadSettings,
+        JobsSettings, LocalStorageSettings, LogFormat, NamespaceFileUploadSettings,
+        NamespaceQuotaSettings, ObjectCleanupSettings, PendingFileSettings, QuotaSettings,
+        S3Buckets, S3Config, S3Credentials, Settings, StorageDriver, StorageSettings,
+        TrashSettings, UploadSettings,
+    },
     server::Server,
+    storage::{self, StorageExt},
     telemetry::{get_subscriber, init_subscriber},
 };
-use std::env;
+use std::{collections::HashMap, env};
 use test_context::AsyncTestContext;
 use url::Url;
 use uuid::Uuid;
@@ -61,6 +68,8 @@ pub struct TestApp {
     database_connection: DatabaseConnection,
     #[allow(unused)]
     s3_client: aws_sdk_s3::Client,
+    #[allow(unused)]
+    storage: StorageExt,
     settings: Settings,
     http_client: reqwest::Client,
     #[getset(set = "pub")]
@@ -73,12 +82,22 @@ impl TestApp {
         method: Method,
         uri: U,
         body: Option<T>,
+    ) -> reqwest::Response {
+        self.req_with_headers(method, uri, body, &[]).await
+    }
+
+    pub async fn req_with_headers<U: AsRef<str>, T: Serialize>(
+        &self,
+        method: Method,
+        uri: U,
+        body: Option<T>,
+        headers: &[(&str, &str)],
     ) -> reqwest::Response {
         let mut request = self
             .http_client
             .request(
                 method,
-                &format!("http://{}/api{}", self.address(), uri.as_ref()),
+                format!("http://{}/api{}", self.address(), uri.as_ref()),
             )
             .header("Content-Type", "application/json");
 
@@ -86,6 +105,10 @@ impl TestApp {
             request = request.header("X-Api-Key", api_key);
         }
 
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
         if let Some(payload) = &body {
             request = request.json(payload);
         }
@@ -100,14 +123,14 @@ impl TestApp {
     ) -> reqwest::Response {
         let mut request = self.http_client.request(
             method,
-            &format!("http://{}/api{}", self.address(), uri.as_ref()),
+            format!("http://{}/api{}", self.address(), uri.as_ref()),
         );
 
         if let Some(api_key) = &self.active_api_key {
             request = request.header("X-Api-Key", api_key);
         }
 
-        request = request.multipart(body.into());
+        request = request.multipart(body);
 
         request.send().await.expect("Failed to execute request")
     }
@@ -116,6 +139,15 @@ impl TestApp {
         self.req(Method::GET, uri, None as Option<()>).await
     }
 
+    pub async fn get_with_headers<S: AsRef<str>>(
+        &self,
+        uri: S,
+        headers: &[(&str, &str)],
+    ) -> reqwest::Response {
+        self.req_with_headers(Method::GET, uri, None as Option<()>, headers)
+            .await
+    }
+
     pub async fn post<T: Serialize, S: AsRef<str>>(&self, uri: S, body: T) -> reqwest::Response {
         self.req(Method::POST, uri, Some(body)).await
     }
@@ -173,7 +205,14 @@ impl TestApp {
         .await
         .expect("Failed to drop database");
 
-        utils::wipe_bucket(&self.s3_client, self.settings().s3().buckets().image()).await;
+        utils::wipe_bucket(
+            &self.s3_client,
+            self.settings().storage().s3().buckets().image(),
+        )
+        .await;
+        if let Some(local) = self.settings().storage().local() {
+            std::fs::remove_dir_all(local.root()).ok();
+        }
 
         release_port(self.port);
     }
@@ -214,7 +253,7 @@ async fn configure_database(settings: &Settings) -> DatabaseConnection {
 
 async fn configure_s3(settings: &Settings) -> Client {
     let client = Client::from_conf(settings.clone().into());
-    let bucket: &String = settings.s3().buckets().image();
+    let bucket: &String = settings.storage().s3().buckets().image();
 
     client
         .create_bucket()
@@ -258,6 +297,10 @@ async fn configure_s3(settings: &Settings) -> Client {
 }
 
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with_storage(StorageDriver::S3).await
+}
+
+pub async fn spawn_app_with_storage(storage_driver: StorageDriver) -> TestApp {
     SyncLazy::force(&TRACING);
 
     let test_db_name = Uuid::new_v4().to_string();
@@ -267,36 +310,84 @@ pub async fn spawn_app() -> TestApp {
             .expect("No database url specified")
             .parse()
             .expect("Invalid database url (cannot parse)");
-        url.set_path(&*format!("/{}", test_db_name));
+        url.set_path(&format!("/{}", test_db_name));
         url.to_string()
     };
 
     let port = pick_unused_port();
     let s3_bucket = format!("test{}", Uuid::new_v4().to_string().replace('-', ""));
-    let settings = Settings::new(
-        String::from("test"),
-        String::from("0.0.0.0"),
-        port.to_string(),
-        database_url,
-        false,
+    let local_storage = match storage_driver {
+        StorageDriver::S3 => None,
+        StorageDriver::Local => Some(LocalStorageSettings::new(
+            env::temp_dir()
+                .join(format!("lyonkit-{test_db_name}"))
+                .to_string_lossy()
+                .to_string(),
+            format!("http://127.0.0.1:{port}"),
+            Uuid::new_v4().to_string(),
+        )),
+    };
+    let storage_settings = StorageSettings::new(
+        storage_driver,
         S3Config::new(
-            env::var("S3__ENDPOINT").expect("No S3 endpoint specified"),
-            env::var("S3__BASE_URL").expect("No S3 base url specified"),
-            env::var("S3__REGION").expect("No S3 region specified"),
+            env::var("STORAGE__S3__ENDPOINT").expect("No S3 endpoint specified"),
+            env::var("STORAGE__S3__BASE_URL").expect("No S3 base url specified"),
+            env::var("STORAGE__S3__REGION").expect("No S3 region specified"),
             S3Credentials::new(
-                env::var("S3__CREDENTIALS__ACCESS_KEY_ID")
+                env::var("STORAGE__S3__CREDENTIALS__ACCESS_KEY_ID")
                     .expect("No S3 credentials access key specified"),
-                env::var("S3__CREDENTIALS__SECRET_ACCESS_KEY")
+                env::var("STORAGE__S3__CREDENTIALS__SECRET_ACCESS_KEY")
                     .expect("No S3 credentials secret key specified"),
             ),
             S3Buckets::new(s3_bucket.clone(), s3_bucket),
         ),
+        local_storage,
+        QuotaSettings::new(
+            None,
+            HashMap::from([(
+                "small_quota".to_string(),
+                NamespaceQuotaSettings::new(Some(2000)),
+            )]),
+        ),
+        UploadSettings::new(
+            ImageUploadSettings::new(1_000_000, 4096, 4096),
+            FileUploadSettings::new(
+                50_000_000,
+                180,
+                300,
+                2,
+                HashMap::from([(
+                    "small_files".to_string(),
+                    NamespaceFileUploadSettings::new(Some(1000), Some(60), Some(60)),
+                )]),
+            ),
+        ),
+    );
+    let settings = Settings::new(
+        String::from("test"),
+        String::from("0.0.0.0"),
+        port.to_string(),
+        database_url,
+        false,
+        storage_settings,
         Vec::new(),
         LogFormat::Json,
+        CacheControlSettings::new(
+            String::from("no-cache"),
+            String::from("no-cache"),
+            String::from("no-cache"),
+        ),
+        JobsSettings::new(
+            TrashSettings::new(30, 3600),
+            ObjectCleanupSettings::new(300, 86400, 3600),
+            ImageProcessingSettings::new(1, 4, 2, 1, 600),
+            PendingFileSettings::new(600),
+        ),
     );
 
     let database_connection = configure_database(&settings).await;
     let s3_client = configure_s3(&settings).await;
+    let storage = storage::from_settings(&settings);
 
     let server = Server::from_settings(&settings)
         .await
@@ -319,6 +410,7 @@ pub async fn spawn_app() -> TestApp {
         port,
         database_connection,
         s3_client,
+        storage,
         settings,
         active_api_key: None,
     }